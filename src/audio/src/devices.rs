@@ -0,0 +1,165 @@
+use anyhow::{Context, Result};
+use cpal::{
+    Device, DevicesError, Host, SupportedStreamConfigRange, SupportedStreamConfigsError,
+    traits::{DeviceTrait, HostTrait},
+};
+use tracing::{debug, warn};
+
+// the devices of one audio host, e.g. ALSA or JACK
+#[derive(Debug, Clone)]
+pub struct HostDevices {
+    pub host: String,
+    pub default: bool,
+    pub outputs: Vec<DeviceInfo>,
+    pub inputs: Vec<DeviceInfo>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DeviceInfo {
+    pub name: String,
+    // the default device of its host
+    pub default: bool,
+    // the stream configurations it supports, e.g. "2 channels, 44100-48000 Hz, f32"
+    pub configs: Vec<String>,
+}
+
+// every host that is available on this system, the default one first
+pub fn list_devices() -> Vec<HostDevices> {
+    let default_host = cpal::default_host().id();
+    hosts()
+        .iter()
+        .map(|host| {
+            let default_output = host
+                .default_output_device()
+                .and_then(|device| device.name().ok());
+            let default_input = host
+                .default_input_device()
+                .and_then(|device| device.name().ok());
+            HostDevices {
+                host: host.id().name().to_string(),
+                default: host.id() == default_host,
+                outputs: devices(host, host.output_devices())
+                    .iter()
+                    .filter_map(|device| {
+                        describe(
+                            device,
+                            default_output.as_deref(),
+                            device.supported_output_configs(),
+                        )
+                    })
+                    .collect(),
+                inputs: devices(host, host.input_devices())
+                    .iter()
+                    .filter_map(|device| {
+                        describe(
+                            device,
+                            default_input.as_deref(),
+                            device.supported_input_configs(),
+                        )
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+// the named device of the first host that has one, or the system default if
+// there is no name
+pub(crate) fn output_device(name: Option<&str>) -> Result<Device> {
+    let Some(name) = name else {
+        return cpal::default_host()
+            .default_output_device()
+            .context("Couldn't obtain default output device");
+    };
+    hosts()
+        .iter()
+        .find_map(|host| named(devices(host, host.output_devices()), name))
+        .with_context(|| format!("There is no output device called '{name}'"))
+}
+
+pub(crate) fn input_device(name: Option<&str>) -> Result<Device> {
+    let Some(name) = name else {
+        return cpal::default_host()
+            .default_input_device()
+            .context("Couldn't obtain default input device");
+    };
+    hosts()
+        .iter()
+        .find_map(|host| named(devices(host, host.input_devices()), name))
+        .with_context(|| format!("There is no input device called '{name}'"))
+}
+
+fn hosts() -> Vec<Host> {
+    let default = cpal::default_host();
+    let default_id = default.id();
+    let others = cpal::available_hosts()
+        .into_iter()
+        .filter(|id| *id != default_id)
+        .filter_map(|id| match cpal::host_from_id(id) {
+            Ok(host) => Some(host),
+            Err(err) => {
+                debug!(?err, host = id.name(), "Audio host is unavailable");
+                None
+            }
+        });
+    std::iter::once(default).chain(others).collect()
+}
+
+// a host that can't list its devices has none
+fn devices<D>(host: &Host, devices: Result<D, DevicesError>) -> Vec<Device>
+where
+    D: Iterator<Item = Device>,
+{
+    match devices {
+        Ok(devices) => devices.collect(),
+        Err(err) => {
+            warn!(
+                ?err,
+                host = host.id().name(),
+                "Failed to list audio devices"
+            );
+            Vec::new()
+        }
+    }
+}
+
+fn named(devices: Vec<Device>, name: &str) -> Option<Device> {
+    devices
+        .into_iter()
+        .find(|device| device.name().is_ok_and(|known| known == name))
+}
+
+fn describe<C>(
+    device: &Device,
+    default: Option<&str>,
+    configs: Result<C, SupportedStreamConfigsError>,
+) -> Option<DeviceInfo>
+where
+    C: Iterator<Item = SupportedStreamConfigRange>,
+{
+    let name = device.name().ok()?;
+    // a device that is busy can't tell what it supports
+    let configs = match configs {
+        Ok(configs) => configs.map(|config| describe_config(&config)).collect(),
+        Err(err) => vec![format!("unknown configurations: {err}")],
+    };
+    Some(DeviceInfo {
+        default: default == Some(name.as_str()),
+        name,
+        configs,
+    })
+}
+
+fn describe_config(config: &SupportedStreamConfigRange) -> String {
+    let (min, max) = (config.min_sample_rate().0, config.max_sample_rate().0);
+    let rates = if min == max {
+        format!("{min} Hz")
+    } else {
+        format!("{min}-{max} Hz")
+    };
+    format!(
+        "{} channels, {rates}, {}",
+        config.channels(),
+        config.sample_format()
+    )
+}
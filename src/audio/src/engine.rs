@@ -0,0 +1,471 @@
+use core::interfaces::audio::AudioOutputMessage;
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{Receiver, RecvTimeoutError, Sender, channel},
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::{Context, Result, anyhow};
+use cpal::{
+    Device, FromSample, I24, SampleFormat, SizedSample, Stream, StreamConfig, StreamError,
+    traits::{DeviceTrait, StreamTrait},
+};
+use rtrb::{Consumer, Producer, RingBuffer};
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::error;
+
+use crate::waveform::Waveform;
+
+// phrases that can wait for the stream, more than that means the stream is
+// stuck
+const QUEUE_CAPACITY: usize = 16;
+// every phrase that is waiting or playing can be reported started and
+// finished
+const PLAYED_CAPACITY: usize = 2 * (QUEUE_CAPACITY + 1);
+// how often the reports of the stream are passed on to the core
+const FORWARD_INTERVAL: Duration = Duration::from_millis(5);
+
+// keeps one output stream running on its own thread, cpal streams can't be
+// moved between threads. phrases are handed to the stream through a lock-free
+// queue and come back through another one once they are played, so the
+// stream neither waits, allocates nor frees. the thread frees them and tells
+// the core. dropping the engine closes the stream
+pub(crate) struct Engine {
+    queue: Producer<Waveform>,
+    // set by the stream when its device goes away, e.g. is unplugged
+    lost: Arc<AtomicBool>,
+    // only there to be dropped, the thread ends when it is
+    _stop: Sender<()>,
+}
+
+impl Engine {
+    pub fn start(
+        output: Device,
+        stream_config: StreamConfig,
+        output_format: SampleFormat,
+        events: UnboundedSender<AudioOutputMessage>,
+    ) -> Result<Self> {
+        let (queue, consumer) = RingBuffer::new(QUEUE_CAPACITY);
+        let (played, reports) = RingBuffer::new(PLAYED_CAPACITY);
+        let (stop, stopped) = channel();
+        let (opened, opening) = channel();
+        let lost = Arc::new(AtomicBool::new(false));
+        let watch = Watch { lost: lost.clone() };
+        let player = Player::new(consumer, played, stream_config.channels as usize);
+        let forwarder = Forwarder {
+            reports,
+            lost: lost.clone(),
+            events,
+        };
+
+        thread::Builder::new()
+            .name("audio output".to_string())
+            .spawn(move || {
+                match open_stream(&output, &stream_config, output_format, player, watch) {
+                    Ok(stream) => {
+                        opened.send(Ok(())).ok();
+                        forwarder.run(stopped);
+                        drop(stream);
+                    }
+                    Err(err) => {
+                        opened.send(Err(err)).ok();
+                    }
+                }
+            })
+            .context("Couldn't start the audio output thread")?;
+        opening
+            .recv()
+            .map_err(|_| anyhow!("The audio output thread ended before the stream was opened"))??;
+
+        Ok(Self {
+            queue,
+            lost,
+            _stop: stop,
+        })
+    }
+
+    pub fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::Relaxed)
+    }
+
+    pub fn play(&mut self, waveform: Waveform) {
+        if self.queue.push(waveform).is_err() {
+            error!("Dropping phrase because the audio output is not keeping up");
+        }
+    }
+}
+
+// what the stream reports back, finished phrases bring their waveform along
+enum Played {
+    Started,
+    Finished(Waveform),
+}
+
+// passes the reports of the stream on to the core until the engine is dropped
+struct Forwarder {
+    reports: Consumer<Played>,
+    lost: Arc<AtomicBool>,
+    events: UnboundedSender<AudioOutputMessage>,
+}
+
+impl Forwarder {
+    fn run(mut self, stopped: Receiver<()>) {
+        let mut lost = false;
+        loop {
+            self.forward();
+            if !lost && self.lost.load(Ordering::Relaxed) {
+                lost = true;
+                self.send(AudioOutputMessage::DeviceLost);
+            }
+            // nothing is ever sent, the engine only drops the sender
+            if let Err(RecvTimeoutError::Disconnected) = stopped.recv_timeout(FORWARD_INTERVAL) {
+                break;
+            }
+        }
+    }
+
+    fn forward(&mut self) {
+        while let Ok(played) = self.reports.pop() {
+            match played {
+                Played::Started => self.send(AudioOutputMessage::PhraseStarted),
+                Played::Finished(waveform) => {
+                    // freed here rather than in the stream
+                    drop(waveform);
+                    self.send(AudioOutputMessage::PhraseFinished);
+                }
+            }
+        }
+    }
+
+    fn send(&self, message: AudioOutputMessage) {
+        // the core is gone when the program ends
+        self.events.send(message).ok();
+    }
+}
+
+fn open_stream(
+    output: &Device,
+    stream_config: &StreamConfig,
+    output_format: SampleFormat,
+    player: Player,
+    watch: Watch,
+) -> Result<Stream> {
+    match output_format {
+        SampleFormat::I8 => build_stream::<i8>(output, stream_config, player, watch),
+        SampleFormat::I16 => build_stream::<i16>(output, stream_config, player, watch),
+        SampleFormat::I24 => build_stream::<I24>(output, stream_config, player, watch),
+        SampleFormat::I32 => build_stream::<i32>(output, stream_config, player, watch),
+        SampleFormat::I64 => build_stream::<i64>(output, stream_config, player, watch),
+        SampleFormat::U8 => build_stream::<u8>(output, stream_config, player, watch),
+        SampleFormat::U16 => build_stream::<u16>(output, stream_config, player, watch),
+        SampleFormat::U32 => build_stream::<u32>(output, stream_config, player, watch),
+        SampleFormat::U64 => build_stream::<u64>(output, stream_config, player, watch),
+        SampleFormat::F32 => build_stream::<f32>(output, stream_config, player, watch),
+        SampleFormat::F64 => build_stream::<f64>(output, stream_config, player, watch),
+        format => Err(anyhow!(
+            "The output sample format {format} is not supported"
+        )),
+    }
+}
+
+fn build_stream<T>(
+    output: &Device,
+    stream_config: &StreamConfig,
+    mut player: Player,
+    watch: Watch,
+) -> Result<Stream>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let stream = output.build_output_stream(
+        stream_config,
+        move |data: &mut [T], _: &cpal::OutputCallbackInfo| player.fill(data),
+        move |err| watch.error(err),
+        None,
+    )?;
+    stream.play()?;
+    Ok(stream)
+}
+
+// marks the device as lost, the forwarder reports it. other stream errors
+// are only logged
+struct Watch {
+    lost: Arc<AtomicBool>,
+}
+
+impl Watch {
+    fn error(&self, err: StreamError) {
+        match err {
+            StreamError::DeviceNotAvailable => {
+                if !self.lost.swap(true, Ordering::Relaxed) {
+                    error!("The output device is no longer available");
+                }
+            }
+            err => error!("an error occured on stream: {err}"),
+        }
+    }
+}
+
+// which side of the phrase a channel plays
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Speaker {
+    Left,
+    Right,
+    Center,
+    Silent,
+}
+
+// cpal does not tell where the speakers stand, so the channels are taken to
+// be in the usual WAVE and ALSA order: front left and right, center, LFE,
+// then the rear and side pairs. the rear and side speakers play the front
+// sides again and the subwoofer stays silent
+fn speakers(channels: usize) -> Vec<Speaker> {
+    use Speaker::{Center, Left, Right, Silent};
+    match channels {
+        0 => Vec::new(),
+        1 => vec![Center],
+        3 => vec![Left, Right, Center],
+        5 => vec![Left, Right, Center, Left, Right],
+        6 => vec![Left, Right, Center, Silent, Left, Right],
+        7 => vec![Left, Right, Center, Silent, Center, Left, Right],
+        channels if channels >= 8 => [Left, Right, Center, Silent]
+            .into_iter()
+            .chain([Left, Right].into_iter().cycle())
+            .take(channels)
+            .collect(),
+        // stereo and quad
+        channels => [Left, Right].into_iter().cycle().take(channels).collect(),
+    }
+}
+
+// runs inside the stream callback, so it must not block, allocate or free
+struct Player {
+    queue: Consumer<Waveform>,
+    played: Producer<Played>,
+    current: Option<Waveform>,
+    position: usize,
+    speakers: Vec<Speaker>,
+}
+
+impl Player {
+    fn new(queue: Consumer<Waveform>, played: Producer<Played>, channels: usize) -> Self {
+        Self {
+            queue,
+            played,
+            current: None,
+            position: 0,
+            speakers: speakers(channels),
+        }
+    }
+
+    fn fill<T>(&mut self, data: &mut [T])
+    where
+        T: SizedSample + FromSample<f32>,
+    {
+        // the newest phrase cuts the one that is playing. it waits while the
+        // forwarder is behind, the finished phrase has to be handed back
+        while self.played.slots() >= 2 {
+            let Ok(waveform) = self.queue.pop() else {
+                break;
+            };
+            self.finish();
+            self.current = Some(waveform);
+            self.position = 0;
+            self.report(Played::Started);
+        }
+
+        for frame in data.chunks_mut(self.speakers.len().max(1)) {
+            let samples = self
+                .current
+                .as_ref()
+                .and_then(|waveform| waveform.frame(self.position));
+            let Some((left, right)) = samples else {
+                frame.fill(T::EQUILIBRIUM);
+                continue;
+            };
+            self.position += 1;
+            for (sample, speaker) in frame.iter_mut().zip(&self.speakers) {
+                *sample = T::from_sample(match speaker {
+                    Speaker::Left => left,
+                    Speaker::Right => right,
+                    Speaker::Center => (left + right) / 2.0,
+                    Speaker::Silent => 0.0,
+                });
+            }
+        }
+
+        if self
+            .current
+            .as_ref()
+            .is_some_and(|waveform| self.position >= waveform.len())
+        {
+            self.finish();
+        }
+    }
+
+    // a phrase that can't be handed back yet stays silent until it can
+    fn finish(&mut self) {
+        if self.played.slots() > 0
+            && let Some(waveform) = self.current.take()
+        {
+            self.report(Played::Finished(waveform));
+        }
+    }
+
+    fn report(&mut self, played: Played) {
+        // there is always a slot, the callers check
+        if self.played.push(played).is_err() {
+            unreachable!("the report queue is full");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::unbounded_channel;
+
+    use super::*;
+
+    fn waveform(frames: usize) -> Waveform {
+        Waveform {
+            left: vec![0.5; frames],
+            right: vec![-0.5; frames],
+        }
+    }
+
+    fn player(reports: usize) -> (Producer<Waveform>, Player, Consumer<Played>) {
+        let (queue, consumer) = RingBuffer::new(QUEUE_CAPACITY);
+        let (played, reports) = RingBuffer::new(reports);
+        (queue, Player::new(consumer, played, 2), reports)
+    }
+
+    // the reports with the length of the finished waveforms
+    fn drain(reports: &mut Consumer<Played>) -> Vec<Option<usize>> {
+        std::iter::from_fn(|| reports.pop().ok())
+            .map(|played| match played {
+                Played::Started => None,
+                Played::Finished(waveform) => Some(waveform.len()),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn finished_phrases_are_handed_back() {
+        let (mut queue, mut player, mut reports) = player(PLAYED_CAPACITY);
+        queue.push(waveform(3)).ok();
+        let mut data = [1.0_f32; 8];
+        player.fill(&mut data);
+        assert_eq!(data, [0.5, -0.5, 0.5, -0.5, 0.5, -0.5, 0.0, 0.0]);
+        assert_eq!(drain(&mut reports), [None, Some(3)]);
+
+        player.fill(&mut data);
+        assert_eq!(data, [0.0; 8]);
+        assert!(drain(&mut reports).is_empty());
+    }
+
+    #[test]
+    fn newer_phrases_cut_the_one_that_plays() {
+        let (mut queue, mut player, mut reports) = player(PLAYED_CAPACITY);
+        queue.push(waveform(10)).ok();
+        let mut data = [0.0_f32; 4];
+        player.fill(&mut data);
+        queue.push(waveform(20)).ok();
+        queue.push(waveform(3)).ok();
+        player.fill(&mut data);
+        assert_eq!(drain(&mut reports), [None, Some(10), None, Some(20), None]);
+        assert_eq!(player.position, 2);
+    }
+
+    #[test]
+    fn phrases_wait_while_the_reports_are_not_taken() {
+        let (mut queue, mut player, mut reports) = player(2);
+        queue.push(waveform(3)).ok();
+        let mut data = [0.0_f32; 4];
+        player.fill(&mut data);
+        queue.push(waveform(5)).ok();
+        // the first one finishes, the second has no room for its reports
+        player.fill(&mut data);
+        player.fill(&mut data);
+        assert_eq!(data, [0.0; 4]);
+        assert_eq!(drain(&mut reports), [None, Some(3)]);
+
+        player.fill(&mut data);
+        assert_eq!(data, [0.5, -0.5, 0.5, -0.5]);
+        assert_eq!(drain(&mut reports), [None]);
+    }
+
+    #[test]
+    fn a_full_queue_keeps_the_phrase_until_it_can_be_handed_back() {
+        let (mut queue, mut player, mut reports) = player(1);
+        queue.push(waveform(2)).ok();
+        let mut data = [0.0_f32; 4];
+        // no room for the start and the finish together
+        player.fill(&mut data);
+        assert_eq!(data, [0.0; 4]);
+        assert!(drain(&mut reports).is_empty());
+        assert!(player.current.is_none());
+    }
+
+    #[test]
+    fn stereo_is_spread_over_every_speaker() {
+        use Speaker::{Center, Left, Right, Silent};
+        assert_eq!(speakers(1), [Center]);
+        assert_eq!(speakers(2), [Left, Right]);
+        assert_eq!(speakers(4), [Left, Right, Left, Right]);
+        assert_eq!(speakers(6), [Left, Right, Center, Silent, Left, Right]);
+        assert_eq!(
+            speakers(8),
+            [Left, Right, Center, Silent, Left, Right, Left, Right]
+        );
+        for channels in 0..=16 {
+            assert_eq!(speakers(channels).len(), channels);
+        }
+
+        let (mut queue, consumer) = RingBuffer::new(QUEUE_CAPACITY);
+        let (played, _reports) = RingBuffer::new(PLAYED_CAPACITY);
+        let mut player = Player::new(consumer, played, 6);
+        queue.push(waveform(1)).ok();
+        let mut data = [1_i16; 12];
+        player.fill(&mut data);
+        assert_eq!(data, [16384, -16384, 0, 0, 16384, -16384, 0, 0, 0, 0, 0, 0]);
+
+        let (mut queue, consumer) = RingBuffer::new(QUEUE_CAPACITY);
+        let (played, _reports) = RingBuffer::new(PLAYED_CAPACITY);
+        let mut player = Player::new(consumer, played, 1);
+        queue.push(waveform(2)).ok();
+        let mut data = [1_u16; 3];
+        player.fill(&mut data);
+        assert_eq!(data, [32768; 3]);
+    }
+
+    #[test]
+    fn reports_are_forwarded_in_order() {
+        let (mut played, reports) = RingBuffer::new(PLAYED_CAPACITY);
+        let (events, mut received) = unbounded_channel();
+        let lost = Arc::new(AtomicBool::new(true));
+        let forwarder = Forwarder {
+            reports,
+            lost,
+            events,
+        };
+        played.push(Played::Started).ok();
+        played.push(Played::Finished(waveform(1))).ok();
+        let (stop, stopped) = channel();
+        drop(stop);
+        forwarder.run(stopped);
+
+        let received: Vec<_> = std::iter::from_fn(|| received.try_recv().ok()).collect();
+        assert_eq!(
+            received,
+            [
+                AudioOutputMessage::PhraseStarted,
+                AudioOutputMessage::PhraseFinished,
+                AudioOutputMessage::DeviceLost
+            ]
+        );
+    }
+}
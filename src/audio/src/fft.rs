@@ -0,0 +1,359 @@
+use core::domain::notes::{Note, Tuning};
+use std::{f32::consts::PI, sync::Arc};
+
+use realfft::{RealFftPlanner, RealToComplex, num_complex::Complex};
+
+const MIN_FREQUENCY: f32 = 30.0;
+const MAX_FREQUENCY: f32 = 1500.0;
+const PARTIAL_RATIO: f32 = 0.1;
+const NOISE_FLOOR: f32 = 1e-3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PitchEstimate {
+    pub frequency: f32,
+    pub confidence: f32,
+    pub note: Note,
+    pub cents: f32,
+}
+
+impl PitchEstimate {
+    // none if no note is near the frequency
+    fn new(frequency: f32, confidence: f32, tuning: Tuning) -> Option<Self> {
+        let (note, cents) = Note::from_frequency(frequency, tuning)?;
+        Some(Self {
+            frequency,
+            confidence: confidence.clamp(0.0, 1.0),
+            note,
+            cents,
+        })
+    }
+}
+
+pub trait PitchEstimator: Send {
+    fn estimate(&mut self, samples: &[f32], sample_rate: u32) -> Option<PitchEstimate>;
+}
+
+// finds the fundamental as the frequency whose harmonics carry the most energy,
+// which keeps working for bass notes with a weak or missing fundamental
+pub struct HarmonicProductSpectrum {
+    planner: RealFftPlanner<f32>,
+    harmonics: usize,
+    padding: usize,
+    tuning: Tuning,
+}
+
+impl Default for HarmonicProductSpectrum {
+    fn default() -> Self {
+        Self::new(5, 4)
+    }
+}
+
+impl HarmonicProductSpectrum {
+    pub fn new(harmonics: usize, padding: usize) -> Self {
+        Self {
+            planner: RealFftPlanner::new(),
+            harmonics: harmonics.max(1),
+            padding: padding.max(1),
+            tuning: Tuning::default(),
+        }
+    }
+
+    pub fn with_tuning(mut self, tuning: Tuning) -> Self {
+        self.tuning = tuning;
+        self
+    }
+
+    fn spectrum(&mut self, samples: &[f32]) -> Vec<f32> {
+        let fft_size = samples.len().next_power_of_two() * self.padding;
+        let fft: Arc<dyn RealToComplex<f32>> = self.planner.plan_fft_forward(fft_size);
+
+        let mut input = fft.make_input_vec();
+        for (i, (slot, sample)) in input.iter_mut().zip(samples).enumerate() {
+            *slot = sample * hann(i, samples.len());
+        }
+        let mut output: Vec<Complex<f32>> = fft.make_output_vec();
+        fft.process(&mut input, &mut output)
+            .expect("buffers are created by the fft plan");
+
+        output.iter().map(|c| c.norm()).collect()
+    }
+
+    // whether there is a peak of its own near the bin, outside of the main lobe
+    // of the loudest partial
+    fn has_partial(&self, spectrum: &[f32], bin: usize, loudest_bin: usize) -> bool {
+        let main_lobe = 2 * self.padding;
+        let from = bin.saturating_sub(self.padding).max(1);
+        let to = (bin + self.padding).min(spectrum.len() - 2);
+        (from..=to).any(|i| {
+            i.abs_diff(loudest_bin) > main_lobe
+                && spectrum[i] > PARTIAL_RATIO * spectrum[loudest_bin]
+                && spectrum[i] >= spectrum[i - 1]
+                && spectrum[i] >= spectrum[i + 1]
+        })
+    }
+}
+
+impl PitchEstimator for HarmonicProductSpectrum {
+    fn estimate(&mut self, samples: &[f32], sample_rate: u32) -> Option<PitchEstimate> {
+        if samples.is_empty() {
+            return None;
+        }
+        let spectrum = self.spectrum(samples);
+        let bin_width = sample_rate as f32 / ((spectrum.len() - 1) * 2) as f32;
+
+        let min_bin = (MIN_FREQUENCY / bin_width).floor().max(1.0) as usize;
+        let max_bin = ((MAX_FREQUENCY / bin_width).ceil() as usize)
+            .min((spectrum.len() - 1) / self.harmonics);
+        if min_bin + 2 >= max_bin {
+            return None;
+        }
+
+        // multiply in the log domain so long products do not underflow, with a
+        // noise floor so absent harmonics do not veto an otherwise clear peak
+        let loudest = spectrum.iter().cloned().fold(0.0, f32::max);
+        if loudest <= f32::EPSILON {
+            return None;
+        }
+        let floor = loudest * NOISE_FLOOR;
+        let log_spectrum: Vec<f32> = spectrum.iter().map(|m| m.max(floor).ln()).collect();
+        let hps: Vec<f32> = (0..=max_bin)
+            .map(|bin| {
+                (1..=self.harmonics)
+                    .map(|h| log_spectrum[bin * h])
+                    .sum::<f32>()
+            })
+            .collect();
+
+        // the fundamental has to be a subharmonic of the loudest partial, and
+        // anything below the partial itself needs a second partial to explain it
+        let loudest_bin = (min_bin..(spectrum.len() - 1).min(max_bin * self.harmonics + 1))
+            .max_by(|a, b| spectrum[*a].total_cmp(&spectrum[*b]))?;
+        let (harmonic, _) = (1..=self.harmonics)
+            .filter_map(|k| {
+                let centre = loudest_bin / k;
+                let from = centre.saturating_sub(1).max(min_bin);
+                let to = (centre + 1).min(max_bin);
+                let candidate = (from..=to).max_by(|a, b| hps[*a].total_cmp(&hps[*b]))?;
+                let explained = k == 1
+                    || (1..=self.harmonics)
+                        .filter(|&j| j != k)
+                        .any(|j| self.has_partial(&spectrum, candidate * j, loudest_bin));
+                explained.then_some((k, candidate))
+            })
+            .max_by(|(_, a), (_, b)| hps[*a].total_cmp(&hps[*b]))?;
+
+        // the loudest partial is resolved far better than the product, so derive
+        // the exact frequency from it
+        let offset = parabolic_offset(
+            log_spectrum[loudest_bin - 1],
+            log_spectrum[loudest_bin],
+            log_spectrum[loudest_bin + 1],
+        );
+        let frequency = (loudest_bin as f32 + offset) * bin_width / harmonic as f32;
+
+        let total_energy: f32 = spectrum.iter().map(|m| m * m).sum();
+        let harmonic_energy: f32 = (1..=self.harmonics)
+            .map(|h| {
+                let centre = ((frequency / bin_width) * h as f32).round() as usize;
+                let from = centre.saturating_sub(2 * self.padding);
+                let to = (centre + 2 * self.padding).min(spectrum.len() - 1);
+                spectrum[from..=to].iter().map(|m| m * m).sum::<f32>()
+            })
+            .sum();
+
+        PitchEstimate::new(frequency, harmonic_energy / total_energy, self.tuning)
+    }
+}
+
+// time domain estimator after de Cheveigné and Kawahara, more precise than the
+// spectrum for clean monophonic input
+pub struct Yin {
+    threshold: f32,
+    tuning: Tuning,
+}
+
+impl Default for Yin {
+    fn default() -> Self {
+        Self::new(0.15)
+    }
+}
+
+impl Yin {
+    pub fn new(threshold: f32) -> Self {
+        Self {
+            threshold,
+            tuning: Tuning::default(),
+        }
+    }
+
+    pub fn with_tuning(mut self, tuning: Tuning) -> Self {
+        self.tuning = tuning;
+        self
+    }
+}
+
+impl PitchEstimator for Yin {
+    fn estimate(&mut self, samples: &[f32], sample_rate: u32) -> Option<PitchEstimate> {
+        let min_tau = ((sample_rate as f32 / MAX_FREQUENCY) as usize).max(2);
+        let max_tau = ((sample_rate as f32 / MIN_FREQUENCY) as usize).min(samples.len() / 2);
+        if min_tau + 2 >= max_tau {
+            return None;
+        }
+        let width = samples.len() - max_tau;
+
+        let mut normalized = vec![1.0_f32; max_tau + 1];
+        let mut running_sum = 0.0;
+        for tau in 1..=max_tau {
+            let difference: f32 = samples[..width]
+                .iter()
+                .zip(&samples[tau..tau + width])
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum();
+            running_sum += difference;
+            normalized[tau] = if running_sum > 0.0 {
+                difference * tau as f32 / running_sum
+            } else {
+                1.0
+            };
+        }
+
+        let mut tau = (min_tau..max_tau).find(|&tau| normalized[tau] < self.threshold);
+        if let Some(found) = &mut tau {
+            while *found + 1 < max_tau && normalized[*found + 1] < normalized[*found] {
+                *found += 1;
+            }
+        }
+        let tau = tau.or_else(|| {
+            (min_tau..max_tau).min_by(|a, b| normalized[*a].total_cmp(&normalized[*b]))
+        })?;
+
+        let offset = parabolic_offset(normalized[tau - 1], normalized[tau], normalized[tau + 1]);
+        let frequency = sample_rate as f32 / (tau as f32 + offset);
+
+        PitchEstimate::new(frequency, 1.0 - normalized[tau], self.tuning)
+    }
+}
+
+fn hann(index: usize, len: usize) -> f32 {
+    if len < 2 {
+        return 1.0;
+    }
+    0.5 - 0.5 * (2.0 * PI * index as f32 / (len - 1) as f32).cos()
+}
+
+// position of the vertex of the parabola through three equidistant points
+fn parabolic_offset(left: f32, centre: f32, right: f32) -> f32 {
+    let denominator = left - 2.0 * centre + right;
+    if denominator.abs() <= f32::EPSILON {
+        return 0.0;
+    }
+    (0.5 * (left - right) / denominator).clamp(-0.5, 0.5)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        domain::{notes::Note, profile::Settings},
+        interfaces::audio::NoteEvent,
+    };
+    use std::{f32::consts::PI, time::Duration};
+
+    use super::{HarmonicProductSpectrum, PitchEstimator, Yin};
+    use crate::{
+        listener::{MIN_CONFIDENCE, WINDOW_SIZE},
+        tone::{SoundFontGenerator, ToneGenerator},
+    };
+
+    const SAMPLE_RATE: u32 = 44100;
+    // from the lowest note of the random mode up
+    const NOTES: [&str; 8] = ["E1", "A1", "D2", "G2", "C3", "A3", "E4", "A5"];
+    // the samples of the soundfont are not perfectly in tune themselves
+    const SOUNDFONT_CENTS: f32 = 15.0;
+
+    fn sine(frequency: f32) -> Vec<f32> {
+        (0..WINDOW_SIZE)
+            .map(|i| (2.0 * PI * frequency * i as f32 / SAMPLE_RATE as f32).sin())
+            .collect()
+    }
+
+    fn sawtooth(frequency: f32) -> Vec<f32> {
+        (0..WINDOW_SIZE)
+            .map(|i| 2.0 * (frequency * i as f32 / SAMPLE_RATE as f32).fract() - 1.0)
+            .collect()
+    }
+
+    fn pure(wave: fn(f32) -> Vec<f32>) -> impl Fn(Note) -> Vec<f32> {
+        move |note| wave(note.frequency(Default::default()))
+    }
+
+    // a window of the built-in bass after the attack
+    fn soundfont(note: Note) -> Vec<f32> {
+        let mut generator =
+            SoundFontGenerator::new(&Settings::default().audio(None), SAMPLE_RATE).unwrap();
+        let waveform = generator.render(&[NoteEvent::note(note, Duration::from_secs(1))]);
+        let start = SAMPLE_RATE as usize / 4;
+        waveform.left[start..start + WINDOW_SIZE].to_vec()
+    }
+
+    fn check(
+        estimator: &mut dyn PitchEstimator,
+        signal: impl Fn(Note) -> Vec<f32>,
+        max_cents: f32,
+    ) {
+        for name in NOTES {
+            let note: Note = name.parse().unwrap();
+            let estimate = estimator
+                .estimate(&signal(note), SAMPLE_RATE)
+                .unwrap_or_else(|| panic!("no pitch found for {note}"));
+            assert!(
+                estimate.note.is_enharmonic(&note),
+                "{note} was taken for {}",
+                estimate.note
+            );
+            assert!(
+                estimate.cents.abs() <= max_cents,
+                "{note} is off by {} cents",
+                estimate.cents
+            );
+            assert!(
+                estimate.confidence >= MIN_CONFIDENCE,
+                "{note} is only found with a confidence of {}",
+                estimate.confidence
+            );
+        }
+    }
+
+    #[test]
+    fn yin_finds_sines() {
+        check(&mut Yin::default(), pure(sine), 1.0);
+    }
+
+    #[test]
+    fn yin_finds_sawtooths() {
+        check(&mut Yin::default(), pure(sawtooth), 5.0);
+    }
+
+    #[test]
+    fn yin_finds_soundfont_notes() {
+        check(&mut Yin::default(), soundfont, SOUNDFONT_CENTS);
+    }
+
+    #[test]
+    fn spectrum_finds_sines() {
+        check(&mut HarmonicProductSpectrum::default(), pure(sine), 1.0);
+    }
+
+    #[test]
+    fn spectrum_finds_sawtooths() {
+        check(&mut HarmonicProductSpectrum::default(), pure(sawtooth), 5.0);
+    }
+
+    #[test]
+    fn spectrum_finds_soundfont_notes() {
+        check(
+            &mut HarmonicProductSpectrum::default(),
+            soundfont,
+            SOUNDFONT_CENTS,
+        );
+    }
+}
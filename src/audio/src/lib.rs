@@ -1,23 +1,51 @@
-use core::{domain::notes::Note, interfaces::audio::AudioInterfaceTrait};
-use std::{io::Cursor, sync::Arc, time::Duration};
-
-use anyhow::{Context, Result};
-use cpal::{
-    Device, FromSample, Host, I24, SampleFormat, SizedSample, StreamConfig,
-    traits::{DeviceTrait, HostTrait, StreamTrait},
+use core::{
+    domain::{instrument::Instrument, notes::Tuning},
+    interfaces::audio::{AudioInterfaceTrait, AudioOutputMessage, AudioSettings, NoteEvent},
 };
-use rustysynth::{SoundFont, Synthesizer, SynthesizerSettings};
-use tracing::error;
-
+use std::{fs::File, io::Cursor, ops::RangeInclusive, path::Path, sync::Arc};
+
+use anyhow::{Context, Result, bail};
+use cpal::{Device, SampleFormat, StreamConfig, traits::DeviceTrait};
+use devices::output_device;
+use engine::Engine;
+use rustysynth::{SoundFont, Synthesizer};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+use tone::{ToneGenerator, default_tone_generator, reconfigure};
+use tracing::warn;
+use waveform::Waveform;
+
+mod devices;
+mod engine;
+pub mod fft;
+mod listener;
+mod render;
+mod tone;
+mod waveform;
+
+pub use devices::{DeviceInfo, HostDevices, list_devices};
+pub use listener::{MicrophoneListener, WavListener};
+pub use render::{WavRecorder, render_wav};
+
+// renders phrases on the calling thread and hands them to the engine, which
+// plays them in the background
 pub struct AudioDevice {
-    _host: Host,
-    output: Device,
-    stream_config: StreamConfig,
-    synth: Synthesizer,
-    output_format: SampleFormat,
+    generator: Box<dyn ToneGenerator>,
+    engine: Engine,
+    output_rate: u32,
+    settings: AudioSettings,
+    event_sender: UnboundedSender<AudioOutputMessage>,
+    events: UnboundedReceiver<AudioOutputMessage>,
 }
 
 const SOUNDFONT: &[u8] = include_bytes!("./bass.sf2");
+// the sample rates rustysynth can render at, the oscillators keep to them too
+const SYNTH_SAMPLE_RATES: RangeInclusive<u32> = 16000..=192000;
+const CONTROL_CHANGE: i32 = 0xB0;
+const BANK_SELECT: i32 = 0x00;
+const PROGRAM_CHANGE: i32 = 0xC0;
+const PITCH_BEND: i32 = 0xE0;
+// the default range of the pitch wheel, in cents either way
+const PITCH_BEND_RANGE: f32 = 200.0;
 
 impl std::fmt::Debug for AudioDevice {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -26,111 +54,141 @@ impl std::fmt::Debug for AudioDevice {
 }
 
 impl AudioDevice {
+    // plays with the embedded soundfont until it is configured otherwise
     pub fn new() -> Result<Self> {
-        let host = cpal::default_host();
-        let output = host
-            .default_output_device()
-            .context("Couldn't obtain default output device")?;
-        let stream_config = output.default_output_config()?;
-        let output_format = stream_config.sample_format();
-
-        let sample_rate = stream_config.sample_rate();
-        let synth_settings = SynthesizerSettings::new(sample_rate.0 as i32);
-
-        let soundfont = SoundFont::new(&mut Cursor::new(SOUNDFONT))?;
-        let soundfont = Arc::new(soundfont);
-        let synth = Synthesizer::new(&soundfont, &synth_settings)?;
+        let output = output_device(None)?;
+        let (event_sender, events) = unbounded_channel();
+        let (engine, output_rate) = open(output, &event_sender)?;
+        let (generator, settings) = default_tone_generator(generator_rate(output_rate));
+
         Ok(Self {
-            _host: host,
-            output,
-            synth,
-            output_format,
-            stream_config: stream_config.into(),
+            generator,
+            engine,
+            output_rate,
+            settings,
+            event_sender,
+            events,
         })
     }
 
-    fn generate_waveform(&mut self, note: Note, interval: Duration) -> Waveform {
-        let sample_count = interval.as_secs() as usize * self.synth.get_sample_rate() as usize;
-        let mut left: Vec<f32> = vec![0_f32; sample_count];
-        let mut right: Vec<f32> = vec![0_f32; sample_count];
-
-        self.synth.note_off_all(true);
-        self.synth.note_on(0, note.to_midi_number() as i32, 100);
-        self.synth.render(&mut left[..], &mut right[..]);
+    fn generate_waveform(&mut self, phrase: &[NoteEvent]) -> Waveform {
+        let generator_rate = self.generator.sample_rate();
+        self.generator
+            .render(phrase)
+            .resample(generator_rate, self.output_rate)
+    }
+}
 
-        Waveform {
-            left,
-            _right: right,
+// the soundfont in the file, or the embedded one if there is no path
+fn load_soundfont(path: Option<&Path>) -> Result<Arc<SoundFont>> {
+    let soundfont = match path {
+        Some(path) => {
+            let mut file = File::open(path)
+                .with_context(|| format!("Couldn't open soundfont {}", path.display()))?;
+            SoundFont::new(&mut file)
+                .with_context(|| format!("Couldn't read soundfont {}", path.display()))?
         }
-    }
+        None => SoundFont::new(&mut Cursor::new(SOUNDFONT))?,
+    };
+    Ok(Arc::new(soundfont))
 }
 
-impl AudioInterfaceTrait for AudioDevice {
-    fn play_note(&mut self, note: Note, interval: Duration) {
-        let waveform = self.generate_waveform(note, interval);
-        waveform.play(self).unwrap();
+// the instruments in the soundfont file, or in the embedded one, with their
+// names
+pub fn presets(path: Option<&Path>) -> Result<Vec<(Instrument, String)>> {
+    let soundfont = load_soundfont(path)?;
+    let mut presets: Vec<(Instrument, String)> = soundfont
+        .get_presets()
+        .iter()
+        .filter_map(|preset| {
+            let instrument = Instrument::new(
+                u8::try_from(preset.get_bank_number()).ok()?,
+                u8::try_from(preset.get_patch_number()).ok()?,
+            )
+            .ok()?;
+            Some((instrument, preset.get_name().to_string()))
+        })
+        .collect();
+    presets.sort();
+    Ok(presets)
+}
+
+// the synthesizer would quietly fall back to another preset
+fn check_instrument(soundfont: &SoundFont, instrument: Instrument) -> Result<()> {
+    let found = soundfont.get_presets().iter().any(|preset| {
+        preset.get_bank_number() == instrument.bank as i32
+            && preset.get_patch_number() == instrument.preset as i32
+    });
+    if !found {
+        bail!("The soundfont has no instrument {instrument}");
     }
+    Ok(())
 }
 
-struct Waveform {
-    left: Vec<f32>,
-    _right: Vec<f32>,
+// a new synthesizer starts out with its own defaults
+fn apply_settings(synth: &mut Synthesizer, settings: &AudioSettings) {
+    synth.set_master_volume(settings.volume as f32 / 100.0);
+    let instrument = settings.instrument;
+    synth.process_midi_message(0, CONTROL_CHANGE, BANK_SELECT, instrument.bank as i32);
+    synth.process_midi_message(0, PROGRAM_CHANGE, instrument.preset as i32, 0);
+
+    let cents = 1200.0 * (settings.tuning().concert_a() / Tuning::A440.concert_a()).log2();
+    let bend = (8192.0 + cents / PITCH_BEND_RANGE * 8192.0)
+        .round()
+        .clamp(0.0, 16383.0) as i32;
+    synth.process_midi_message(0, PITCH_BEND, bend & 0x7F, bend >> 7);
 }
 
-impl Waveform {
-    fn play(&self, device: &AudioDevice) -> Result<()> {
-        match device.output_format {
-            SampleFormat::I8 => self.play_inner::<i8>(device),
-            SampleFormat::I16 => self.play_inner::<i16>(device),
-            SampleFormat::I24 => self.play_inner::<I24>(device),
-            SampleFormat::I32 => self.play_inner::<i32>(device),
-            SampleFormat::I64 => self.play_inner::<i64>(device),
-            SampleFormat::U8 => self.play_inner::<u8>(device),
-            SampleFormat::U32 => self.play_inner::<u32>(device),
-            SampleFormat::U64 => self.play_inner::<u64>(device),
-            SampleFormat::F32 => self.play_inner::<f32>(device),
-            SampleFormat::F64 => self.play_inner::<f64>(device),
-            _ => unimplemented!(),
+impl AudioInterfaceTrait for AudioDevice {
+    fn configure(&mut self, settings: &AudioSettings) -> Result<()> {
+        // a device that went away is replaced even if it was the default
+        let mut opened = None;
+        if settings.output_device != self.settings.output_device || self.engine.is_lost() {
+            let output = output_device(settings.output_device.as_deref())?;
+            opened = Some(open(output, &self.event_sender)?);
         }
-    }
-
-    fn play_inner<T>(&self, device: &AudioDevice) -> Result<()>
-    where
-        T: SizedSample + FromSample<f32>,
-    {
-        let duration_secs = self.left.len() as u64 / device.stream_config.sample_rate.0 as u64;
-        let duration = Duration::from_secs(duration_secs);
-
-        let mono = self.left.clone();
-        let mut index = 0;
-        let mut next_value = move || {
-            let value = mono[index];
-            index += 1;
-            index %= mono.len();
-            value
-        };
-
-        let channels = device.stream_config.channels as usize;
-        let err_fn = |err| error!("an error occured on stream: {err}");
-        let data_callback = move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
-            for frame in data.chunks_mut(channels) {
-                let next = next_value();
-                let value = T::from_sample(next);
-                for sample in frame.iter_mut() {
-                    *sample = value;
-                }
-            }
-        };
-        let stream = device.output.build_output_stream(
-            &device.stream_config,
-            data_callback,
-            err_fn,
-            None,
+        let output_rate = opened.as_ref().map_or(self.output_rate, |(_, rate)| *rate);
+        reconfigure(
+            &mut self.generator,
+            &self.settings,
+            settings,
+            generator_rate(output_rate),
         )?;
-        stream.play()?;
-
-        std::thread::sleep(duration);
 
+        if let Some((engine, output_rate)) = opened {
+            self.engine = engine;
+            self.output_rate = output_rate;
+        }
+        self.settings = settings.clone();
         Ok(())
     }
+
+    fn events(&mut self) -> Option<&mut UnboundedReceiver<AudioOutputMessage>> {
+        Some(&mut self.events)
+    }
+
+    fn play_phrase(&mut self, phrase: &[NoteEvent]) {
+        // the core opens another device once it hears of the loss
+        if self.engine.is_lost() {
+            warn!("Dropping phrase because the output device is gone");
+            return;
+        }
+        let waveform = self.generate_waveform(phrase);
+        self.engine.play(waveform);
+    }
+}
+
+// a running stream on the device and the sample rate of the device
+fn open(output: Device, events: &UnboundedSender<AudioOutputMessage>) -> Result<(Engine, u32)> {
+    let stream_config = output.default_output_config()?;
+    let output_format: SampleFormat = stream_config.sample_format();
+    let output_rate = stream_config.sample_rate().0;
+    let stream_config: StreamConfig = stream_config.into();
+    let engine = Engine::start(output, stream_config, output_format, events.clone())?;
+    Ok((engine, output_rate))
+}
+
+// as close to the sample rate of the device as possible
+fn generator_rate(output_rate: u32) -> u32 {
+    output_rate.clamp(*SYNTH_SAMPLE_RATES.start(), *SYNTH_SAMPLE_RATES.end())
 }
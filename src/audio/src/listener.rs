@@ -0,0 +1,566 @@
+use core::{
+    domain::notes::Note,
+    interfaces::audio::{
+        AudioInput, AudioInputCommand, AudioInputMessage, InputSettings, PitchDetection,
+    },
+};
+use std::{
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{Receiver, RecvTimeoutError, Sender, channel},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use cpal::{
+    FromSample, SampleFormat, SizedSample, Stream, StreamConfig, StreamError,
+    traits::{DeviceTrait, StreamTrait},
+};
+use rtrb::{Consumer, Producer, RingBuffer};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, error::TryRecvError};
+use tracing::{debug, error, info};
+
+use crate::{
+    devices::input_device,
+    fft::{HarmonicProductSpectrum, PitchEstimator, Yin},
+};
+
+pub(crate) const WINDOW_SIZE: usize = 4096;
+const HOP_SIZE: usize = 2048;
+const SILENCE_THRESHOLD: f32 = 0.01;
+pub(crate) const MIN_CONFIDENCE: f32 = 0.8;
+const STABLE_WINDOWS: usize = 3;
+// how often the listener thread takes the captured samples out of the ring,
+// well below the time a hop takes at common sample rates
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+// how much of the input the ring holds before the newest samples are dropped
+const CAPTURE_SECONDS: usize = 1;
+
+// listens to the default input device and reports the notes played into it
+pub struct MicrophoneListener;
+
+// a running input stream and the samples it captured, dropping it closes the
+// stream
+struct Capture {
+    _stream: Stream,
+    samples: Consumer<f32>,
+    detector: NoteDetector,
+    // set by the stream once the device is gone
+    lost: Arc<AtomicBool>,
+}
+
+impl Capture {
+    fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::Relaxed)
+    }
+
+    fn notes(&mut self) -> Vec<Note> {
+        let Ok(chunk) = self.samples.read_chunk(self.samples.slots()) else {
+            return Vec::new();
+        };
+        let (first, second) = chunk.as_slices();
+        let mut notes = self.detector.push(first);
+        notes.extend(self.detector.push(second));
+        chunk.commit_all();
+        notes
+    }
+}
+
+impl MicrophoneListener {
+    pub fn create() -> AudioInput {
+        let (input, sender, commands) = AudioInput::new();
+        let (forwarded, command_receiver) = channel();
+
+        forward_commands(commands, forwarded);
+        thread::spawn(move || Self::run(command_receiver, sender));
+
+        input
+    }
+
+    fn run(commands: Receiver<AudioInputCommand>, sender: UnboundedSender<AudioInputMessage>) {
+        let mut settings: Option<InputSettings> = None;
+        let mut capture: Option<Capture> = None;
+
+        loop {
+            match commands.recv_timeout(POLL_INTERVAL) {
+                Ok(AudioInputCommand::Start) => capture = Self::start(settings.as_ref()),
+                Ok(AudioInputCommand::Stop) => capture = None,
+                Ok(AudioInputCommand::Configure(new_settings)) => {
+                    settings = Some(new_settings);
+                    // a running listener switches over right away, the old
+                    // device is closed before the new one is opened
+                    if capture.take().is_some() {
+                        capture = Self::start(settings.as_ref());
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+
+            let Some(current) = &mut capture else {
+                continue;
+            };
+            if current.is_lost() {
+                // closed before the default device is opened
+                drop(capture.take());
+                capture = Self::recover(settings.as_ref());
+                let recovered = capture.is_some();
+                if sender
+                    .send(AudioInputMessage::DeviceLost { recovered })
+                    .is_err()
+                {
+                    return;
+                }
+                continue;
+            }
+            for note in current.notes() {
+                if sender.send(AudioInputMessage::NoteDetected(note)).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+
+    fn start(settings: Option<&InputSettings>) -> Option<Capture> {
+        Self::build_stream(settings)
+            .inspect_err(|err| error!(?err, "Failed to start microphone listener"))
+            .ok()
+    }
+
+    // listens to the default input instead, the settings keep the lost device
+    fn recover(settings: Option<&InputSettings>) -> Option<Capture> {
+        let settings = settings.map(|settings| InputSettings {
+            input_device: None,
+            ..settings.clone()
+        });
+        let capture = Self::start(settings.as_ref());
+        if capture.is_some() {
+            info!("Switched to the default input device");
+        }
+        capture
+    }
+
+    fn build_stream(settings: Option<&InputSettings>) -> Result<Capture> {
+        let input = input_device(settings.and_then(|s| s.input_device.as_deref()))?;
+        let supported_config = input.default_input_config()?;
+        let sample_format = supported_config.sample_format();
+        let stream_config: StreamConfig = supported_config.into();
+        let sample_rate = stream_config.sample_rate.0;
+
+        // allocated up front, the stream callback only writes into it
+        let (ring, samples) = RingBuffer::new(sample_rate as usize * CAPTURE_SECONDS);
+        let lost = Arc::new(AtomicBool::new(false));
+        let stream = match sample_format {
+            SampleFormat::I8 => {
+                Self::build_stream_inner::<i8>(&input, &stream_config, ring, lost.clone())
+            }
+            SampleFormat::I16 => {
+                Self::build_stream_inner::<i16>(&input, &stream_config, ring, lost.clone())
+            }
+            SampleFormat::I32 => {
+                Self::build_stream_inner::<i32>(&input, &stream_config, ring, lost.clone())
+            }
+            SampleFormat::U8 => {
+                Self::build_stream_inner::<u8>(&input, &stream_config, ring, lost.clone())
+            }
+            SampleFormat::U16 => {
+                Self::build_stream_inner::<u16>(&input, &stream_config, ring, lost.clone())
+            }
+            SampleFormat::U32 => {
+                Self::build_stream_inner::<u32>(&input, &stream_config, ring, lost.clone())
+            }
+            SampleFormat::F32 => {
+                Self::build_stream_inner::<f32>(&input, &stream_config, ring, lost.clone())
+            }
+            SampleFormat::F64 => {
+                Self::build_stream_inner::<f64>(&input, &stream_config, ring, lost.clone())
+            }
+            format => anyhow::bail!("Unsupported input sample format {format}"),
+        }?;
+        stream.play()?;
+        debug!(sample_rate, "Started microphone listener");
+
+        Ok(Capture {
+            _stream: stream,
+            samples,
+            detector: NoteDetector::new(sample_rate, settings),
+            lost,
+        })
+    }
+
+    fn build_stream_inner<T>(
+        input: &cpal::Device,
+        stream_config: &StreamConfig,
+        mut ring: Producer<f32>,
+        lost: Arc<AtomicBool>,
+    ) -> Result<Stream>
+    where
+        T: SizedSample,
+        f32: FromSample<T>,
+    {
+        let channels = stream_config.channels as usize;
+        // the listener thread notices the device is gone and reports it
+        let err_fn = move |err| match err {
+            StreamError::DeviceNotAvailable => {
+                if !lost.swap(true, Ordering::Relaxed) {
+                    error!("The input device is no longer available");
+                }
+            }
+            err => error!("an error occured on input stream: {err}"),
+        };
+        // runs on the realtime thread, so it neither allocates nor waits. if
+        // the listener falls behind the newest samples are dropped
+        let data_callback = move |data: &[T], _: &cpal::InputCallbackInfo| {
+            for frame in data.chunks(channels) {
+                let mono =
+                    frame.iter().map(|s| s.to_sample::<f32>()).sum::<f32>() / channels as f32;
+                if ring.push(mono).is_err() {
+                    break;
+                }
+            }
+        };
+        let stream = input.build_input_stream(stream_config, data_callback, err_fn, None)?;
+        Ok(stream)
+    }
+}
+
+// replays a wav file as if it was played into the microphone, one hop at the
+// time it takes to play it. every start replays it from the beginning
+pub struct WavListener;
+
+impl WavListener {
+    pub fn create(path: impl AsRef<Path>) -> Result<AudioInput> {
+        let reader = hound::WavReader::open(path)?;
+        let spec = reader.spec();
+        let channels = spec.channels as usize;
+
+        let interleaved: Vec<f32> = match spec.sample_format {
+            hound::SampleFormat::Float => reader.into_samples::<f32>().collect::<Result<_, _>>()?,
+            hound::SampleFormat::Int => {
+                let scale = (1_i64 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .into_samples::<i32>()
+                    .map(|s| s.map(|s| s as f32 / scale))
+                    .collect::<Result<_, _>>()?
+            }
+        };
+        let samples = interleaved
+            .chunks(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect();
+
+        Ok(Self::from_samples(samples, spec.sample_rate))
+    }
+
+    pub fn from_samples(samples: Vec<f32>, sample_rate: u32) -> AudioInput {
+        let (input, sender, commands) = AudioInput::new();
+        thread::spawn(move || Self::run(commands, sender, &samples, sample_rate));
+        input
+    }
+
+    fn run(
+        mut commands: UnboundedReceiver<AudioInputCommand>,
+        sender: UnboundedSender<AudioInputMessage>,
+        samples: &[f32],
+        sample_rate: u32,
+    ) {
+        let hop = Duration::from_secs_f64(HOP_SIZE as f64 / sample_rate as f64);
+        let mut settings: Option<InputSettings> = None;
+        let mut replay: Option<Replay> = None;
+
+        loop {
+            // only waits for a command while nothing is replayed
+            let command = match &replay {
+                Some(_) => match commands.try_recv() {
+                    Ok(command) => Some(command),
+                    Err(TryRecvError::Empty) => None,
+                    Err(TryRecvError::Disconnected) => return,
+                },
+                None => match commands.blocking_recv() {
+                    Some(command) => Some(command),
+                    None => return,
+                },
+            };
+            match command {
+                Some(AudioInputCommand::Start) => {
+                    replay = Some(Replay::new(sample_rate, settings.as_ref()));
+                }
+                Some(AudioInputCommand::Stop) => replay = None,
+                Some(AudioInputCommand::Configure(new_settings)) => {
+                    settings = Some(new_settings);
+                    if let Some(replay) = &mut replay {
+                        replay.detector = NoteDetector::new(sample_rate, settings.as_ref());
+                    }
+                }
+                None => {}
+            }
+
+            let Some(current) = &mut replay else {
+                continue;
+            };
+            let Some(chunk) = samples.chunks(HOP_SIZE).nth(current.hops) else {
+                debug!("Replayed the whole recording");
+                replay = None;
+                continue;
+            };
+            for note in current.detector.push(chunk) {
+                if sender.send(AudioInputMessage::NoteDetected(note)).is_err() {
+                    return;
+                }
+            }
+            current.hops += 1;
+
+            // a deadline keeps the pace even if detecting takes a while
+            let deadline = current.started + hop * current.hops as u32;
+            if let Some(wait) = deadline.checked_duration_since(Instant::now()) {
+                thread::sleep(wait);
+            }
+        }
+    }
+}
+
+// how far a recording has been replayed
+struct Replay {
+    detector: NoteDetector,
+    started: Instant,
+    hops: usize,
+}
+
+impl Replay {
+    fn new(sample_rate: u32, settings: Option<&InputSettings>) -> Self {
+        Self {
+            detector: NoteDetector::new(sample_rate, settings),
+            started: Instant::now(),
+            hops: 0,
+        }
+    }
+}
+
+fn forward_commands(
+    mut commands: UnboundedReceiver<AudioInputCommand>,
+    forwarded: Sender<AudioInputCommand>,
+) {
+    thread::spawn(move || {
+        while let Some(command) = commands.blocking_recv() {
+            if forwarded.send(command).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+// turns a continuous stream of samples into distinct note events
+struct NoteDetector {
+    sample_rate: u32,
+    estimator: Box<dyn PitchEstimator>,
+    buffer: Vec<f32>,
+    candidate: Option<Note>,
+    candidate_count: usize,
+    last_reported: Option<Note>,
+}
+
+impl NoteDetector {
+    fn new(sample_rate: u32, settings: Option<&InputSettings>) -> Self {
+        let tuning = settings.map(InputSettings::tuning).unwrap_or_default();
+        let detection = settings
+            .map(|settings| settings.pitch_detection)
+            .unwrap_or_default();
+        let estimator: Box<dyn PitchEstimator> = match detection {
+            PitchDetection::Yin => Box::new(Yin::default().with_tuning(tuning)),
+            PitchDetection::Spectrum => {
+                Box::new(HarmonicProductSpectrum::default().with_tuning(tuning))
+            }
+        };
+        Self {
+            sample_rate,
+            estimator,
+            buffer: Vec::with_capacity(WINDOW_SIZE * 2),
+            candidate: None,
+            candidate_count: 0,
+            last_reported: None,
+        }
+    }
+
+    fn push(&mut self, samples: &[f32]) -> Vec<Note> {
+        let mut notes = Vec::new();
+        self.buffer.extend_from_slice(samples);
+
+        while self.buffer.len() >= WINDOW_SIZE {
+            if let Some(note) = self.process_window() {
+                notes.push(note);
+            }
+            self.buffer.drain(..HOP_SIZE);
+        }
+
+        notes
+    }
+
+    fn process_window(&mut self) -> Option<Note> {
+        let window = &self.buffer[..WINDOW_SIZE];
+        let rms = (window.iter().map(|s| s * s).sum::<f32>() / window.len() as f32).sqrt();
+        let note = (rms >= SILENCE_THRESHOLD)
+            .then(|| self.estimator.estimate(window, self.sample_rate))
+            .flatten()
+            .filter(|estimate| estimate.confidence >= MIN_CONFIDENCE)
+            .map(|estimate| estimate.note);
+
+        if note.is_none() || note != self.candidate {
+            self.candidate = note;
+            self.candidate_count = 0;
+            if note.is_none() {
+                self.last_reported = None;
+            }
+        }
+        self.candidate_count += 1;
+
+        if self.candidate_count >= STABLE_WINDOWS && self.candidate != self.last_reported {
+            self.last_reported = self.candidate;
+            return self.candidate;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        Core,
+        domain::notes::Note,
+        interfaces::{
+            audio::{
+                AudioInputCommand, AudioInputInterfaceTrait, AudioInputMessage, InputSettings,
+                PitchDetection,
+            },
+            storage::InMemoryStorage,
+            ui::{
+                CoreGameMessage, CoreMessage, GameMode, UIGameMessage, UIMainMenuMessage,
+                UserInterface, UserInterfaceMessage,
+            },
+        },
+    };
+    use std::{f32::consts::PI, time::Duration};
+
+    use tokio::time::{Instant, timeout};
+
+    use super::{HOP_SIZE, WavListener};
+    use crate::WavRecorder;
+
+    const SAMPLE_RATE: u32 = 44100;
+
+    fn sine(frequency: f32, seconds: f32) -> Vec<f32> {
+        let length = (seconds * SAMPLE_RATE as f32) as usize;
+        (0..length)
+            .map(|i| 0.5 * (2.0 * PI * frequency * i as f32 / SAMPLE_RATE as f32).sin())
+            .collect()
+    }
+
+    // tokio::test refers to ::core, which is the core crate here
+    fn block_on<F: Future>(future: F) -> F::Output {
+        tokio::runtime::Runtime::new().unwrap().block_on(future)
+    }
+
+    #[test]
+    fn detects_the_note_at_the_pace_of_the_recording() {
+        block_on(async {
+            let mut input = WavListener::from_samples(sine(220.0, 1.0), SAMPLE_RATE);
+            let started = Instant::now();
+            input.send(AudioInputCommand::Start);
+
+            let AudioInputMessage::NoteDetected(note) =
+                timeout(Duration::from_secs(5), input.receive())
+                    .await
+                    .expect("the note is detected")
+            else {
+                panic!("only notes are detected in a recording");
+            };
+            assert_eq!(note, "A3".parse::<Note>().unwrap());
+            // three stable windows are needed, which take a while to be heard
+            let hop = Duration::from_secs_f64(HOP_SIZE as f64 / SAMPLE_RATE as f64);
+            assert!(started.elapsed() >= hop * 3);
+        })
+    }
+
+    #[test]
+    fn detects_with_the_configured_estimator() {
+        block_on(async {
+            let mut input = WavListener::from_samples(sine(55.0, 1.0), SAMPLE_RATE);
+            input.send(AudioInputCommand::Configure(InputSettings {
+                input_device: None,
+                concert_a: 440,
+                pitch_detection: PitchDetection::Spectrum,
+            }));
+            input.send(AudioInputCommand::Start);
+
+            let AudioInputMessage::NoteDetected(note) =
+                timeout(Duration::from_secs(5), input.receive())
+                    .await
+                    .expect("the note is detected")
+            else {
+                panic!("only notes are detected in a recording");
+            };
+            assert_eq!(note, "A1".parse::<Note>().unwrap());
+        })
+    }
+
+    #[test]
+    fn stop_interrupts_the_replay() {
+        block_on(async {
+            let mut input = WavListener::from_samples(
+                [vec![0.0; SAMPLE_RATE as usize], sine(220.0, 1.0)].concat(),
+                SAMPLE_RATE,
+            );
+            input.send(AudioInputCommand::Start);
+            input.send(AudioInputCommand::Stop);
+
+            let heard = timeout(Duration::from_secs(3), input.receive()).await;
+            assert!(heard.is_err(), "nothing is heard after the stop");
+        })
+    }
+
+    #[test]
+    fn played_note_is_judged_by_the_core() {
+        block_on(async {
+            let input = WavListener::from_samples(sine(220.0, 2.0), SAMPLE_RATE);
+            let (ui, ui_sender, mut core_receiver) = UserInterface::new();
+            let mut core = Core::new(
+                Box::new(WavRecorder::new().unwrap()),
+                Box::new(input),
+                Box::new(ui),
+                Box::new(InMemoryStorage::default()),
+            );
+            for message in [
+                UserInterfaceMessage::MainMenuMessage(UIMainMenuMessage::Start(GameMode::Random)),
+                UserInterfaceMessage::GameMessage(UIGameMessage::NoteRequest),
+            ] {
+                ui_sender.send(message).unwrap();
+            }
+            let running = tokio::spawn(async move { core.run().await });
+
+            let note_guess = timeout(Duration::from_secs(10), async {
+                loop {
+                    match core_receiver.recv().await {
+                        Some(CoreMessage::GameMessage(CoreGameMessage::GuessResponse(guess))) => {
+                            break guess;
+                        }
+                        Some(_) => {}
+                        None => panic!("the core stopped"),
+                    }
+                }
+            })
+            .await
+            .expect("the played note is judged");
+            assert_eq!(note_guess.note_played, "A3".parse::<Note>().unwrap());
+
+            ui_sender
+                .send(UserInterfaceMessage::MainMenuMessage(
+                    UIMainMenuMessage::Quit,
+                ))
+                .unwrap();
+            running.await.unwrap();
+        })
+    }
+}
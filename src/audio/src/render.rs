@@ -0,0 +1,310 @@
+use core::{
+    interfaces::audio::{AudioFallback, AudioInterfaceTrait, AudioSettings, NoteEvent},
+    use_cases::config::fall_back,
+};
+use std::{fs::File, io::BufWriter, path::Path};
+
+use anyhow::{Context, Result, bail};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use tracing::error;
+
+use crate::{
+    tone::{ToneGenerator, default_tone_generator, reconfigure},
+    waveform::Waveform,
+};
+
+const SAMPLE_RATE: u32 = 44100;
+
+// plays phrases into a recording instead of a device, so the core can run
+// without a sound card. the phrases follow each other with their release
+// tails, none is cut short. the recording is kept in memory and can be
+// written to a WAV file as it grows
+pub struct WavRecorder {
+    generator: Box<dyn ToneGenerator>,
+    settings: AudioSettings,
+    recording: Waveform,
+    file: Option<WavWriter<BufWriter<File>>>,
+}
+
+impl std::fmt::Debug for WavRecorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WavRecorder")
+            .field("frames", &self.recording.len())
+            .finish()
+    }
+}
+
+impl WavRecorder {
+    // records into memory only, with the embedded soundfont until it is
+    // configured otherwise
+    pub fn new() -> Result<Self> {
+        let (generator, settings) = default_tone_generator(SAMPLE_RATE);
+        Ok(Self {
+            generator,
+            settings,
+            recording: Waveform::default(),
+            file: None,
+        })
+    }
+
+    // also writes every phrase to the file, which is complete after each one
+    pub fn create(path: &Path) -> Result<Self> {
+        let mut recorder = Self::new()?;
+        recorder.file = Some(
+            WavWriter::create(path, wav_spec())
+                .with_context(|| format!("Couldn't create {}", path.display()))?,
+        );
+        Ok(recorder)
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    pub fn left(&self) -> &[f32] {
+        &self.recording.left
+    }
+
+    pub fn right(&self) -> &[f32] {
+        &self.recording.right
+    }
+
+    // both channels mixed, as a microphone would hear them
+    pub fn mono(&self) -> Vec<f32> {
+        self.left()
+            .iter()
+            .zip(self.right())
+            .map(|(left, right)| (left + right) / 2.0)
+            .collect()
+    }
+
+    pub fn write_wav(&self, path: &Path) -> Result<()> {
+        let mut writer = WavWriter::create(path, wav_spec())
+            .with_context(|| format!("Couldn't create {}", path.display()))?;
+        write_samples(&mut writer, &self.recording)?;
+        writer.finalize()?;
+        Ok(())
+    }
+
+    fn record(&mut self, phrase: &[NoteEvent]) -> Result<()> {
+        let waveform = self.generator.render(phrase);
+        if let Some(writer) = &mut self.file {
+            write_samples(writer, &waveform)?;
+            // updates the header, so the file can be read at any time
+            writer.flush()?;
+        }
+        self.recording.append(waveform);
+        Ok(())
+    }
+}
+
+impl AudioInterfaceTrait for WavRecorder {
+    // the output device has no meaning here
+    fn configure(&mut self, settings: &AudioSettings) -> Result<()> {
+        reconfigure(&mut self.generator, &self.settings, settings, SAMPLE_RATE)?;
+        self.settings = settings.clone();
+        Ok(())
+    }
+
+    fn play_phrase(&mut self, phrase: &[NoteEvent]) {
+        if let Err(err) = self.record(phrase) {
+            error!(?err, "Failed to record phrase");
+        }
+    }
+}
+
+// writes the phrase to a stereo WAV file instead of playing it, no audio
+// device is needed for this. settings that can't be used fall back the way
+// playback does, the fallback that was used is returned
+pub fn render_wav(
+    path: &Path,
+    phrase: &[NoteEvent],
+    settings: &AudioSettings,
+) -> Result<Option<AudioFallback>> {
+    let mut recorder = WavRecorder::new()?;
+    let fallback = match recorder.configure(settings) {
+        Ok(()) => None,
+        Err(err) => {
+            error!(?err, "Failed to apply audio settings");
+            match fall_back(&mut recorder, settings) {
+                Some(fallback) => Some(fallback),
+                None => bail!("No fallback for the audio settings could be applied"),
+            }
+        }
+    };
+    recorder.record(phrase)?;
+    recorder.write_wav(path)?;
+    Ok(fallback)
+}
+
+fn wav_spec() -> WavSpec {
+    WavSpec {
+        channels: 2,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample: 32,
+        sample_format: SampleFormat::Float,
+    }
+}
+
+fn write_samples(writer: &mut WavWriter<BufWriter<File>>, waveform: &Waveform) -> Result<()> {
+    for (left, right) in waveform.left.iter().zip(&waveform.right) {
+        writer.write_sample(*left)?;
+        writer.write_sample(*right)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        domain::{
+            instrument::{Instrument, Sound},
+            notes::Note,
+            profile::Settings,
+        },
+        interfaces::audio::{AudioFallback, AudioInterfaceTrait, AudioSettings, NoteEvent},
+    };
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+        time::Duration,
+    };
+
+    use hound::WavReader;
+
+    use super::{SAMPLE_RATE, WavRecorder, render_wav};
+    use crate::{
+        fft::{PitchEstimator, Yin},
+        listener::WINDOW_SIZE,
+        waveform::RELEASE_TAIL,
+    };
+
+    fn note(s: &str) -> Note {
+        s.parse().unwrap()
+    }
+
+    fn phrase() -> Vec<NoteEvent> {
+        vec![
+            NoteEvent::note(note("A2"), Duration::from_secs(1)),
+            NoteEvent::new(vec![note("C3"), note("E3")], Duration::from_millis(500)),
+        ]
+    }
+
+    fn frames(duration: Duration) -> usize {
+        (duration.as_secs_f64() * SAMPLE_RATE as f64).round() as usize
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|sample| sample * sample).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("zankyou-{}-{name}.wav", std::process::id()))
+    }
+
+    fn read_wav(path: &Path) -> (hound::WavSpec, Vec<f32>) {
+        let mut reader = WavReader::open(path).unwrap();
+        let samples = reader.samples::<f32>().map(Result::unwrap).collect();
+        (reader.spec(), samples)
+    }
+
+    // checks the recording of the phrase from the first frame on
+    fn check_phrase(recorder: &WavRecorder, start: usize) {
+        let length = frames(Duration::from_millis(1500) + RELEASE_TAIL);
+        let mono = &recorder.mono()[start..start + length];
+        assert!(rms(mono) > 0.01, "the phrase is silent");
+        assert!(mono.iter().all(|sample| sample.abs() <= 1.0));
+
+        // the first note after its attack
+        let window = &mono[SAMPLE_RATE as usize / 4..][..WINDOW_SIZE];
+        let estimate = Yin::default().estimate(window, SAMPLE_RATE).unwrap();
+        assert!(
+            estimate.note.is_enharmonic(&note("A2")),
+            "{}",
+            estimate.note
+        );
+    }
+
+    #[test]
+    fn records_phrases_one_after_another() {
+        let mut recorder = WavRecorder::new().unwrap();
+        assert_eq!(recorder.sample_rate(), 44100);
+        assert!(recorder.mono().is_empty());
+
+        recorder.play_phrase(&phrase());
+        let length = frames(Duration::from_millis(1500) + RELEASE_TAIL);
+        assert_eq!(recorder.left().len(), length);
+        assert_eq!(recorder.right().len(), length);
+        check_phrase(&recorder, 0);
+
+        recorder.play_phrase(&phrase());
+        assert_eq!(recorder.left().len(), 2 * length);
+        check_phrase(&recorder, length);
+    }
+
+    #[test]
+    fn records_with_the_oscillators() {
+        let mut recorder = WavRecorder::new().unwrap();
+        let settings = Settings {
+            sound: Sound::Sine,
+            ..Settings::default()
+        };
+        recorder.configure(&settings.audio(None)).unwrap();
+        recorder.play_phrase(&phrase());
+        check_phrase(&recorder, 0);
+        // the oscillators play the same on both sides
+        assert_eq!(recorder.left(), recorder.right());
+    }
+
+    #[test]
+    fn writes_what_was_recorded() {
+        let streamed = temp_path("streamed");
+        let written = temp_path("written");
+        let mut recorder = WavRecorder::create(&streamed).unwrap();
+        recorder.play_phrase(&phrase());
+        recorder.play_phrase(&[NoteEvent::note(note("E1"), Duration::from_millis(250))]);
+        recorder.write_wav(&written).unwrap();
+
+        let interleaved: Vec<f32> = recorder
+            .left()
+            .iter()
+            .zip(recorder.right())
+            .flat_map(|(left, right)| [*left, *right])
+            .collect();
+        for path in [&streamed, &written] {
+            let (spec, samples) = read_wav(path);
+            assert_eq!(spec.channels, 2);
+            assert_eq!(spec.sample_rate, SAMPLE_RATE);
+            assert_eq!(samples, interleaved);
+        }
+        drop(recorder);
+        fs::remove_file(streamed).ok();
+        fs::remove_file(written).ok();
+    }
+
+    #[test]
+    fn renders_with_a_fallback_if_the_settings_cant_be_used() {
+        let path = temp_path("fallback");
+        let settings = Settings::default().audio(None);
+        assert_eq!(render_wav(&path, &phrase(), &settings).unwrap(), None);
+
+        let missing = AudioSettings {
+            soundfont: Some("/nonexistent.sf2".into()),
+            ..settings.clone()
+        };
+        let unknown = AudioSettings {
+            instrument: Instrument { bank: 0, preset: 0 },
+            ..settings
+        };
+        for settings in [missing, unknown] {
+            let fallback = render_wav(&path, &phrase(), &settings).unwrap();
+            assert_eq!(fallback, Some(AudioFallback::ALL[1]));
+            let (_, samples) = read_wav(&path);
+            assert_eq!(
+                samples.len(),
+                2 * frames(Duration::from_millis(1500) + RELEASE_TAIL)
+            );
+        }
+        fs::remove_file(path).ok();
+    }
+}
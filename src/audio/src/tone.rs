@@ -0,0 +1,349 @@
+use core::{
+    domain::{instrument::Sound, profile::Settings},
+    interfaces::audio::{AudioSettings, NoteEvent},
+};
+use std::{f64::consts::TAU, sync::Arc, time::Duration};
+
+use anyhow::Result;
+use rustysynth::{SoundFont, Synthesizer, SynthesizerSettings};
+use tracing::error;
+
+use crate::{
+    apply_settings, check_instrument, load_soundfont,
+    waveform::{RELEASE_TAIL, Waveform},
+};
+
+// turns phrases into waveforms at a fixed sample rate
+pub(crate) trait ToneGenerator: Send {
+    // the old settings stay in place if the new ones can not be applied
+    fn configure(&mut self, settings: &AudioSettings) -> Result<()>;
+
+    fn render(&mut self, phrase: &[NoteEvent]) -> Waveform;
+
+    fn sample_rate(&self) -> u32;
+}
+
+// the generator for the sound of the settings
+pub(crate) fn tone_generator(
+    settings: &AudioSettings,
+    sample_rate: u32,
+) -> Result<Box<dyn ToneGenerator>> {
+    Ok(match settings.sound {
+        Sound::SoundFont => Box::new(SoundFontGenerator::new(settings, sample_rate)?),
+        _ => Box::new(Oscillator::new(settings, sample_rate)),
+    })
+}
+
+// the default settings with the embedded soundfont, or with a sine wave if
+// even that can't be read
+pub(crate) fn default_tone_generator(sample_rate: u32) -> (Box<dyn ToneGenerator>, AudioSettings) {
+    let mut settings = Settings::default().audio(None);
+    match tone_generator(&settings, sample_rate) {
+        Ok(generator) => (generator, settings),
+        Err(err) => {
+            error!(
+                ?err,
+                "Failed to load the built-in soundfont, playing sine waves"
+            );
+            settings.sound = Sound::Sine;
+            (Box::new(Oscillator::new(&settings, sample_rate)), settings)
+        }
+    }
+}
+
+// replaces the generator if the sound or the sample rate changes, otherwise
+// configures it in place
+pub(crate) fn reconfigure(
+    generator: &mut Box<dyn ToneGenerator>,
+    current: &AudioSettings,
+    settings: &AudioSettings,
+    sample_rate: u32,
+) -> Result<()> {
+    if settings.sound != current.sound || sample_rate != generator.sample_rate() {
+        *generator = tone_generator(settings, sample_rate)?;
+        Ok(())
+    } else {
+        generator.configure(settings)
+    }
+}
+
+pub(crate) struct SoundFontGenerator {
+    soundfont: Arc<SoundFont>,
+    synth: Synthesizer,
+    settings: AudioSettings,
+}
+
+impl SoundFontGenerator {
+    pub fn new(settings: &AudioSettings, sample_rate: u32) -> Result<Self> {
+        let soundfont = load_soundfont(settings.soundfont.as_deref())?;
+        check_instrument(&soundfont, settings.instrument)?;
+        let mut synth =
+            Synthesizer::new(&soundfont, &SynthesizerSettings::new(sample_rate as i32))?;
+        apply_settings(&mut synth, settings);
+        Ok(Self {
+            soundfont,
+            synth,
+            settings: settings.clone(),
+        })
+    }
+}
+
+impl ToneGenerator for SoundFontGenerator {
+    fn configure(&mut self, settings: &AudioSettings) -> Result<()> {
+        if settings.soundfont != self.settings.soundfont {
+            *self = Self::new(settings, self.sample_rate())?;
+            return Ok(());
+        }
+        check_instrument(&self.soundfont, settings.instrument)?;
+        self.settings = settings.clone();
+        apply_settings(&mut self.synth, &self.settings);
+        Ok(())
+    }
+
+    fn render(&mut self, phrase: &[NoteEvent]) -> Waveform {
+        Waveform::render(&mut self.synth, phrase, self.settings.velocity)
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.synth.get_sample_rate() as u32
+    }
+}
+
+// in seconds
+const ATTACK: f64 = 0.01;
+const DECAY: f64 = 0.15;
+const RELEASE: f64 = 0.3;
+// the level the notes are held at after the decay
+const SUSTAIN: f64 = 0.7;
+// leaves room for chords before the samples clip
+const VOICE_GAIN: f64 = 0.2;
+// harmonics and their amplitudes
+const ORGAN: &[(f64, f64)] = &[(1.0, 1.0), (2.0, 0.5), (3.0, 0.25), (4.0, 0.125)];
+const CLARINET: &[(f64, f64)] = &[(1.0, 1.0), (3.0, 0.5), (5.0, 0.25), (7.0, 0.125)];
+
+// plays pure or simple additive tones, needs no soundfont and can't fail
+pub(crate) struct Oscillator {
+    sample_rate: u32,
+    settings: AudioSettings,
+}
+
+impl Oscillator {
+    pub fn new(settings: &AudioSettings, sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            settings: settings.clone(),
+        }
+    }
+
+    fn frames(&self, duration: Duration) -> usize {
+        (duration.as_secs_f64() * self.sample_rate as f64).round() as usize
+    }
+}
+
+impl ToneGenerator for Oscillator {
+    fn configure(&mut self, settings: &AudioSettings) -> Result<()> {
+        self.settings = settings.clone();
+        Ok(())
+    }
+
+    // like the soundfont, every event releases the notes of the one before
+    fn render(&mut self, phrase: &[NoteEvent]) -> Waveform {
+        let rate = self.sample_rate as f64;
+        let release = (RELEASE * rate) as usize;
+        let total = phrase
+            .iter()
+            .map(|event| self.frames(event.duration))
+            .sum::<usize>()
+            + self.frames(RELEASE_TAIL);
+        let velocity = self.settings.velocity as f64 / 127.0;
+        let volume = self.settings.volume as f64 / 100.0;
+        let gain = VOICE_GAIN * velocity * volume;
+
+        let mut samples = vec![0_f32; total];
+        let mut start = 0;
+        for event in phrase {
+            let held = self.frames(event.duration);
+            let end = (start + held + release).min(total);
+            for note in &event.notes {
+                let frequency = note.frequency(self.settings.tuning()) as f64;
+                for (offset, sample) in samples[start..end].iter_mut().enumerate() {
+                    let time = offset as f64 / rate;
+                    let phase = (frequency * time).fract();
+                    *sample += (gain
+                        * envelope(time, held as f64 / rate)
+                        * wave(self.settings.sound, phase)) as f32;
+                }
+            }
+            start += held;
+        }
+
+        Waveform {
+            left: samples.clone(),
+            right: samples,
+        }
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
+
+// the level of a note at the time since it started, it is let go after held
+fn envelope(time: f64, held: f64) -> f64 {
+    let level = |time: f64| {
+        if time < ATTACK {
+            time / ATTACK
+        } else {
+            let decayed = ((time - ATTACK) / DECAY).min(1.0);
+            1.0 - (1.0 - SUSTAIN) * decayed
+        }
+    };
+    if time < held {
+        level(time)
+    } else {
+        level(held) * (1.0 - (time - held) / RELEASE).max(0.0)
+    }
+}
+
+// one period of the sound, the phase goes from 0 to 1
+fn wave(sound: Sound, phase: f64) -> f64 {
+    match sound {
+        Sound::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+        Sound::Saw => 2.0 * phase - 1.0,
+        Sound::Organ => additive(ORGAN, phase),
+        Sound::Clarinet => additive(CLARINET, phase),
+        Sound::Sine | Sound::SoundFont => (TAU * phase).sin(),
+    }
+}
+
+fn additive(harmonics: &[(f64, f64)], phase: f64) -> f64 {
+    let total: f64 = harmonics.iter().map(|(_, amplitude)| amplitude).sum();
+    harmonics
+        .iter()
+        .map(|(harmonic, amplitude)| amplitude * (TAU * harmonic * phase).sin())
+        .sum::<f64>()
+        / total
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        domain::{instrument::Sound, notes::Note, profile::Settings},
+        interfaces::audio::NoteEvent,
+    };
+    use std::time::Duration;
+
+    use super::{
+        ATTACK, DECAY, Oscillator, RELEASE, SUSTAIN, ToneGenerator, VOICE_GAIN, envelope,
+        tone_generator,
+    };
+    use crate::{
+        fft::{HarmonicProductSpectrum, PitchEstimator, Yin},
+        listener::{MIN_CONFIDENCE, WINDOW_SIZE},
+        waveform::RELEASE_TAIL,
+    };
+
+    const SAMPLE_RATE: u32 = 44100;
+    const NOTES: [&str; 4] = ["E2", "C3", "A3", "E4"];
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    fn frames(duration: Duration) -> usize {
+        (duration.as_secs_f64() * SAMPLE_RATE as f64).round() as usize
+    }
+
+    #[test]
+    fn notes_rise_decay_to_the_sustain_and_are_released_to_silence() {
+        let held = 1.0;
+        assert_eq!(envelope(0.0, held), 0.0);
+        assert!(close(envelope(ATTACK / 2.0, held), 0.5));
+        assert!(close(envelope(ATTACK, held), 1.0));
+        assert!(close(
+            envelope(ATTACK + DECAY / 2.0, held),
+            (1.0 + SUSTAIN) / 2.0
+        ));
+        assert!(close(envelope(ATTACK + DECAY, held), SUSTAIN));
+        assert!(close(envelope(0.9, held), SUSTAIN));
+        assert!(close(envelope(held + RELEASE / 2.0, held), SUSTAIN / 2.0));
+        assert_eq!(envelope(held + RELEASE, held), 0.0);
+        assert_eq!(envelope(held + 2.0 * RELEASE, held), 0.0);
+
+        // a note let go during the attack is released from where it got to
+        assert!(close(envelope(ATTACK / 2.0, ATTACK / 2.0), 0.5));
+        assert!(close(
+            envelope(ATTACK / 2.0 + RELEASE / 2.0, ATTACK / 2.0),
+            0.25
+        ));
+    }
+
+    #[test]
+    fn oscillators_hold_the_notes_and_let_them_ring_out() {
+        let settings = Settings {
+            sound: Sound::Sine,
+            ..Default::default()
+        }
+        .audio(None);
+        let mut oscillator = Oscillator::new(&settings, SAMPLE_RATE);
+        let second = Duration::from_secs(1);
+        let note: Note = "A3".parse().unwrap();
+        let waveform =
+            oscillator.render(&[NoteEvent::note(note, second), NoteEvent::note(note, second)]);
+        assert_eq!(waveform.left.len(), frames(2 * second + RELEASE_TAIL));
+        assert_eq!(waveform.left, waveform.right);
+
+        let peak = |range: std::ops::Range<usize>| {
+            waveform.left[range]
+                .iter()
+                .fold(0_f32, |peak, sample| peak.max(sample.abs())) as f64
+        };
+        let gain = VOICE_GAIN * settings.velocity as f64 / 127.0 * settings.volume as f64 / 100.0;
+        let rate = SAMPLE_RATE as usize;
+        // full level at the end of the attack, the sustain before the next note
+        assert!((peak(0..rate / 20) - gain).abs() < gain * 0.01);
+        assert!((peak(rate * 9 / 10..rate) - gain * SUSTAIN).abs() < gain * 0.01);
+        // silent once the last note is released
+        let released = 2 * rate + frames(Duration::from_secs_f64(RELEASE));
+        assert_eq!(peak(released..waveform.left.len()), 0.0);
+    }
+
+    #[test]
+    fn every_sound_plays_the_note_it_was_given() {
+        let mut estimators: [Box<dyn PitchEstimator>; 2] = [
+            Box::new(Yin::default()),
+            Box::new(HarmonicProductSpectrum::default()),
+        ];
+        for sound in Sound::ALL {
+            let settings = Settings {
+                sound,
+                ..Default::default()
+            }
+            .audio(None);
+            let mut generator = tone_generator(&settings, SAMPLE_RATE).unwrap();
+            for name in NOTES {
+                let note: Note = name.parse().unwrap();
+                let waveform = generator.render(&[NoteEvent::note(note, Duration::from_secs(1))]);
+                // a window after the attack
+                let start = SAMPLE_RATE as usize / 4;
+                let window = &waveform.left[start..start + WINDOW_SIZE];
+                for estimator in &mut estimators {
+                    let estimate = estimator
+                        .estimate(window, SAMPLE_RATE)
+                        .unwrap_or_else(|| panic!("no pitch found for {note} on {sound:?}"));
+                    assert!(
+                        estimate.note.is_enharmonic(&note),
+                        "{note} on {sound:?} was taken for {}",
+                        estimate.note
+                    );
+                    assert!(
+                        estimate.cents.abs() <= 15.0,
+                        "{note} on {sound:?} is {} cents off",
+                        estimate.cents
+                    );
+                    assert!(estimate.confidence >= MIN_CONFIDENCE);
+                }
+            }
+        }
+    }
+}
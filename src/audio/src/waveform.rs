@@ -0,0 +1,89 @@
+use core::interfaces::audio::NoteEvent;
+use std::time::Duration;
+
+use rustysynth::Synthesizer;
+use tracing::error;
+
+// how long the notes of the last event may ring out after it
+pub(crate) const RELEASE_TAIL: Duration = Duration::from_millis(500);
+
+// a stereo phrase at the sample rate it is played at
+#[derive(Default)]
+pub(crate) struct Waveform {
+    pub left: Vec<f32>,
+    pub right: Vec<f32>,
+}
+
+impl Waveform {
+    // renders the events back to back into one buffer, so the timing does not
+    // depend on how fast streams can be opened
+    pub fn render(synth: &mut Synthesizer, phrase: &[NoteEvent], velocity: u8) -> Self {
+        let mut waveform = Waveform::default();
+
+        synth.note_off_all(true);
+        for event in phrase {
+            synth.note_off_all(false);
+            for note in &event.notes {
+                match note.to_midi_number() {
+                    Some(key) => synth.note_on(0, key as i32, velocity as i32),
+                    None => {
+                        error!(%note, "Note can not be played because it is outside of the MIDI range")
+                    }
+                }
+            }
+            waveform.extend(synth, event.duration);
+        }
+        synth.note_off_all(false);
+        waveform.extend(synth, RELEASE_TAIL);
+
+        waveform
+    }
+
+    pub fn len(&self) -> usize {
+        self.left.len()
+    }
+
+    pub fn frame(&self, index: usize) -> Option<(f32, f32)> {
+        Some((*self.left.get(index)?, *self.right.get(index)?))
+    }
+
+    pub fn append(&mut self, mut other: Waveform) {
+        self.left.append(&mut other.left);
+        self.right.append(&mut other.right);
+    }
+
+    fn extend(&mut self, synth: &mut Synthesizer, duration: Duration) {
+        let start = self.len();
+        let sample_count = (duration.as_secs_f64() * synth.get_sample_rate() as f64).round();
+        self.left.resize(start + sample_count as usize, 0_f32);
+        self.right.resize(start + sample_count as usize, 0_f32);
+        synth.render(&mut self.left[start..], &mut self.right[start..]);
+    }
+
+    // linear interpolation is enough here, the synthesizer only runs at
+    // another rate than the device if the device rate is out of its range
+    pub fn resample(self, from: u32, to: u32) -> Self {
+        if from == to || self.left.is_empty() {
+            return self;
+        }
+        Waveform {
+            left: resample(&self.left, from, to),
+            right: resample(&self.right, from, to),
+        }
+    }
+}
+
+fn resample(samples: &[f32], from: u32, to: u32) -> Vec<f32> {
+    let ratio = from as f64 / to as f64;
+    let length = (samples.len() as f64 / ratio).round() as usize;
+    let last = samples.len() - 1;
+    (0..length)
+        .map(|index| {
+            let position = index as f64 * ratio;
+            let before = (position.floor() as usize).min(last);
+            let after = (before + 1).min(last);
+            let fraction = (position - before as f64) as f32;
+            samples[before] + (samples[after] - samples[before]) * fraction
+        })
+        .collect()
+}
@@ -0,0 +1,379 @@
+use std::{collections::BTreeSet, fmt};
+
+use anyhow::{Result, bail};
+
+use super::{interval::Interval, notes::Note};
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum ChordQuality {
+    Major,
+    Minor,
+    Diminished,
+    Augmented,
+    DominantSeventh,
+    MajorSeventh,
+    MinorSeventh,
+    HalfDiminished,
+    SuspendedSecond,
+    SuspendedFourth,
+}
+
+impl fmt::Display for ChordQuality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ChordQuality::Major => "major",
+            ChordQuality::Minor => "minor",
+            ChordQuality::Diminished => "diminished",
+            ChordQuality::Augmented => "augmented",
+            ChordQuality::DominantSeventh => "dominant seventh",
+            ChordQuality::MajorSeventh => "major seventh",
+            ChordQuality::MinorSeventh => "minor seventh",
+            ChordQuality::HalfDiminished => "half-diminished seventh",
+            ChordQuality::SuspendedSecond => "suspended second",
+            ChordQuality::SuspendedFourth => "suspended fourth",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl ChordQuality {
+    pub const ALL: [ChordQuality; 10] = [
+        ChordQuality::Major,
+        ChordQuality::Minor,
+        ChordQuality::Diminished,
+        ChordQuality::Augmented,
+        ChordQuality::DominantSeventh,
+        ChordQuality::MajorSeventh,
+        ChordQuality::MinorSeventh,
+        ChordQuality::HalfDiminished,
+        ChordQuality::SuspendedSecond,
+        ChordQuality::SuspendedFourth,
+    ];
+
+    // the intervals of the chord tones above the root
+    pub fn intervals(&self) -> &'static [Interval] {
+        match self {
+            ChordQuality::Major => &[Interval::MAJOR_THIRD, Interval::PERFECT_FIFTH],
+            ChordQuality::Minor => &[Interval::MINOR_THIRD, Interval::PERFECT_FIFTH],
+            ChordQuality::Diminished => &[Interval::MINOR_THIRD, Interval::DIMINISHED_FIFTH],
+            ChordQuality::Augmented => &[Interval::MAJOR_THIRD, Interval::AUGMENTED_FIFTH],
+            ChordQuality::DominantSeventh => &[
+                Interval::MAJOR_THIRD,
+                Interval::PERFECT_FIFTH,
+                Interval::MINOR_SEVENTH,
+            ],
+            ChordQuality::MajorSeventh => &[
+                Interval::MAJOR_THIRD,
+                Interval::PERFECT_FIFTH,
+                Interval::MAJOR_SEVENTH,
+            ],
+            ChordQuality::MinorSeventh => &[
+                Interval::MINOR_THIRD,
+                Interval::PERFECT_FIFTH,
+                Interval::MINOR_SEVENTH,
+            ],
+            ChordQuality::HalfDiminished => &[
+                Interval::MINOR_THIRD,
+                Interval::DIMINISHED_FIFTH,
+                Interval::MINOR_SEVENTH,
+            ],
+            ChordQuality::SuspendedSecond => &[Interval::MAJOR_SECOND, Interval::PERFECT_FIFTH],
+            ChordQuality::SuspendedFourth => &[Interval::PERFECT_FOURTH, Interval::PERFECT_FIFTH],
+        }
+    }
+
+    // number of notes including the root
+    pub fn size(&self) -> usize {
+        self.intervals().len() + 1
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            ChordQuality::Major => "",
+            ChordQuality::Minor => "m",
+            ChordQuality::Diminished => "dim",
+            ChordQuality::Augmented => "aug",
+            ChordQuality::DominantSeventh => "7",
+            ChordQuality::MajorSeventh => "maj7",
+            ChordQuality::MinorSeventh => "m7",
+            ChordQuality::HalfDiminished => "m7b5",
+            ChordQuality::SuspendedSecond => "sus2",
+            ChordQuality::SuspendedFourth => "sus4",
+        }
+    }
+}
+
+// a chord in close position, an inversion of n moves the n lowest notes up an
+// octave
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct Chord {
+    root: Note,
+    quality: ChordQuality,
+    inversion: u8,
+}
+
+impl Chord {
+    pub fn new(root: Note, quality: ChordQuality, inversion: u8) -> Result<Self> {
+        if inversion as usize >= quality.size() {
+            bail!(
+                "A {quality} chord has no inversion {inversion}, it only has {} notes",
+                quality.size()
+            );
+        }
+        Ok(Chord {
+            root,
+            quality,
+            inversion,
+        })
+    }
+
+    pub fn root(&self) -> Note {
+        self.root
+    }
+
+    pub fn quality(&self) -> ChordQuality {
+        self.quality
+    }
+
+    pub fn inversion(&self) -> u8 {
+        self.inversion
+    }
+
+    // the notes from the bass upwards
+    pub fn notes(&self) -> Vec<Note> {
+        let mut notes: Vec<Note> = std::iter::once(self.root)
+            .chain(
+                self.quality
+                    .intervals()
+                    .iter()
+                    .map(|interval| self.root + *interval),
+            )
+            .collect();
+        notes.rotate_left(self.inversion as usize);
+        let upper = notes.len() - self.inversion as usize;
+        for note in &mut notes[upper..] {
+            *note = *note + Interval::PERFECT_OCTAVE;
+        }
+        notes
+    }
+
+    pub fn bass(&self) -> Note {
+        self.notes()[0]
+    }
+
+    // whether the chord sounds like the given quality on any of its notes, sus2
+    // and sus4 or the inversions of an augmented triad can't be told apart
+    pub fn sounds_like(&self, quality: ChordQuality) -> bool {
+        let played = pitch_classes(&self.notes());
+        self.notes().into_iter().any(|root| {
+            Chord::new(root, quality, 0).is_ok_and(|chord| played == pitch_classes(&chord.notes()))
+        })
+    }
+
+    pub fn name(&self) -> String {
+        let inversion = match self.inversion {
+            0 => "",
+            1 => ", first inversion",
+            2 => ", second inversion",
+            _ => ", third inversion",
+        };
+        format!("{} {}{inversion}", pitch_name(&self.root), self.quality)
+    }
+}
+
+// the note without its octave
+fn pitch_name(note: &Note) -> String {
+    format!("{}{}", note.letter, note.accidental)
+}
+
+fn pitch_classes(notes: &[Note]) -> BTreeSet<i16> {
+    notes
+        .iter()
+        .map(|note| note.pitch().rem_euclid(12))
+        .collect()
+}
+
+// chord symbol with the bass as slash note for inversions, like Cm7/Eb
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", pitch_name(&self.root), self.quality.symbol())?;
+        if self.inversion > 0 {
+            write!(f, "/{}", pitch_name(&self.bass()))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interfaces::ui::ChordGuess;
+
+    fn note(s: &str) -> Note {
+        s.parse().unwrap()
+    }
+
+    fn chord(root: &str, quality: ChordQuality, inversion: u8) -> Chord {
+        Chord::new(note(root), quality, inversion).unwrap()
+    }
+
+    fn spelled(chord: &Chord) -> String {
+        let notes: Vec<String> = chord.notes().iter().map(Note::to_string).collect();
+        notes.join(" ")
+    }
+
+    #[test]
+    fn chords_have_an_inversion_per_note() {
+        for quality in ChordQuality::ALL {
+            for inversion in 0..quality.size() as u8 {
+                assert!(Chord::new(note("C4"), quality, inversion).is_ok());
+            }
+            let err = Chord::new(note("C4"), quality, quality.size() as u8).unwrap_err();
+            assert!(err.to_string().contains("has no inversion"), "{err}");
+        }
+        assert!(Chord::new(note("C4"), ChordQuality::Major, 3).is_err());
+        assert!(Chord::new(note("C4"), ChordQuality::MajorSeventh, 3).is_ok());
+    }
+
+    #[test]
+    fn inversions_move_the_lowest_notes_up_an_octave() {
+        let minor = chord("B3", ChordQuality::Minor, 1);
+        assert_eq!(minor.notes(), [note("D4"), note("F#4"), note("B4")]);
+        assert_eq!(minor.bass(), note("D4"));
+        assert_eq!(minor.to_string(), "Bm/D");
+        assert_eq!(minor.name(), "B minor, first inversion");
+
+        let seventh = chord("Bb3", ChordQuality::DominantSeventh, 3);
+        assert_eq!(spelled(&seventh), "Ab4 Bb4 D5 F5");
+        assert_eq!(seventh.to_string(), "Bb7/Ab");
+        assert_eq!(seventh.name(), "Bb dominant seventh, third inversion");
+    }
+
+    #[test]
+    fn every_quality_is_pinned_in_every_inversion() {
+        let pinned: [(ChordQuality, &[&str]); 10] = [
+            (ChordQuality::Major, &["C4 E4 G4", "E4 G4 C5", "G4 C5 E5"]),
+            (
+                ChordQuality::Minor,
+                &["C4 Eb4 G4", "Eb4 G4 C5", "G4 C5 Eb5"],
+            ),
+            (
+                ChordQuality::Diminished,
+                &["C4 Eb4 Gb4", "Eb4 Gb4 C5", "Gb4 C5 Eb5"],
+            ),
+            (
+                ChordQuality::Augmented,
+                &["C4 E4 G#4", "E4 G#4 C5", "G#4 C5 E5"],
+            ),
+            (
+                ChordQuality::DominantSeventh,
+                &[
+                    "C4 E4 G4 Bb4",
+                    "E4 G4 Bb4 C5",
+                    "G4 Bb4 C5 E5",
+                    "Bb4 C5 E5 G5",
+                ],
+            ),
+            (
+                ChordQuality::MajorSeventh,
+                &["C4 E4 G4 B4", "E4 G4 B4 C5", "G4 B4 C5 E5", "B4 C5 E5 G5"],
+            ),
+            (
+                ChordQuality::MinorSeventh,
+                &[
+                    "C4 Eb4 G4 Bb4",
+                    "Eb4 G4 Bb4 C5",
+                    "G4 Bb4 C5 Eb5",
+                    "Bb4 C5 Eb5 G5",
+                ],
+            ),
+            (
+                ChordQuality::HalfDiminished,
+                &[
+                    "C4 Eb4 Gb4 Bb4",
+                    "Eb4 Gb4 Bb4 C5",
+                    "Gb4 Bb4 C5 Eb5",
+                    "Bb4 C5 Eb5 Gb5",
+                ],
+            ),
+            (
+                ChordQuality::SuspendedSecond,
+                &["C4 D4 G4", "D4 G4 C5", "G4 C5 D5"],
+            ),
+            (
+                ChordQuality::SuspendedFourth,
+                &["C4 F4 G4", "F4 G4 C5", "G4 C5 F5"],
+            ),
+        ];
+        for (quality, inversions) in pinned {
+            assert_eq!(inversions.len(), quality.size());
+            for (inversion, notes) in inversions.iter().enumerate() {
+                assert_eq!(
+                    spelled(&chord("C4", quality, inversion as u8)),
+                    *notes,
+                    "{quality} in inversion {inversion}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn chords_sound_like_their_own_quality_in_every_inversion() {
+        for quality in ChordQuality::ALL {
+            for inversion in 0..quality.size() as u8 {
+                let played = chord("D4", quality, inversion);
+                assert!(played.sounds_like(quality), "{played}");
+                let others = ChordQuality::ALL.into_iter().filter(|other| {
+                    *other != quality
+                        && !matches!(
+                            (quality, other),
+                            (ChordQuality::SuspendedSecond, ChordQuality::SuspendedFourth)
+                                | (ChordQuality::SuspendedFourth, ChordQuality::SuspendedSecond)
+                        )
+                });
+                for other in others {
+                    assert!(!played.sounds_like(other), "{played} sounds like {other}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn suspended_chords_sound_like_each_other() {
+        // C D G is also G C D, the sus4 chord on its fifth
+        let sus2 = chord("C4", ChordQuality::SuspendedSecond, 0);
+        assert!(sus2.sounds_like(ChordQuality::SuspendedFourth));
+        let sus4 = chord("C4", ChordQuality::SuspendedFourth, 2);
+        assert!(sus4.sounds_like(ChordQuality::SuspendedSecond));
+        assert!(!sus2.sounds_like(ChordQuality::Major));
+        assert!(!sus4.sounds_like(ChordQuality::Minor));
+    }
+
+    #[test]
+    fn augmented_inversions_sound_like_augmented_triads() {
+        // E G# C is the augmented triad on E spelled with B# as C
+        let augmented = chord("C4", ChordQuality::Augmented, 1);
+        assert_eq!(augmented.bass(), note("E4"));
+        assert!(augmented.sounds_like(ChordQuality::Augmented));
+        assert!(!augmented.sounds_like(ChordQuality::Major));
+        // but a major triad is not one
+        let major = chord("C4", ChordQuality::Major, 0);
+        assert!(!major.sounds_like(ChordQuality::Augmented));
+    }
+
+    #[test]
+    fn guesses_count_if_the_chord_sounds_like_them() {
+        let sus2 = chord("F3", ChordQuality::SuspendedSecond, 1);
+        let guess = ChordGuess::new(sus2, ChordQuality::SuspendedFourth);
+        assert!(guess.correct);
+        assert_eq!(guess.chord, sus2);
+        assert_eq!(guess.guessed, ChordQuality::SuspendedFourth);
+        assert!(ChordGuess::new(sus2, ChordQuality::SuspendedSecond).correct);
+        assert!(!ChordGuess::new(sus2, ChordQuality::Major).correct);
+
+        let minor = chord("A3", ChordQuality::MinorSeventh, 2);
+        assert!(ChordGuess::new(minor, ChordQuality::MinorSeventh).correct);
+        assert!(!ChordGuess::new(minor, ChordQuality::HalfDiminished).correct);
+        assert!(!ChordGuess::new(minor, ChordQuality::Minor).correct);
+    }
+}
@@ -0,0 +1,394 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{Context, Result, bail};
+
+use super::notes::{KeySignature, Note};
+
+// costs of the alignment, a wrong pitch weighs more than a wrong rhythm so a
+// note with both right is never traded for a note with the right length only
+const PITCH_COST: u32 = 2;
+const RHYTHM_COST: u32 = 1;
+const GAP_COST: u32 = 2;
+
+// a note of a melody, its length is counted in beats
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct MelodyNote {
+    pub note: Note,
+    pub beats: u8,
+}
+
+impl MelodyNote {
+    pub fn new(note: Note, beats: u8) -> Self {
+        Self { note, beats }
+    }
+}
+
+impl fmt::Display for MelodyNote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.note, self.beats)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Melody {
+    key: KeySignature,
+    notes: Vec<MelodyNote>,
+}
+
+impl Melody {
+    pub fn new(key: KeySignature, notes: Vec<MelodyNote>) -> Self {
+        Self { key, notes }
+    }
+
+    pub fn key(&self) -> KeySignature {
+        self.key
+    }
+
+    pub fn notes(&self) -> &[MelodyNote] {
+        &self.notes
+    }
+
+    pub fn len(&self) -> usize {
+        self.notes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+}
+
+// a note given by the player, the length is only known if it was typed
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct AnswerNote {
+    pub note: Note,
+    pub beats: Option<u8>,
+}
+
+impl AnswerNote {
+    pub fn new(note: Note, beats: Option<u8>) -> Self {
+        Self { note, beats }
+    }
+}
+
+impl fmt::Display for AnswerNote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.beats {
+            Some(beats) => write!(f, "{}:{beats}", self.note),
+            None => write!(f, "{}", self.note),
+        }
+    }
+}
+
+// a note with an optional length in beats, like C4 or C4:2
+impl FromStr for AnswerNote {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (note, beats) = match s.split_once(':') {
+            Some((note, beats)) => {
+                let beats: u8 = beats
+                    .parse()
+                    .with_context(|| format!("'{beats}' is not a number of beats"))?;
+                if beats == 0 {
+                    bail!("A note has to last at least one beat");
+                }
+                (note, Some(beats))
+            }
+            None => (s, None),
+        };
+        Ok(AnswerNote::new(note.parse()?, beats))
+    }
+}
+
+// whitespace separated notes, commas are left to the Helmholtz octave marks
+pub fn parse_answer(s: &str) -> Result<Vec<AnswerNote>> {
+    s.split_whitespace()
+        .enumerate()
+        .map(|(index, token)| {
+            token
+                .parse()
+                .with_context(|| format!("Note {} ('{token}') is invalid", index + 1))
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum AlignedNote {
+    // the pitch is right, the rhythm is unknown if the answer had no lengths
+    Correct {
+        target: MelodyNote,
+        answer: AnswerNote,
+        rhythm_correct: Option<bool>,
+    },
+    WrongPitch {
+        target: MelodyNote,
+        answer: AnswerNote,
+    },
+    Missing {
+        target: MelodyNote,
+    },
+    Extra {
+        answer: AnswerNote,
+    },
+}
+
+impl AlignedNote {
+    pub fn is_correct(&self) -> bool {
+        matches!(
+            self,
+            AlignedNote::Correct {
+                rhythm_correct: None | Some(true),
+                ..
+            }
+        )
+    }
+}
+
+// the answer aligned note by note to the melody with the least edit distance,
+// enharmonic spellings count as the same pitch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alignment {
+    notes: Vec<AlignedNote>,
+    distance: u32,
+}
+
+impl Alignment {
+    pub fn new(target: &[MelodyNote], answer: &[AnswerNote], compare_rhythm: bool) -> Self {
+        let substitution = |t: &MelodyNote, a: &AnswerNote| {
+            let pitch = if t.note.is_enharmonic(&a.note) {
+                0
+            } else {
+                PITCH_COST
+            };
+            let rhythm = match a.beats {
+                Some(beats) if compare_rhythm && beats != t.beats => RHYTHM_COST,
+                _ => 0,
+            };
+            pitch + rhythm
+        };
+
+        // distances[i][j] aligns the first i target notes with the first j answers
+        let mut distances = vec![vec![0; answer.len() + 1]; target.len() + 1];
+        for (i, row) in distances.iter_mut().enumerate() {
+            row[0] = i as u32 * GAP_COST;
+        }
+        for (j, distance) in distances[0].iter_mut().enumerate() {
+            *distance = j as u32 * GAP_COST;
+        }
+        for i in 1..=target.len() {
+            for j in 1..=answer.len() {
+                distances[i][j] = (distances[i - 1][j - 1]
+                    + substitution(&target[i - 1], &answer[j - 1]))
+                .min(distances[i - 1][j] + GAP_COST)
+                .min(distances[i][j - 1] + GAP_COST);
+            }
+        }
+
+        let mut notes = Vec::new();
+        let (mut i, mut j) = (target.len(), answer.len());
+        while i > 0 || j > 0 {
+            if i > 0
+                && j > 0
+                && distances[i][j]
+                    == distances[i - 1][j - 1] + substitution(&target[i - 1], &answer[j - 1])
+            {
+                let (t, a) = (target[i - 1], answer[j - 1]);
+                if t.note.is_enharmonic(&a.note) {
+                    let rhythm_correct = a.beats.filter(|_| compare_rhythm).map(|b| b == t.beats);
+                    notes.push(AlignedNote::Correct {
+                        target: t,
+                        answer: a,
+                        rhythm_correct,
+                    });
+                } else {
+                    notes.push(AlignedNote::WrongPitch {
+                        target: t,
+                        answer: a,
+                    });
+                }
+                i -= 1;
+                j -= 1;
+            } else if i > 0 && distances[i][j] == distances[i - 1][j] + GAP_COST {
+                notes.push(AlignedNote::Missing {
+                    target: target[i - 1],
+                });
+                i -= 1;
+            } else {
+                notes.push(AlignedNote::Extra {
+                    answer: answer[j - 1],
+                });
+                j -= 1;
+            }
+        }
+        notes.reverse();
+
+        Self {
+            notes,
+            distance: distances[target.len()][answer.len()],
+        }
+    }
+
+    pub fn notes(&self) -> &[AlignedNote] {
+        &self.notes
+    }
+
+    pub fn distance(&self) -> u32 {
+        self.distance
+    }
+
+    pub fn correct_count(&self) -> usize {
+        self.notes.iter().filter(|note| note.is_correct()).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interfaces::ui::DictationGuess;
+
+    fn note(s: &str) -> Note {
+        s.parse().unwrap()
+    }
+
+    // C4 D4 and a long E4
+    fn melody() -> Melody {
+        Melody::new(
+            KeySignature::default(),
+            vec![
+                MelodyNote::new(note("C4"), 1),
+                MelodyNote::new(note("D4"), 1),
+                MelodyNote::new(note("E4"), 2),
+            ],
+        )
+    }
+
+    fn dictate(answer: &str, compare_rhythm: bool) -> DictationGuess {
+        DictationGuess::new(melody(), parse_answer(answer).unwrap(), compare_rhythm)
+    }
+
+    fn target(index: usize) -> MelodyNote {
+        melody().notes()[index]
+    }
+
+    fn answer(s: &str) -> AnswerNote {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn answers_are_parsed_with_optional_lengths() {
+        assert_eq!(
+            parse_answer(" C4  D#4:2\tEb4:12 ").unwrap(),
+            [
+                AnswerNote::new(note("C4"), None),
+                AnswerNote::new(note("D#4"), Some(2)),
+                AnswerNote::new(note("Eb4"), Some(12)),
+            ]
+        );
+        assert!(parse_answer("").unwrap().is_empty());
+
+        let err = parse_answer("C4 X4").unwrap_err();
+        assert!(err.to_string().contains("Note 2 ('X4')"), "{err}");
+        assert!(parse_answer("C4:0").is_err());
+        assert!(parse_answer("C4:x").is_err());
+        assert!(parse_answer("C4:").is_err());
+    }
+
+    #[test]
+    fn exact_answers_are_correct() {
+        let guess = dictate("C4:1 D4:1 E4:2", true);
+        assert!(guess.correct);
+        assert_eq!(guess.alignment.distance(), 0);
+        assert_eq!(guess.alignment.correct_count(), 3);
+        assert_eq!(
+            guess.alignment.notes()[2],
+            AlignedNote::Correct {
+                target: target(2),
+                answer: answer("E4:2"),
+                rhythm_correct: Some(true),
+            }
+        );
+    }
+
+    #[test]
+    fn enharmonic_answers_are_correct() {
+        let guess = dictate("B#3 Ebb4 Fb4", true);
+        assert!(guess.correct);
+        assert_eq!(guess.alignment.distance(), 0);
+        assert!(guess.alignment.notes().iter().all(AlignedNote::is_correct));
+    }
+
+    #[test]
+    fn missing_extra_and_wrong_notes_are_found() {
+        let guess = dictate("C4 E4", true);
+        assert!(!guess.correct);
+        assert_eq!(guess.alignment.distance(), GAP_COST);
+        assert_eq!(
+            guess.alignment.notes()[1],
+            AlignedNote::Missing { target: target(1) }
+        );
+        assert_eq!(guess.alignment.correct_count(), 2);
+
+        let guess = dictate("C4 D4 F4 E4", true);
+        assert_eq!(guess.alignment.distance(), GAP_COST);
+        assert_eq!(
+            guess.alignment.notes()[2],
+            AlignedNote::Extra {
+                answer: answer("F4")
+            }
+        );
+        // every note of the melody was found, but there is one too many
+        assert_eq!(guess.alignment.correct_count(), 3);
+        assert!(!guess.correct);
+
+        let guess = dictate("C4 D#4 E4", true);
+        assert!(!guess.correct);
+        assert_eq!(guess.alignment.distance(), PITCH_COST);
+        assert_eq!(guess.alignment.notes().len(), 3);
+        assert_eq!(
+            guess.alignment.notes()[1],
+            AlignedNote::WrongPitch {
+                target: target(1),
+                answer: answer("D#4")
+            }
+        );
+    }
+
+    #[test]
+    fn rhythm_only_counts_if_it_is_compared_and_given() {
+        let guess = dictate("C4:1 D4:2 E4:2", true);
+        assert!(!guess.correct);
+        assert_eq!(guess.alignment.distance(), RHYTHM_COST);
+        assert_eq!(
+            guess.alignment.notes()[1],
+            AlignedNote::Correct {
+                target: target(1),
+                answer: answer("D4:2"),
+                rhythm_correct: Some(false),
+            }
+        );
+        assert_eq!(guess.alignment.correct_count(), 2);
+
+        let guess = dictate("C4:1 D4:2 E4:2", false);
+        assert!(guess.correct);
+        assert_eq!(guess.alignment.distance(), 0);
+        assert!(guess.alignment.notes().iter().all(|aligned| matches!(
+            aligned,
+            AlignedNote::Correct {
+                rhythm_correct: None,
+                ..
+            }
+        )));
+
+        // without lengths the rhythm is unknown even if it is compared
+        let guess = dictate("C4 D4 E4", true);
+        assert!(guess.correct);
+        assert_eq!(
+            guess.alignment.notes()[0],
+            AlignedNote::Correct {
+                target: target(0),
+                answer: answer("C4"),
+                rhythm_correct: None,
+            }
+        );
+    }
+}
@@ -0,0 +1,329 @@
+use std::{collections::BTreeSet, fmt, str::FromStr};
+
+use anyhow::{Context, Result, bail};
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+
+use super::{
+    notes::{KeySignature, Note},
+    random::{Accidentals, NotePool, RandomNoteSequence, RandomNoteSequenceConfig},
+};
+
+const SHARE_CODE_VERSION: u8 = 1;
+// crockford's base32, it leaves out letters that look like digits
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const GROUP_LENGTH: usize = 4;
+
+// a drill that plays the same note tuples for everyone who starts it, it is
+// passed around as a share code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exercise {
+    seed: u64,
+    config: RandomNoteSequenceConfig,
+}
+
+impl Exercise {
+    pub fn new(seed: u64, config: RandomNoteSequenceConfig) -> Self {
+        Self { seed, config }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn config(&self) -> &RandomNoteSequenceConfig {
+        &self.config
+    }
+
+    pub fn note_sequence(&self) -> RandomNoteSequence {
+        RandomNoteSequence::with_config(ChaCha12Rng::seed_from_u64(self.seed), self.config.clone())
+    }
+
+    pub fn share_code(&self) -> String {
+        let mut bytes = vec![SHARE_CODE_VERSION];
+        bytes.extend(self.seed.to_be_bytes());
+        // only the pitch of the range matters, the pool decides the spelling
+        bytes.extend([
+            self.config.lowest.to_midi_number().unwrap_or_default(),
+            self.config.highest.to_midi_number().unwrap_or(127),
+            self.config.min_offset,
+            self.config.max_offset,
+            self.config.ascending,
+            self.config.allow_unison as u8,
+        ]);
+        match &self.config.pool {
+            NotePool::Accidentals(accidentals) => {
+                let index = Accidentals::ALL
+                    .iter()
+                    .position(|known| known == accidentals)
+                    .expect("every accidental pool is listed");
+                bytes.extend([0, index as u8]);
+            }
+            NotePool::PitchClasses(pitch_classes) => {
+                let mask = pitch_classes
+                    .iter()
+                    .filter(|pitch_class| **pitch_class < 12)
+                    .fold(0_u16, |mask, pitch_class| mask | 1 << pitch_class);
+                bytes.push(1);
+                bytes.extend(mask.to_be_bytes());
+            }
+            NotePool::Key(key) => bytes.extend([2, key.fifths() as u8]),
+        }
+        bytes.push(checksum(&bytes));
+
+        let code = encode(&bytes);
+        let groups: Vec<&str> = code
+            .as_bytes()
+            .chunks(GROUP_LENGTH)
+            .map(|group| std::str::from_utf8(group).expect("the alphabet is ascii"))
+            .collect();
+        groups.join("-")
+    }
+}
+
+impl fmt::Display for Exercise {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.share_code())
+    }
+}
+
+impl FromStr for Exercise {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut bytes = decode(s)?;
+        let Some(expected) = bytes.pop() else {
+            bail!("The share code is empty");
+        };
+        if checksum(&bytes) != expected {
+            bail!("The share code has a typo");
+        }
+
+        let mut reader = Reader(&bytes);
+        let version = reader.byte()?;
+        if version != SHARE_CODE_VERSION {
+            bail!("Share codes of version {version} are not supported");
+        }
+        let seed = u64::from_be_bytes(reader.array()?);
+        let [
+            lowest,
+            highest,
+            min_offset,
+            max_offset,
+            ascending,
+            allow_unison,
+        ] = reader.array()?;
+        let pool = match reader.byte()? {
+            0 => {
+                let index = reader.byte()? as usize;
+                NotePool::Accidentals(
+                    *Accidentals::ALL
+                        .get(index)
+                        .context("The share code has an unknown accidental pool")?,
+                )
+            }
+            1 => {
+                let mask = u16::from_be_bytes(reader.array()?);
+                NotePool::PitchClasses(
+                    (0..12)
+                        .filter(|pitch_class| mask & 1 << pitch_class != 0)
+                        .collect::<BTreeSet<u8>>(),
+                )
+            }
+            2 => NotePool::Key(KeySignature::new(reader.byte()? as i8)?),
+            tag => bail!("The share code has an unknown note pool {tag}"),
+        };
+        if !reader.0.is_empty() {
+            bail!("The share code is too long");
+        }
+
+        let config = RandomNoteSequenceConfig {
+            lowest: Note::from_midi_number(lowest)
+                .context("The share code has a lowest note outside of the MIDI range")?,
+            highest: Note::from_midi_number(highest)
+                .context("The share code has a highest note outside of the MIDI range")?,
+            pool,
+            min_offset,
+            max_offset,
+            ascending,
+            allow_unison: allow_unison != 0,
+        };
+        config
+            .validate()
+            .context("The share code describes an exercise that can not be played")?;
+        Ok(Exercise::new(seed, config))
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn byte(&mut self) -> Result<u8> {
+        self.array().map(|[byte]| byte)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let Some((head, rest)) = self.0.split_first_chunk() else {
+            bail!("The share code is too short");
+        };
+        self.0 = rest;
+        Ok(*head)
+    }
+}
+
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes
+        .iter()
+        .fold(0_u8, |sum, byte| sum.rotate_left(3) ^ byte)
+}
+
+fn encode(bytes: &[u8]) -> String {
+    let mut code = String::new();
+    let mut buffer = 0_u16;
+    let mut bits = 0;
+    for byte in bytes {
+        buffer = buffer << 8 | *byte as u16;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            code.push(ALPHABET[(buffer >> bits & 0x1F) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        code.push(ALPHABET[(buffer << (5 - bits) & 0x1F) as usize] as char);
+    }
+    code
+}
+
+// ignores case, dashes and spaces and reads the letters crockford's base32
+// leaves out as the digits they look like
+fn decode(code: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut buffer = 0_u16;
+    let mut bits = 0;
+    for c in code.chars().filter(|c| *c != '-' && !c.is_whitespace()) {
+        let c = match c.to_ascii_uppercase() {
+            'O' => '0',
+            'I' | 'L' => '1',
+            c => c,
+        };
+        let value = ALPHABET
+            .iter()
+            .position(|known| *known as char == c)
+            .with_context(|| format!("'{c}' does not appear in share codes"))?;
+        buffer = buffer << 5 | value as u16;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    // the bits left over pad the last letter and are always zero, otherwise
+    // a typo there would go unnoticed
+    if buffer & ((1 << bits) - 1) != 0 {
+        bail!("The share code has a typo");
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(s: &str) -> Note {
+        s.parse().unwrap()
+    }
+
+    fn exercises() -> Vec<Exercise> {
+        let config = RandomNoteSequenceConfig {
+            lowest: note("A0"),
+            highest: note("C8"),
+            pool: NotePool::PitchClasses(BTreeSet::from([0, 2, 4, 11])),
+            min_offset: 2,
+            max_offset: 24,
+            ascending: 0,
+            allow_unison: false,
+        };
+        let mut exercises = vec![
+            Exercise::new(0, RandomNoteSequenceConfig::default()),
+            Exercise::new(u64::MAX, config.clone()),
+        ];
+        for accidentals in Accidentals::ALL {
+            exercises.push(Exercise::new(
+                42,
+                RandomNoteSequenceConfig {
+                    pool: NotePool::Accidentals(accidentals),
+                    ..config.clone()
+                },
+            ));
+        }
+        for fifths in [-7, 0, 7] {
+            exercises.push(Exercise::new(
+                1234,
+                RandomNoteSequenceConfig {
+                    pool: NotePool::Key(KeySignature::new(fifths).unwrap()),
+                    ascending: 100,
+                    ..config.clone()
+                },
+            ));
+        }
+        exercises
+    }
+
+    #[test]
+    fn share_codes_decode_to_the_same_exercise() {
+        for exercise in exercises() {
+            let code = exercise.share_code();
+            assert_eq!(code.parse::<Exercise>().unwrap(), exercise, "{code}");
+            // typed in by hand
+            let typed = code.to_lowercase().replace('-', " ").replace('0', "o");
+            assert_eq!(typed.parse::<Exercise>().unwrap(), exercise, "{typed}");
+        }
+    }
+
+    #[test]
+    fn share_codes_are_pinned() {
+        let exercise = Exercise::new(7, RandomNoteSequenceConfig::default());
+        assert_eq!(
+            exercise.share_code(),
+            "0400-0000-0000-01RW-8400-RCG1-001P-E"
+        );
+    }
+
+    #[test]
+    fn every_typo_is_caught() {
+        for exercise in exercises() {
+            let code = exercise.share_code();
+            for (index, original) in code.char_indices().filter(|(_, c)| *c != '-') {
+                for replacement in ALPHABET.iter().map(|c| *c as char) {
+                    if replacement == original {
+                        continue;
+                    }
+                    let mut typo = code.clone();
+                    typo.replace_range(index..=index, &replacement.to_string());
+                    assert!(typo.parse::<Exercise>().is_err(), "{typo}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn broken_share_codes_are_rejected() {
+        let code = Exercise::new(7, RandomNoteSequenceConfig::default()).share_code();
+        assert!("".parse::<Exercise>().is_err());
+        assert!(code[..code.len() - 2].parse::<Exercise>().is_err());
+        assert!(format!("{code}0000").parse::<Exercise>().is_err());
+        assert!(
+            code.replacen(char::is_alphanumeric, "U", 1)
+                .parse::<Exercise>()
+                .is_err()
+        );
+
+        // a valid checksum over a config that can't be played
+        let mut bytes = decode(&code).unwrap();
+        bytes.pop();
+        bytes.swap(9, 10);
+        bytes.push(checksum(&bytes));
+        assert!(encode(&bytes).parse::<Exercise>().is_err());
+    }
+}
@@ -0,0 +1,152 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use anyhow::{Context, Result, anyhow, bail};
+
+use crate::interfaces::ui::GameMode;
+
+const MIDI_VALUES: u8 = 128;
+// the finger bass, the built-in soundfont has no other General MIDI preset but
+// the picked bass
+const DEFAULT_PRESET: u8 = 33;
+
+// a preset of the soundfont, bank 0 holds the General MIDI instruments and the
+// other banks variations of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instrument {
+    pub bank: u8,
+    pub preset: u8,
+}
+
+impl Default for Instrument {
+    fn default() -> Self {
+        Self {
+            bank: 0,
+            preset: DEFAULT_PRESET,
+        }
+    }
+}
+
+impl Instrument {
+    pub fn new(bank: u8, preset: u8) -> Result<Self> {
+        if bank >= MIDI_VALUES || preset >= MIDI_VALUES {
+            bail!("Banks and presets go from 0 to 127, got {bank}:{preset}");
+        }
+        Ok(Self { bank, preset })
+    }
+}
+
+impl fmt::Display for Instrument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.bank, self.preset)
+    }
+}
+
+// "bank:preset", or only the preset for bank 0
+impl FromStr for Instrument {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (bank, preset) = s.trim().split_once(':').unwrap_or(("0", s.trim()));
+        let number = |value: &str| {
+            value
+                .trim()
+                .parse::<u8>()
+                .with_context(|| format!("'{s}' is not an instrument like 0:32"))
+        };
+        Instrument::new(number(bank)?, number(preset)?)
+    }
+}
+
+// the instruments that replace the default one in some game modes, e.g. a
+// piano for chords
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ModeInstruments(pub BTreeMap<GameMode, Instrument>);
+
+impl ModeInstruments {
+    pub fn get(&self, mode: GameMode) -> Option<Instrument> {
+        self.0.get(&mode).copied()
+    }
+}
+
+impl fmt::Display for ModeInstruments {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entries: Vec<String> = self
+            .0
+            .iter()
+            .map(|(mode, instrument)| format!("{}={instrument}", mode.name()))
+            .collect();
+        write!(f, "{}", entries.join(", "))
+    }
+}
+
+// "chord=0:0, random=0:32", empty for none
+impl FromStr for ModeInstruments {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (mode, instrument) = entry.split_once('=').ok_or_else(|| {
+                    anyhow!("'{entry}' is not a mode with an instrument like chord=0:0")
+                })?;
+                Ok((mode.trim().parse()?, instrument.parse()?))
+            })
+            .collect::<Result<_>>()
+            .map(ModeInstruments)
+    }
+}
+
+// what the notes are played with, the oscillators need no soundfont and give
+// pure tones for precise pitch drills
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Sound {
+    #[default]
+    SoundFont,
+    Sine,
+    Triangle,
+    Saw,
+    // additive timbres built from a few harmonics
+    Organ,
+    Clarinet,
+}
+
+impl Sound {
+    pub const ALL: [Sound; 6] = [
+        Sound::SoundFont,
+        Sound::Sine,
+        Sound::Triangle,
+        Sound::Saw,
+        Sound::Organ,
+        Sound::Clarinet,
+    ];
+}
+
+impl fmt::Display for Sound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Sound::SoundFont => "soundfont",
+            Sound::Sine => "sine",
+            Sound::Triangle => "triangle",
+            Sound::Saw => "saw",
+            Sound::Organ => "organ",
+            Sound::Clarinet => "clarinet",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Sound {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        Sound::ALL
+            .into_iter()
+            .find(|sound| sound.to_string().eq_ignore_ascii_case(s))
+            .with_context(|| {
+                format!("'{s}' is not one of soundfont, sine, triangle, saw, organ or clarinet")
+            })
+    }
+}
@@ -0,0 +1,550 @@
+use std::{
+    fmt,
+    ops::{Add, Sub},
+    str::FromStr,
+};
+
+use anyhow::{Context, Result, bail};
+
+use super::notes::{Accidental, Note, NoteLetter};
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum IntervalQuality {
+    Diminished,
+    Minor,
+    Perfect,
+    Major,
+    Augmented,
+}
+
+impl fmt::Display for IntervalQuality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            IntervalQuality::Diminished => "diminished",
+            IntervalQuality::Minor => "minor",
+            IntervalQuality::Perfect => "perfect",
+            IntervalQuality::Major => "major",
+            IntervalQuality::Augmented => "augmented",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl IntervalQuality {
+    pub fn abbreviation(&self) -> char {
+        match self {
+            IntervalQuality::Diminished => 'd',
+            IntervalQuality::Minor => 'm',
+            IntervalQuality::Perfect => 'P',
+            IntervalQuality::Major => 'M',
+            IntervalQuality::Augmented => 'A',
+        }
+    }
+}
+
+// an interval by quality and number, a number of 1 is a unison, 8 an octave and
+// everything above a compound interval
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct Interval {
+    quality: IntervalQuality,
+    number: u8,
+}
+
+impl Interval {
+    pub const PERFECT_UNISON: Interval = Interval::perfect(1);
+    pub const MINOR_SECOND: Interval = Interval::minor(2);
+    pub const MAJOR_SECOND: Interval = Interval::major(2);
+    pub const MINOR_THIRD: Interval = Interval::minor(3);
+    pub const MAJOR_THIRD: Interval = Interval::major(3);
+    pub const PERFECT_FOURTH: Interval = Interval::perfect(4);
+    pub const AUGMENTED_FOURTH: Interval = Interval::augmented(4);
+    pub const DIMINISHED_FIFTH: Interval = Interval::diminished(5);
+    pub const PERFECT_FIFTH: Interval = Interval::perfect(5);
+    pub const AUGMENTED_FIFTH: Interval = Interval::augmented(5);
+    pub const MINOR_SIXTH: Interval = Interval::minor(6);
+    pub const MAJOR_SIXTH: Interval = Interval::major(6);
+    pub const AUGMENTED_SIXTH: Interval = Interval::augmented(6);
+    pub const DIMINISHED_SEVENTH: Interval = Interval::diminished(7);
+    pub const MINOR_SEVENTH: Interval = Interval::minor(7);
+    pub const MAJOR_SEVENTH: Interval = Interval::major(7);
+    pub const PERFECT_OCTAVE: Interval = Interval::perfect(8);
+
+    // the intervals up to an octave, with the tritone as augmented fourth
+    pub const SIMPLE: [Interval; 13] = [
+        Interval::PERFECT_UNISON,
+        Interval::MINOR_SECOND,
+        Interval::MAJOR_SECOND,
+        Interval::MINOR_THIRD,
+        Interval::MAJOR_THIRD,
+        Interval::PERFECT_FOURTH,
+        Interval::AUGMENTED_FOURTH,
+        Interval::PERFECT_FIFTH,
+        Interval::MINOR_SIXTH,
+        Interval::MAJOR_SIXTH,
+        Interval::MINOR_SEVENTH,
+        Interval::MAJOR_SEVENTH,
+        Interval::PERFECT_OCTAVE,
+    ];
+
+    pub fn new(quality: IntervalQuality, number: u8) -> Result<Self> {
+        if number == 0 {
+            bail!("Intervals are counted from 1 (unison)");
+        }
+        let interval = Interval { quality, number };
+        let valid = match quality {
+            IntervalQuality::Perfect => interval.is_perfect_class(),
+            IntervalQuality::Minor | IntervalQuality::Major => !interval.is_perfect_class(),
+            IntervalQuality::Diminished => number != 1,
+            IntervalQuality::Augmented => true,
+        };
+        if !valid {
+            bail!("There is no {quality} {}", interval.number_name());
+        }
+        if interval.checked_semitones().is_none() {
+            bail!("The {} is too wide", interval.name());
+        }
+        Ok(interval)
+    }
+
+    const fn perfect(number: u8) -> Self {
+        Interval {
+            quality: IntervalQuality::Perfect,
+            number,
+        }
+    }
+
+    const fn minor(number: u8) -> Self {
+        Interval {
+            quality: IntervalQuality::Minor,
+            number,
+        }
+    }
+
+    const fn major(number: u8) -> Self {
+        Interval {
+            quality: IntervalQuality::Major,
+            number,
+        }
+    }
+
+    const fn diminished(number: u8) -> Self {
+        Interval {
+            quality: IntervalQuality::Diminished,
+            number,
+        }
+    }
+
+    const fn augmented(number: u8) -> Self {
+        Interval {
+            quality: IntervalQuality::Augmented,
+            number,
+        }
+    }
+
+    // the common name for a number of semitones, the tritone is an augmented fourth
+    pub fn from_semitones(semitones: u8) -> Interval {
+        let simple = Interval::SIMPLE[(semitones % 12) as usize];
+        Interval {
+            quality: simple.quality,
+            number: simple.number + 7 * (semitones / 12),
+        }
+    }
+
+    pub fn quality(&self) -> IntervalQuality {
+        self.quality
+    }
+
+    pub fn number(&self) -> u8 {
+        self.number
+    }
+
+    // the number within one octave, a tenth becomes a third
+    pub fn simple_number(&self) -> u8 {
+        (self.number - 1) % 7 + 1
+    }
+
+    pub fn octaves(&self) -> u8 {
+        (self.number - 1) / 7
+    }
+
+    pub fn is_compound(&self) -> bool {
+        self.number > 8
+    }
+
+    fn is_perfect_class(&self) -> bool {
+        matches!(self.simple_number(), 1 | 4 | 5)
+    }
+
+    pub fn semitones(&self) -> u8 {
+        self.checked_semitones()
+            .expect("intervals that don't fit are rejected when they are created")
+    }
+
+    // none for a diminished unison or wider than 255 semitones
+    fn checked_semitones(&self) -> Option<u8> {
+        let base = NoteLetter::from_index(self.simple_number() - 1).semitones();
+        let offset = match (self.quality, self.is_perfect_class()) {
+            (IntervalQuality::Diminished, true) => -1,
+            (IntervalQuality::Diminished, false) => -2,
+            (IntervalQuality::Minor, _) => -1,
+            (IntervalQuality::Perfect | IntervalQuality::Major, _) => 0,
+            (IntervalQuality::Augmented, _) => 1,
+        };
+        u8::try_from(base as i16 + offset + 12 * self.octaves() as i16).ok()
+    }
+
+    // the interval that completes this one to an octave, compound intervals are
+    // reduced to at most an octave first, so a double octave inverts to a
+    // unison. augmented octaves have no inversion, a diminished unison would
+    // be smaller than nothing
+    pub fn invert(&self) -> Option<Interval> {
+        let reduced = match self.number {
+            1 => 1,
+            n => (n - 2) % 7 + 2,
+        };
+        let number = match reduced {
+            1 => 8,
+            n => 9 - n,
+        };
+        let quality = match self.quality {
+            IntervalQuality::Diminished => IntervalQuality::Augmented,
+            IntervalQuality::Minor => IntervalQuality::Major,
+            IntervalQuality::Perfect => IntervalQuality::Perfect,
+            IntervalQuality::Major => IntervalQuality::Minor,
+            IntervalQuality::Augmented => IntervalQuality::Diminished,
+        };
+        Interval::new(quality, number).ok()
+    }
+
+    pub fn name(&self) -> String {
+        format!("{} {}", self.quality, self.number_name())
+    }
+
+    fn number_name(&self) -> String {
+        let name = match self.number {
+            1 => "unison",
+            2 => "second",
+            3 => "third",
+            4 => "fourth",
+            5 => "fifth",
+            6 => "sixth",
+            7 => "seventh",
+            8 => "octave",
+            9 => "ninth",
+            10 => "tenth",
+            11 => "eleventh",
+            12 => "twelfth",
+            13 => "thirteenth",
+            14 => "fourteenth",
+            15 => "double octave",
+            n => {
+                // 11 to 13 take th like every teen, so 111th but 121st
+                let suffix = match (n % 10, n % 100) {
+                    (_, 11..=13) => "th",
+                    (1, _) => "st",
+                    (2, _) => "nd",
+                    (3, _) => "rd",
+                    _ => "th",
+                };
+                return format!("{n}{suffix}");
+            }
+        };
+        name.to_string()
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.quality.abbreviation(), self.number)
+    }
+}
+
+// the abbreviation followed by the number, like M3 or P8
+impl FromStr for Interval {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut chars = s.trim().chars();
+        let quality = match chars.next() {
+            Some('d') => IntervalQuality::Diminished,
+            Some('m') => IntervalQuality::Minor,
+            Some('P') => IntervalQuality::Perfect,
+            Some('M') => IntervalQuality::Major,
+            Some('A') => IntervalQuality::Augmented,
+            _ => bail!("'{s}' does not start with an interval quality"),
+        };
+        let number = chars
+            .as_str()
+            .parse()
+            .with_context(|| format!("'{s}' does not end with an interval number"))?;
+        Interval::new(quality, number)
+    }
+}
+
+// how the two notes of an interval are played
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum IntervalPlayback {
+    Ascending,
+    Descending,
+    Harmonic,
+}
+
+impl fmt::Display for IntervalPlayback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            IntervalPlayback::Ascending => "melodic ascending",
+            IntervalPlayback::Descending => "melodic descending",
+            IntervalPlayback::Harmonic => "harmonic",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl Note {
+    // the spelled interval from this note up to the other one, if it can be
+    // named without doubly augmented or diminished qualities
+    pub fn interval_to(&self, other: &Note) -> Option<Interval> {
+        let (lower, upper) = if self <= other {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let steps = upper.diatonic_steps() - lower.diatonic_steps();
+        let number = u8::try_from(steps + 1).ok()?;
+        let semitones = upper.pitch() - lower.pitch();
+
+        [
+            IntervalQuality::Diminished,
+            IntervalQuality::Minor,
+            IntervalQuality::Perfect,
+            IntervalQuality::Major,
+            IntervalQuality::Augmented,
+        ]
+        .into_iter()
+        .filter_map(|quality| Interval::new(quality, number).ok())
+        .find(|interval| interval.semitones() as i16 == semitones)
+    }
+
+    fn diatonic_steps(&self) -> i16 {
+        self.octave as i16 * 7 + self.letter.index() as i16
+    }
+
+    // moves the letter by the interval number and picks the accidental that
+    // gives the right size, falls back to a sharp spelling if more than a double
+    // accidental would be needed
+    fn transpose(&self, interval: Interval, upwards: bool) -> Note {
+        let direction = if upwards { 1 } else { -1 };
+        let steps = self.diatonic_steps() + direction * (interval.number() as i16 - 1);
+        let letter = NoteLetter::from_index(steps.rem_euclid(7) as u8);
+        let octave = steps.div_euclid(7) as i8;
+
+        let target = self.pitch() + direction * interval.semitones() as i16;
+        let natural = Note::new(letter, Accidental::Natural, octave);
+        let accidental = i8::try_from(target - natural.pitch())
+            .ok()
+            .and_then(Accidental::from_semitones);
+
+        match accidental {
+            Some(accidental) => Note::new(letter, accidental, octave),
+            None => Note::from_midi_number(target.clamp(0, 127) as u8)
+                .expect("the pitch is clamped to the MIDI range"),
+        }
+    }
+}
+
+impl Add<Interval> for Note {
+    type Output = Note;
+
+    fn add(self, interval: Interval) -> Self::Output {
+        self.transpose(interval, true)
+    }
+}
+
+impl Sub<Interval> for Note {
+    type Output = Note;
+
+    fn sub(self, interval: Interval) -> Self::Output {
+        self.transpose(interval, false)
+    }
+}
+
+// the interval between both notes regardless of their order, named by the
+// number of semitones if the spelling has no regular name
+impl Sub<Note> for Note {
+    type Output = Interval;
+
+    fn sub(self, other: Note) -> Self::Output {
+        self.interval_to(&other)
+            .unwrap_or_else(|| Interval::from_semitones(self.distance(&other)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUALITIES: [IntervalQuality; 5] = [
+        IntervalQuality::Diminished,
+        IntervalQuality::Minor,
+        IntervalQuality::Perfect,
+        IntervalQuality::Major,
+        IntervalQuality::Augmented,
+    ];
+
+    fn parse(s: &str) -> Interval {
+        s.parse().unwrap()
+    }
+
+    // every valid interval up to three octaves
+    fn all_intervals() -> impl Iterator<Item = Interval> {
+        (1..=22).flat_map(|number| {
+            QUALITIES
+                .into_iter()
+                .filter_map(move |quality| Interval::new(quality, number).ok())
+        })
+    }
+
+    #[test]
+    fn rejects_intervals_that_do_not_exist() {
+        for s in ["P0", "d1", "m1", "M4", "P3", "m8", "P2", "M15"] {
+            assert!(s.parse::<Interval>().is_err(), "{s}");
+        }
+        // a few octaves beyond what a byte of semitones can hold
+        assert!(Interval::new(IntervalQuality::Perfect, 155).is_err());
+        assert!(Interval::new(IntervalQuality::Perfect, 148).is_ok());
+    }
+
+    #[test]
+    fn semitones_of_every_quality() {
+        for (s, semitones) in [
+            ("P1", 0),
+            ("A1", 1),
+            ("d2", 0),
+            ("m2", 1),
+            ("M2", 2),
+            ("A2", 3),
+            ("d3", 2),
+            ("m3", 3),
+            ("M3", 4),
+            ("A3", 5),
+            ("d4", 4),
+            ("P4", 5),
+            ("A4", 6),
+            ("d5", 6),
+            ("P5", 7),
+            ("A5", 8),
+            ("m6", 8),
+            ("M6", 9),
+            ("A6", 10),
+            ("d7", 9),
+            ("m7", 10),
+            ("M7", 11),
+            ("d8", 11),
+            ("P8", 12),
+            ("A8", 13),
+            ("m9", 13),
+            ("M10", 16),
+            ("P11", 17),
+            ("P12", 19),
+            ("M13", 21),
+            ("M14", 23),
+            ("P15", 24),
+            ("M17", 28),
+            ("P22", 36),
+        ] {
+            assert_eq!(parse(s).semitones(), semitones, "{s}");
+        }
+    }
+
+    #[test]
+    fn compound_intervals_add_octaves() {
+        for simple in all_intervals().filter(|interval| interval.number() <= 8) {
+            if simple.number() == 1 || simple.number() == 8 {
+                continue;
+            }
+            let compound = Interval::new(simple.quality(), simple.number() + 7).unwrap();
+            assert!(compound.is_compound());
+            assert_eq!(compound.semitones(), simple.semitones() + 12, "{compound}");
+            assert_eq!(compound.simple_number(), simple.simple_number());
+        }
+    }
+
+    #[test]
+    fn inversions_complete_the_octave() {
+        for interval in all_intervals().filter(|interval| interval.number() <= 8) {
+            match interval.invert() {
+                Some(inversion) => {
+                    assert_eq!(
+                        interval.semitones() + inversion.semitones(),
+                        12,
+                        "{interval} and {inversion}"
+                    );
+                    assert_eq!(inversion.invert(), Some(interval));
+                }
+                None => assert_eq!(interval, parse("A8")),
+            }
+        }
+    }
+
+    #[test]
+    fn compound_intervals_are_reduced_before_inverting() {
+        for (from, to) in [
+            ("P1", Some("P8")),
+            ("P8", Some("P1")),
+            ("d8", Some("A1")),
+            ("A1", Some("d8")),
+            ("M3", Some("m6")),
+            ("A4", Some("d5")),
+            ("M9", Some("m7")),
+            ("P12", Some("P4")),
+            ("P15", Some("P1")),
+            ("d15", Some("A1")),
+            ("A8", None),
+            ("A15", None),
+        ] {
+            assert_eq!(parse(from).invert(), to.map(parse), "{from}");
+        }
+    }
+
+    #[test]
+    fn wide_intervals_have_ordinal_names() {
+        let name = |number| Interval::new(IntervalQuality::Major, number).map(|i| i.name());
+        assert_eq!(name(9).unwrap(), "major ninth");
+        assert_eq!(name(16).unwrap(), "major 16th");
+        assert_eq!(name(20).unwrap(), "major 20th");
+        assert_eq!(name(21).unwrap(), "major 21st");
+        assert_eq!(name(22).unwrap_err().to_string(), "There is no major 22nd");
+        assert_eq!(name(23).unwrap(), "major 23rd");
+        assert_eq!(name(24).unwrap(), "major 24th");
+        assert_eq!(parse("P22").name(), "perfect 22nd");
+        assert_eq!(parse("M111").name(), "major 111th");
+        assert_eq!(parse("m112").name(), "minor 112th");
+        assert_eq!(parse("P113").name(), "perfect 113th");
+        assert_eq!(parse("m121").name(), "minor 121st");
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for interval in all_intervals() {
+            assert_eq!(interval.to_string().parse::<Interval>().unwrap(), interval);
+        }
+        assert_eq!(parse("M10").name(), "major tenth");
+        assert_eq!(parse("P15").name(), "perfect double octave");
+    }
+
+    #[test]
+    fn notes_are_moved_by_intervals() {
+        let note = |s: &str| s.parse::<Note>().unwrap();
+        for (from, by, up, down) in [
+            ("C4", "M3", "E4", "Ab3"),
+            ("E4", "m3", "G4", "C#4"),
+            ("F4", "A4", "B4", "Cb4"),
+            ("B3", "d5", "F4", "E#3"),
+            ("C4", "M10", "E5", "Ab2"),
+        ] {
+            assert_eq!(note(from) + parse(by), note(up), "{from} + {by}");
+            assert_eq!(note(from) - parse(by), note(down), "{from} - {by}");
+            assert_eq!(note(up) - note(from), parse(by));
+        }
+    }
+}
@@ -1,3 +1,12 @@
+pub mod chord;
+pub mod dictation;
+pub mod exercise;
+pub mod instrument;
+pub mod interval;
 pub mod notes;
+pub mod profile;
 pub mod random;
+pub mod scale;
+pub mod srs;
 pub mod state;
+pub mod statistics;
@@ -2,9 +2,12 @@ use std::{
     cmp::Ordering,
     fmt,
     ops::{Add, Sub},
+    str::FromStr,
 };
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+use anyhow::{Context, Result, bail};
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 pub enum NoteLetter {
     C,
     D,
@@ -30,34 +33,204 @@ impl fmt::Display for NoteLetter {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+impl NoteLetter {
+    pub const ALL: [NoteLetter; 7] = [
+        NoteLetter::C,
+        NoteLetter::D,
+        NoteLetter::E,
+        NoteLetter::F,
+        NoteLetter::G,
+        NoteLetter::A,
+        NoteLetter::B,
+    ];
+
+    // position of the letter within the octave, starting at C
+    pub fn index(&self) -> u8 {
+        match self {
+            NoteLetter::C => 0,
+            NoteLetter::D => 1,
+            NoteLetter::E => 2,
+            NoteLetter::F => 3,
+            NoteLetter::G => 4,
+            NoteLetter::A => 5,
+            NoteLetter::B => 6,
+        }
+    }
+
+    pub fn from_index(index: u8) -> NoteLetter {
+        NoteLetter::ALL[(index % 7) as usize]
+    }
+
+    // semitones of the natural note above C
+    pub fn semitones(&self) -> i8 {
+        match self {
+            NoteLetter::C => 0,
+            NoteLetter::D => 2,
+            NoteLetter::E => 4,
+            NoteLetter::F => 5,
+            NoteLetter::G => 7,
+            NoteLetter::A => 9,
+            NoteLetter::B => 11,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Default)]
 pub enum Accidental {
+    DoubleSharp,
     Sharp,
     Flat,
+    DoubleFlat,
     #[default]
     Natural,
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+impl fmt::Display for Accidental {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let acc = match self {
+            Accidental::DoubleSharp => "##",
+            Accidental::Sharp => "#",
+            Accidental::Flat => "b",
+            Accidental::DoubleFlat => "bb",
+            Accidental::Natural => "",
+        };
+        write!(f, "{acc}")
+    }
+}
+
+impl Accidental {
+    pub fn semitones(&self) -> i8 {
+        match self {
+            Accidental::DoubleSharp => 2,
+            Accidental::Sharp => 1,
+            Accidental::Natural => 0,
+            Accidental::Flat => -1,
+            Accidental::DoubleFlat => -2,
+        }
+    }
+
+    pub fn from_semitones(semitones: i8) -> Option<Accidental> {
+        match semitones {
+            2 => Some(Accidental::DoubleSharp),
+            1 => Some(Accidental::Sharp),
+            0 => Some(Accidental::Natural),
+            -1 => Some(Accidental::Flat),
+            -2 => Some(Accidental::DoubleFlat),
+            _ => None,
+        }
+    }
+}
+
+// number of sharps (positive) or flats (negative) in a key signature
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Default)]
+pub struct KeySignature {
+    fifths: i8,
+}
+
+impl KeySignature {
+    pub fn new(fifths: i8) -> Result<Self> {
+        if !(-7..=7).contains(&fifths) {
+            bail!("A key signature has at most seven sharps or flats, got {fifths}");
+        }
+        Ok(KeySignature { fifths })
+    }
+
+    pub fn fifths(&self) -> i8 {
+        self.fifths
+    }
+
+    pub fn accidental(&self, letter: NoteLetter) -> Accidental {
+        // sharps are added in the order F C G D A E B, flats in the reverse
+        let order_of_sharps = ((letter.index() + 4) * 2) % 7;
+        if self.fifths > 0 && (order_of_sharps as i8) < self.fifths {
+            Accidental::Sharp
+        } else if self.fifths < 0 && ((6 - order_of_sharps) as i8) < -self.fifths {
+            Accidental::Flat
+        } else {
+            Accidental::Natural
+        }
+    }
+
+    // every fifth moves the tonic four letters up, so one sharp is G and one
+    // flat is F
+    pub fn major_tonic(&self) -> (NoteLetter, Accidental) {
+        let letter = NoteLetter::from_index((self.fifths as i16 * 4).rem_euclid(7) as u8);
+        (letter, self.accidental(letter))
+    }
+}
+
+impl fmt::Display for KeySignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (letter, accidental) = self.major_tonic();
+        write!(f, "{letter}{accidental} major")
+    }
+}
+
+// the major key by its tonic, e.g. Eb major
+impl FromStr for KeySignature {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let Some(tonic) = s.strip_suffix("major").map(str::trim) else {
+            bail!("'{s}' is not a major key like Eb major");
+        };
+        let tonic = parse_spelling(tonic)?;
+        (-7..=7)
+            .filter_map(|fifths| KeySignature::new(fifths).ok())
+            .find(|key| key.major_tonic() == tonic)
+            .with_context(|| format!("No key signature has the tonic of {s}"))
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Tuning {
+    concert_a: f32,
+}
+
+impl Tuning {
+    pub const A440: Tuning = Tuning { concert_a: 440.0 };
+    pub const A442: Tuning = Tuning { concert_a: 442.0 };
+    pub const A432: Tuning = Tuning { concert_a: 432.0 };
+
+    pub fn new(concert_a: f32) -> Self {
+        Tuning { concert_a }
+    }
+
+    pub fn concert_a(&self) -> f32 {
+        self.concert_a
+    }
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Tuning::A440
+    }
+}
+
+impl fmt::Display for Tuning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "A4 = {} Hz", self.concert_a)
+    }
+}
+
+// notes compare equal only if they are spelled the same, use `is_enharmonic`
+// to check whether they sound the same
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 pub struct Note {
     pub letter: NoteLetter,
     pub accidental: Accidental,
-    pub octave: u8,
+    pub octave: i8,
 }
 
 impl fmt::Display for Note {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let acc = match self.accidental {
-            Accidental::Sharp => "#",
-            Accidental::Flat => "b",
-            Accidental::Natural => "",
-        };
-        write!(f, "{}{}{}", self.letter, acc, self.octave)
+        write!(f, "{}{}{}", self.letter, self.accidental, self.octave)
     }
 }
 
 impl Note {
-    pub fn new(letter: NoteLetter, accidental: Accidental, octave: u8) -> Self {
+    pub fn new(letter: NoteLetter, accidental: Accidental, octave: i8) -> Self {
         Note {
             letter,
             accidental,
@@ -65,32 +238,36 @@ impl Note {
         }
     }
 
-    // FIXME
-    pub fn to_midi_number(&self) -> u8 {
-        let mut midi_number: i16 = match self.letter {
-            NoteLetter::C => 0,
-            NoteLetter::D => 2,
-            NoteLetter::E => 4,
-            NoteLetter::F => 5,
-            NoteLetter::G => 7,
-            NoteLetter::A => 9,
-            NoteLetter::B => 11,
-        };
-        midi_number += ((self.octave as i16) + 1) * 12;
-
-        match self.accidental {
-            Accidental::Sharp => midi_number += 1,
-            Accidental::Flat => midi_number -= 1,
-            Accidental::Natural => {}
+    pub fn try_new(letter: NoteLetter, accidental: Accidental, octave: i8) -> Result<Self> {
+        let note = Note::new(letter, accidental, octave);
+        if note.to_midi_number().is_none() {
+            bail!("{note} is outside of the MIDI range");
         }
-        midi_number.clamp(0, 127) as u8
+        Ok(note)
+    }
+
+    // semitones above C-1, the octave belongs to the letter so B#3 sounds as C4
+    // and Cb4 as B3
+    pub fn pitch(&self) -> i16 {
+        (self.octave as i16 + 1) * 12
+            + self.letter.semitones() as i16
+            + self.accidental.semitones() as i16
     }
 
-    pub fn from_midi_number(midi_number: u8) -> Self {
-        let midi_number_i16 = midi_number as i16;
+    pub fn to_midi_number(&self) -> Option<u8> {
+        u8::try_from(self.pitch())
+            .ok()
+            .filter(|pitch| *pitch <= 127)
+    }
+
+    // spelled with sharps, none above 127
+    pub fn from_midi_number(midi_number: u8) -> Option<Self> {
+        (midi_number <= 127).then(|| Note::from_pitch(midi_number as i16))
+    }
 
-        let octave = ((midi_number_i16 / 12) - 1) as u8;
-        let pitch_class = midi_number_i16 % 12;
+    fn from_pitch(pitch: i16) -> Self {
+        let octave = (pitch.div_euclid(12) - 1) as i8;
+        let pitch_class = pitch.rem_euclid(12);
 
         let (letter, accidental) = match pitch_class {
             0 => (NoteLetter::C, Accidental::Natural),
@@ -115,15 +292,86 @@ impl Note {
         }
     }
 
-    pub fn distance(&self, note: &Note) -> u8 {
-        let note_value = self.to_midi_number();
-        let other_note_value = note.to_midi_number();
+    pub fn is_enharmonic(&self, note: &Note) -> bool {
+        self.pitch() == note.pitch()
+    }
 
-        if note_value > other_note_value {
-            note_value.saturating_sub(other_note_value)
-        } else {
-            other_note_value.saturating_sub(note_value)
+    // the same pitch spelled with the given letter, if at most a double
+    // accidental is needed
+    pub fn respell(&self, letter: NoteLetter) -> Option<Note> {
+        (self.octave - 1..=self.octave + 1).find_map(|octave| {
+            let natural = Note::new(letter, Accidental::Natural, octave);
+            let difference = self.pitch() - natural.pitch();
+            let accidental = Accidental::from_semitones(i8::try_from(difference).ok()?)?;
+            Some(Note::new(letter, accidental, octave))
+        })
+    }
+
+    // the spelling on a neighbouring letter with the fewest accidentals, a sharp
+    // becomes the flat above and a flat the sharp below
+    pub fn enharmonic_equivalent(&self) -> Note {
+        let above = self.respell(NoteLetter::from_index(self.letter.index() + 1));
+        let below = self.respell(NoteLetter::from_index(self.letter.index() + 6));
+        let candidates = match self.accidental.semitones() {
+            s if s > 0 => [above, below],
+            _ => [below, above],
+        };
+
+        candidates
+            .into_iter()
+            .flatten()
+            .min_by_key(|note| note.accidental.semitones().abs())
+            .unwrap_or(*self)
+    }
+
+    // the spelling that fits the key best, diatonic notes use the letter of the
+    // scale degree and chromatic ones the fewest accidentals, leaning towards
+    // sharps in sharp keys and flats in flat keys
+    pub fn spell_in_key(&self, key: KeySignature) -> Note {
+        NoteLetter::ALL
+            .iter()
+            .filter_map(|letter| self.respell(*letter))
+            .min_by_key(|note| {
+                let in_key = key.accidental(note.letter).semitones();
+                let deviation = note.accidental.semitones() - in_key;
+                let against_key = match key.fifths() {
+                    f if f < 0 => deviation > 0,
+                    _ => deviation < 0,
+                };
+                (
+                    deviation != 0,
+                    note.accidental.semitones().abs(),
+                    against_key,
+                )
+            })
+            .unwrap_or(*self)
+    }
+
+    pub fn frequency(&self, tuning: Tuning) -> f32 {
+        let semitones_from_a4 = self.pitch() as f32 - 69.0;
+        tuning.concert_a * 2_f32.powf(semitones_from_a4 / 12.0)
+    }
+
+    // nearest note to the frequency and how many cents the frequency is above
+    // it, none if the frequency is not positive or no MIDI note is nearest
+    pub fn from_frequency(frequency: f32, tuning: Tuning) -> Option<(Note, f32)> {
+        if !frequency.is_finite() || frequency <= 0.0 {
+            return None;
+        }
+        let midi_number = 69.0 + 12.0 * (frequency / tuning.concert_a).log2();
+        let nearest = midi_number.round();
+        if !(0.0..=127.0).contains(&nearest) {
+            return None;
         }
+        let cents = (midi_number - nearest) * 100.0;
+        Note::from_midi_number(nearest as u8).map(|note| (note, cents))
+    }
+
+    pub fn distance(&self, note: &Note) -> u8 {
+        (self.pitch() - note.pitch())
+            .unsigned_abs()
+            .try_into()
+            .unwrap_or(u8::MAX)
     }
 }
 
@@ -131,9 +379,8 @@ impl Add<u8> for Note {
     type Output = Note;
 
     fn add(self, semitones: u8) -> Self::Output {
-        let current_midi = self.to_midi_number();
-        let new_midi = current_midi.saturating_add(semitones);
-        Note::from_midi_number(new_midi)
+        let new_pitch = (self.pitch() + semitones as i16).clamp(0, 127);
+        Note::from_pitch(new_pitch)
     }
 }
 
@@ -141,9 +388,8 @@ impl Sub<u8> for Note {
     type Output = Note;
 
     fn sub(self, semitones: u8) -> Self::Output {
-        let current_midi = self.to_midi_number();
-        let new_midi = current_midi.saturating_sub(semitones);
-        Note::from_midi_number(new_midi)
+        let new_pitch = (self.pitch() - semitones as i16).clamp(0, 127);
+        Note::from_pitch(new_pitch)
     }
 }
 
@@ -153,8 +399,504 @@ impl PartialOrd for Note {
     }
 }
 
+// orders by pitch first, enharmonic notes by their spelling
 impl Ord for Note {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.to_midi_number().cmp(&other.to_midi_number())
+        self.pitch()
+            .cmp(&other.pitch())
+            .then(self.octave.cmp(&other.octave))
+            .then(self.letter.index().cmp(&other.letter.index()))
+            .then(
+                self.accidental
+                    .semitones()
+                    .cmp(&other.accidental.semitones()),
+            )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseNoteError {
+    Empty,
+    InvalidLetter(char),
+    InvalidAccidental(String),
+    InvalidOctave(String),
+    OutOfRange(Note),
+}
+
+impl fmt::Display for ParseNoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseNoteError::Empty => write!(f, "expected a note but got an empty string"),
+            ParseNoteError::InvalidLetter(c) => {
+                write!(f, "'{c}' is not a note letter, expected one of A-H")
+            }
+            ParseNoteError::InvalidAccidental(acc) => write!(
+                f,
+                "'{acc}' is not an accidental, expected #, x, b, bb, their unicode symbols or is/es"
+            ),
+            ParseNoteError::InvalidOctave(octave) => write!(
+                f,
+                "'{octave}' is not an octave, expected a number or Helmholtz marks (' after lowercase, , after uppercase letters)"
+            ),
+            ParseNoteError::OutOfRange(note) => write!(f, "{note} is outside of the MIDI range"),
+        }
+    }
+}
+
+impl std::error::Error for ParseNoteError {}
+
+// how the letter B is read, in german H is B and B is Bb
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub enum NoteNaming {
+    #[default]
+    English,
+    German,
+}
+
+impl FromStr for NoteLetter {
+    type Err = ParseNoteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.trim().chars();
+        let c = chars.next().ok_or(ParseNoteError::Empty)?;
+        if chars.next().is_some() {
+            return Err(ParseNoteError::InvalidLetter(c));
+        }
+        parse_letter(c, NoteNaming::English).map(|(letter, _)| letter)
+    }
+}
+
+impl FromStr for Accidental {
+    type Err = ParseNoteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        parse_accidentals(s)
+            .filter(|(_, rest)| rest.is_empty())
+            .and_then(|(semitones, _)| Accidental::from_semitones(semitones))
+            .ok_or_else(|| ParseNoteError::InvalidAccidental(s.to_string()))
+    }
+}
+
+// a letter with its accidentals but without an octave, e.g. F#
+pub(crate) fn parse_spelling(s: &str) -> Result<(NoteLetter, Accidental), ParseNoteError> {
+    let s = s.trim();
+    let letter = s.chars().next().ok_or(ParseNoteError::Empty)?;
+    let accidental = &s[letter.len_utf8()..];
+    Ok((
+        letter.to_string().parse()?,
+        if accidental.is_empty() {
+            Accidental::Natural
+        } else {
+            accidental.parse()?
+        },
+    ))
+}
+
+// accepts scientific pitch notation (C#4, Eb-1), unicode and double accidentals
+// (F♯3, Bbb2, Cx4), german suffixes (Fis3, Es4) and Helmholtz notation where
+// uppercase letters without an octave are the great octave (C = C2, C, = C1)
+// and lowercase ones the small octave and above (c = C3, c' = C4)
+impl FromStr for Note {
+    type Err = ParseNoteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Note::parse_with(s, NoteNaming::English)
+    }
+}
+
+impl Note {
+    // the name as it is written with the naming, in german e.g. Fis4, Es3, B2
+    // for Bb2 and H2 for B2
+    pub fn name(&self, naming: NoteNaming) -> String {
+        if naming == NoteNaming::English {
+            return self.to_string();
+        }
+        let suffix = match self.accidental {
+            Accidental::DoubleSharp => "isis",
+            Accidental::Sharp => "is",
+            Accidental::Natural => "",
+            Accidental::Flat => "es",
+            Accidental::DoubleFlat => "eses",
+        };
+        let stem = match (self.letter, self.accidental) {
+            (NoteLetter::B, Accidental::Flat) => "B".to_string(),
+            (NoteLetter::B, _) => format!("H{suffix}"),
+            // As and Es instead of Aes and Ees
+            (NoteLetter::A | NoteLetter::E, Accidental::Flat | Accidental::DoubleFlat) => {
+                format!("{}{}", self.letter, &suffix[1..])
+            }
+            (letter, _) => format!("{letter}{suffix}"),
+        };
+        format!("{stem}{}", self.octave)
+    }
+
+    pub fn parse_with(s: &str, naming: NoteNaming) -> Result<Note, ParseNoteError> {
+        let s = s.trim();
+        let mut chars = s.chars();
+        let first = chars.next().ok_or(ParseNoteError::Empty)?;
+        let (letter, mut implied_semitones) = parse_letter(first, naming)?;
+
+        let accidentals = chars.as_str();
+        let invalid_accidental = || {
+            let octave_marks =
+                |c: char| c.is_ascii_digit() || matches!(c, '-' | '\'' | '’' | '′' | ',');
+            ParseNoteError::InvalidAccidental(
+                accidentals.trim_end_matches(octave_marks).to_string(),
+            )
+        };
+        // in german B already is the flat one, Bb would silently be a double flat
+        if implied_semitones < 0 && accidentals.starts_with(['b', '♭', '𝄫']) {
+            return Err(invalid_accidental());
+        }
+        // As and Es are the german Ab and Eb, other letters take a full es
+        let remaining = match accidentals.strip_prefix('s') {
+            Some(rest) if matches!(letter, NoteLetter::A | NoteLetter::E) => {
+                implied_semitones -= 1;
+                rest
+            }
+            _ => accidentals,
+        };
+        let (semitones, rest) = parse_accidentals(remaining).ok_or_else(invalid_accidental)?;
+        if implied_semitones < 0 && semitones > 0 {
+            return Err(invalid_accidental());
+        }
+        let accidental = Accidental::from_semitones(semitones + implied_semitones)
+            .ok_or_else(invalid_accidental)?;
+
+        let octave = parse_octave(rest, first.is_lowercase())?;
+        let note = Note::new(letter, accidental, octave);
+        if note.to_midi_number().is_none() {
+            return Err(ParseNoteError::OutOfRange(note));
+        }
+        Ok(note)
+    }
+}
+
+fn parse_letter(c: char, naming: NoteNaming) -> Result<(NoteLetter, i8), ParseNoteError> {
+    let letter = match c.to_ascii_uppercase() {
+        'C' => (NoteLetter::C, 0),
+        'D' => (NoteLetter::D, 0),
+        'E' => (NoteLetter::E, 0),
+        'F' => (NoteLetter::F, 0),
+        'G' => (NoteLetter::G, 0),
+        'A' => (NoteLetter::A, 0),
+        'H' => (NoteLetter::B, 0),
+        'B' if naming == NoteNaming::German => (NoteLetter::B, -1),
+        'B' => (NoteLetter::B, 0),
+        _ => return Err(ParseNoteError::InvalidLetter(c)),
+    };
+    Ok(letter)
+}
+
+// sums up all leading accidental symbols and returns the unparsed remainder,
+// mixing sharps and flats is rejected
+fn parse_accidentals(s: &str) -> Option<(i8, &str)> {
+    const SUFFIXES: [(&str, i8); 10] = [
+        ("𝄪", 2),
+        ("𝄫", -2),
+        ("♯", 1),
+        ("♭", -1),
+        ("♮", 0),
+        ("is", 1),
+        ("es", -1),
+        ("#", 1),
+        ("x", 2),
+        ("b", -1),
+    ];
+
+    let mut semitones = 0;
+    let mut directions = (false, false);
+    let mut rest = s;
+    while let Some((suffix, value)) = SUFFIXES.iter().find(|(suffix, _)| rest.starts_with(suffix)) {
+        semitones += value;
+        directions.0 |= *value > 0;
+        directions.1 |= *value < 0;
+        rest = &rest[suffix.len()..];
+    }
+    match directions {
+        (true, true) => None,
+        _ => Some((semitones, rest)),
+    }
+}
+
+fn parse_octave(s: &str, lowercase: bool) -> Result<i8, ParseNoteError> {
+    let invalid = || ParseNoteError::InvalidOctave(s.to_string());
+    if s.is_empty() {
+        return Ok(if lowercase { 3 } else { 2 });
+    }
+    if let Ok(octave) = s.parse::<i8>() {
+        return Ok(octave);
+    }
+
+    let marks = s.chars().count() as i8;
+    if lowercase && s.chars().all(|c| matches!(c, '\'' | '’' | '′')) {
+        Ok(3 + marks)
+    } else if !lowercase && s.chars().all(|c| c == ',') {
+        Ok(2 - marks)
+    } else {
+        Err(invalid())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TUNINGS: [Tuning; 3] = [Tuning::A440, Tuning::A442, Tuning::A432];
+
+    fn note(s: &str) -> Note {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn midi_numbers_follow_the_letter_octave() {
+        for (name, midi_number) in [
+            ("C-1", 0),
+            ("C4", 60),
+            ("B#3", 60),
+            ("Cb4", 59),
+            ("E#4", 65),
+            ("Fb4", 64),
+            ("Bbb3", 57),
+            ("C##4", 62),
+            ("G9", 127),
+        ] {
+            assert_eq!(note(name).to_midi_number(), Some(midi_number), "{name}");
+        }
+        let below = Note::new(NoteLetter::C, Accidental::Flat, -1);
+        let above = Note::new(NoteLetter::G, Accidental::Sharp, 9);
+        assert_eq!(below.to_midi_number(), None);
+        assert_eq!(above.to_midi_number(), None);
+    }
+
+    #[test]
+    fn midi_numbers_round_trip() {
+        for midi_number in 0..=127 {
+            let note = Note::from_midi_number(midi_number).unwrap();
+            assert_eq!(note.to_midi_number(), Some(midi_number));
+        }
+        assert_eq!(Note::from_midi_number(0), Some(note("C-1")));
+        assert_eq!(Note::from_midi_number(128), None);
+        assert_eq!(Note::from_midi_number(u8::MAX), None);
+    }
+
+    #[test]
+    fn spelling_and_pitch_are_compared_apart() {
+        assert_ne!(note("C#4"), note("Db4"));
+        assert!(note("C#4").is_enharmonic(&note("Db4")));
+        assert!(note("B#3").is_enharmonic(&note("C4")));
+        assert!(!note("C4").is_enharmonic(&note("C5")));
+    }
+
+    #[test]
+    fn enharmonic_equivalents_use_the_neighbouring_letters() {
+        for (from, to) in [
+            ("C#4", "Db4"),
+            ("Db4", "C#4"),
+            ("B#3", "C4"),
+            ("C4", "B#3"),
+            ("Cb4", "B3"),
+            ("E#4", "F4"),
+            ("Fb4", "E4"),
+            ("F##4", "G4"),
+            ("Abb4", "G4"),
+            ("E4", "Fb4"),
+        ] {
+            let equivalent = note(from).enharmonic_equivalent();
+            assert_eq!(equivalent, note(to), "{from}");
+            assert!(equivalent.is_enharmonic(&note(from)));
+        }
+    }
+
+    #[test]
+    fn notes_are_spelled_as_in_the_key() {
+        for (fifths, from, to) in [
+            // diatonic notes take the letter of their degree
+            (2, "Db4", "C#4"),
+            (-4, "C#4", "Db4"),
+            (-1, "A#4", "Bb4"),
+            (-6, "B3", "Cb4"),
+            (7, "C4", "B#3"),
+            (0, "Fb4", "E4"),
+            // chromatic ones lean towards the accidentals of the key
+            (0, "Gb4", "F#4"),
+            (3, "Eb4", "D#4"),
+            (-2, "G#4", "Ab4"),
+        ] {
+            let key = KeySignature::new(fifths).unwrap();
+            assert_eq!(note(from).spell_in_key(key), note(to), "{from} in {key}");
+        }
+    }
+
+    // every spelling in the MIDI range
+    fn all_notes() -> impl Iterator<Item = Note> {
+        const ACCIDENTALS: [Accidental; 5] = [
+            Accidental::DoubleFlat,
+            Accidental::Flat,
+            Accidental::Natural,
+            Accidental::Sharp,
+            Accidental::DoubleSharp,
+        ];
+        (-1..=9).flat_map(|octave| {
+            NoteLetter::ALL.into_iter().flat_map(move |letter| {
+                ACCIDENTALS
+                    .into_iter()
+                    .filter_map(move |accidental| Note::try_new(letter, accidental, octave).ok())
+            })
+        })
+    }
+
+    #[test]
+    fn names_round_trip_in_both_namings() {
+        for note in all_notes() {
+            assert_eq!(note.to_string().parse::<Note>(), Ok(note));
+            for naming in [NoteNaming::English, NoteNaming::German] {
+                let name = note.name(naming);
+                assert_eq!(Note::parse_with(&name, naming), Ok(note), "{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn parses_the_notations() {
+        for (s, expected) in [
+            ("C#4", "C#4"),
+            ("Eb-1", "Eb-1"),
+            ("F♯3", "F#3"),
+            ("B♭2", "Bb2"),
+            ("Cx4", "C##4"),
+            ("D𝄪4", "D##4"),
+            ("G𝄫4", "Gbb4"),
+            ("Bbb2", "Bbb2"),
+            ("E♮4", "E4"),
+            ("Fis3", "F#3"),
+            ("Ges3", "Gb3"),
+            ("Es4", "Eb4"),
+            ("As2", "Ab2"),
+            ("Ases2", "Abb2"),
+            ("H3", "B3"),
+            ("C", "C2"),
+            ("C,", "C1"),
+            ("c", "C3"),
+            ("c'", "C4"),
+            ("fis''", "F#5"),
+        ] {
+            assert_eq!(s.parse::<Note>(), Ok(note(expected)), "{s}");
+        }
+    }
+
+    #[test]
+    fn german_b_is_b_flat() {
+        let german = |s: &str| Note::parse_with(s, NoteNaming::German);
+        assert_eq!(german("B3"), Ok(note("Bb3")));
+        assert_eq!(german("b"), Ok(note("Bb3")));
+        assert_eq!(german("H3"), Ok(note("B3")));
+        assert_eq!(german("Heses3"), Ok(note("Bbb3")));
+        assert_eq!(german("His3"), Ok(note("B#3")));
+        assert_eq!(german("Es4"), Ok(note("Eb4")));
+    }
+
+    #[test]
+    fn rejects_ambiguous_accidentals() {
+        for naming in [NoteNaming::English, NoteNaming::German] {
+            for s in ["Cs4", "Fs4", "Bs3", "Asis4", "C#b4", "Eis♭4"] {
+                assert!(Note::parse_with(s, naming).is_err(), "{s} in {naming:?}");
+            }
+        }
+        for s in ["Bb3", "B♭3", "B𝄫3", "Bis3", "bb"] {
+            assert_eq!(
+                Note::parse_with(s, NoteNaming::German),
+                Err(ParseNoteError::InvalidAccidental(
+                    s[1..].trim_end_matches('3').to_string()
+                )),
+                "{s}"
+            );
+        }
+    }
+
+    #[test]
+    fn describes_what_is_wrong() {
+        assert_eq!("".parse::<Note>(), Err(ParseNoteError::Empty));
+        assert_eq!(
+            "X4".parse::<Note>(),
+            Err(ParseNoteError::InvalidLetter('X'))
+        );
+        assert_eq!(
+            "C#b4".parse::<Note>(),
+            Err(ParseNoteError::InvalidAccidental("#b".to_string()))
+        );
+        assert_eq!(
+            "C4x".parse::<Note>(),
+            Err(ParseNoteError::InvalidOctave("4x".to_string()))
+        );
+        assert_eq!(
+            "G#9".parse::<Note>(),
+            Err(ParseNoteError::OutOfRange(Note::new(
+                NoteLetter::G,
+                Accidental::Sharp,
+                9
+            )))
+        );
+    }
+
+    #[test]
+    fn parses_letters_and_accidentals_alone() {
+        assert_eq!("H".parse(), Ok(NoteLetter::B));
+        assert_eq!("d".parse(), Ok(NoteLetter::D));
+        assert_eq!("##".parse(), Ok(Accidental::DoubleSharp));
+        assert_eq!("es".parse(), Ok(Accidental::Flat));
+        assert_eq!("♭♭".parse(), Ok(Accidental::DoubleFlat));
+        assert!("s".parse::<Accidental>().is_err());
+        assert!("#b".parse::<Accidental>().is_err());
+    }
+
+    #[test]
+    fn concert_a_is_the_reference() {
+        for tuning in TUNINGS {
+            let a4 = Note::new(NoteLetter::A, Accidental::Natural, 4);
+            assert_eq!(a4.frequency(tuning), tuning.concert_a());
+        }
+    }
+
+    #[test]
+    fn frequency_round_trips_over_the_midi_range() {
+        for tuning in TUNINGS {
+            for midi_number in 0..=127 {
+                let note = Note::from_midi_number(midi_number).unwrap();
+                let (found, cents) = Note::from_frequency(note.frequency(tuning), tuning)
+                    .unwrap_or_else(|| panic!("{note} is not found at {tuning}"));
+                assert_eq!(found, note, "at {tuning}");
+                assert!(
+                    cents.abs() < 0.01,
+                    "{note} is off by {cents} cents at {tuning}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn cents_measure_the_deviation() {
+        for tuning in TUNINGS {
+            for midi_number in 1..=126 {
+                let note = Note::from_midi_number(midi_number).unwrap();
+                for cents in [-49.0, -25.0, 10.0, 49.0] {
+                    let frequency = note.frequency(tuning) * 2_f32.powf(cents / 1200.0);
+                    let (found, found_cents) = Note::from_frequency(frequency, tuning).unwrap();
+                    assert_eq!(found, note, "at {cents} cents and {tuning}");
+                    assert!((found_cents - cents).abs() < 0.01, "{note} at {tuning}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_frequencies_are_rejected() {
+        for frequency in [0.0, -440.0, f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            assert_eq!(Note::from_frequency(frequency, Tuning::A440), None);
+        }
+        // far below C-1 and above G9
+        assert_eq!(Note::from_frequency(1.0, Tuning::A440), None);
+        assert_eq!(Note::from_frequency(20000.0, Tuning::A440), None);
     }
 }
@@ -0,0 +1,247 @@
+use std::{
+    collections::HashMap,
+    ops::RangeInclusive,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{Result, bail};
+
+use crate::interfaces::{
+    audio::{AudioSettings, InputSettings, PitchDetection},
+    ui::{GameMode, NoteGuess},
+};
+
+use super::{
+    exercise::Exercise,
+    instrument::{Instrument, ModeInstruments, Sound},
+    notes::Note,
+    random::{NotePool, RandomNoteSequenceConfig},
+    srs::{Card, NewCards, ReviewState},
+    statistics::RandomModeStatistics,
+};
+
+const NEW_CARDS_PER_DAY: usize = 10;
+const MAX_NEW_CARDS_PER_DAY: usize = 100;
+const OCTAVE_SPANS: RangeInclusive<u8> = 1..=4;
+const TONE_DURATIONS: RangeInclusive<Duration> =
+    Duration::from_millis(100)..=Duration::from_secs(5);
+// the synthesizer bends the pitch by at most a whole tone
+const CONCERT_A: RangeInclusive<u16> = 415..=466;
+const VELOCITY: u8 = 100;
+// 0 would be a note off in MIDI
+const VELOCITIES: RangeInclusive<u8> = 1..=127;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settings {
+    // the range the random and review modes pick notes from
+    pub lowest_note: Note,
+    pub highest_note: Note,
+    // the notes both notes of a pair are drawn from
+    pub pool: NotePool,
+    // how many octaves the second note may lie above or below the first
+    pub octave_span: u8,
+    // the smallest distance between the notes of a pair, in semitones
+    pub min_offset: u8,
+    // how often the second note lies above the first, in percent
+    pub ascending: u8,
+    pub allow_unison: bool,
+    pub tone_duration: Duration,
+    // in percent
+    pub volume: u8,
+    pub sound: Sound,
+    // the built-in bass soundfont if there is none
+    pub soundfont: Option<PathBuf>,
+    // the preset the notes are played with, unless the game mode has its own
+    pub instrument: Instrument,
+    pub mode_instruments: ModeInstruments,
+    pub velocity: u8,
+    // the frequency of A4 in Hz, for playback and note detection
+    pub concert_a: u16,
+    pub pitch_detection: PitchDetection,
+    // devices by name, the system default if there is none
+    pub input_device: Option<String>,
+    pub output_device: Option<String>,
+    // unseen note pairs the review mode introduces per day
+    pub new_cards_per_day: usize,
+    // makes every session repeatable, random mode turns into a fixed exercise
+    pub seed: Option<u64>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        let notes = RandomNoteSequenceConfig::default();
+        Self {
+            lowest_note: notes.lowest,
+            highest_note: notes.highest,
+            pool: notes.pool,
+            octave_span: 1,
+            min_offset: notes.min_offset,
+            ascending: notes.ascending,
+            allow_unison: notes.allow_unison,
+            tone_duration: Duration::from_secs(1),
+            volume: 50,
+            sound: Sound::default(),
+            soundfont: None,
+            instrument: Instrument::default(),
+            mode_instruments: ModeInstruments::default(),
+            velocity: VELOCITY,
+            concert_a: 440,
+            pitch_detection: PitchDetection::default(),
+            input_device: None,
+            output_device: None,
+            new_cards_per_day: NEW_CARDS_PER_DAY,
+            seed: None,
+        }
+    }
+}
+
+impl Settings {
+    pub fn validate(&self) -> Result<()> {
+        self.note_sequence().validate()?;
+        if !OCTAVE_SPANS.contains(&self.octave_span) {
+            bail!(
+                "The octave span has to be between {} and {}, got {}",
+                OCTAVE_SPANS.start(),
+                OCTAVE_SPANS.end(),
+                self.octave_span
+            );
+        }
+        if !TONE_DURATIONS.contains(&self.tone_duration) {
+            bail!(
+                "The tone duration has to be between {} and {} ms, got {}",
+                TONE_DURATIONS.start().as_millis(),
+                TONE_DURATIONS.end().as_millis(),
+                self.tone_duration.as_millis()
+            );
+        }
+        if self.volume > 100 {
+            bail!("The volume is at most 100%, got {}%", self.volume);
+        }
+        if !VELOCITIES.contains(&self.velocity) {
+            bail!(
+                "The velocity has to be between {} and {}, got {}",
+                VELOCITIES.start(),
+                VELOCITIES.end(),
+                self.velocity
+            );
+        }
+        if !CONCERT_A.contains(&self.concert_a) {
+            bail!(
+                "Concert A has to be between {} and {} Hz, got {}",
+                CONCERT_A.start(),
+                CONCERT_A.end(),
+                self.concert_a
+            );
+        }
+        if self.new_cards_per_day > MAX_NEW_CARDS_PER_DAY {
+            bail!(
+                "At most {MAX_NEW_CARDS_PER_DAY} new cards can be introduced per day, got {}",
+                self.new_cards_per_day
+            );
+        }
+        Ok(())
+    }
+
+    pub fn note_sequence(&self) -> RandomNoteSequenceConfig {
+        RandomNoteSequenceConfig {
+            lowest: self.lowest_note,
+            highest: self.highest_note,
+            pool: self.pool.clone(),
+            min_offset: self.min_offset,
+            max_offset: self.octave_span * 12,
+            ascending: self.ascending,
+            allow_unison: self.allow_unison,
+        }
+    }
+
+    // with the instrument of the game mode that is played, if any
+    pub fn audio(&self, mode: Option<GameMode>) -> AudioSettings {
+        let instrument = mode
+            .and_then(|mode| self.mode_instruments.get(mode))
+            .unwrap_or(self.instrument);
+        AudioSettings {
+            output_device: self.output_device.clone(),
+            sound: self.sound,
+            soundfont: self.soundfont.clone(),
+            instrument,
+            velocity: self.velocity,
+            volume: self.volume,
+            concert_a: self.concert_a,
+        }
+    }
+
+    pub fn input(&self) -> InputSettings {
+        InputSettings {
+            input_device: self.input_device.clone(),
+            concert_a: self.concert_a,
+            pitch_detection: self.pitch_detection,
+        }
+    }
+}
+
+// the progress that carries over from one session to the next
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Profile {
+    pub statistics: RandomModeStatistics,
+    pub deck: HashMap<Card, ReviewState>,
+    pub new_cards: NewCards,
+}
+
+// a single answer in the random or review mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuessRecord {
+    pub reference: Note,
+    pub divergence: Note,
+    pub played: Note,
+    pub correct: bool,
+    pub at: SystemTime,
+    pub response_time: Duration,
+}
+
+impl From<&NoteGuess> for GuessRecord {
+    fn from(note_guess: &NoteGuess) -> Self {
+        Self {
+            reference: note_guess.true_note_tuple.reference(),
+            divergence: note_guess.true_note_tuple.divergence(),
+            played: note_guess.note_played,
+            correct: note_guess.correct,
+            at: SystemTime::now(),
+            response_time: note_guess.response_time,
+        }
+    }
+}
+
+// everything that happened from starting a game mode until leaving it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub mode: GameMode,
+    // the game mode was seeded with it, sessions from older versions have none
+    pub seed: Option<u64>,
+    // the exercise that was played, if it was one
+    pub exercise: Option<Exercise>,
+    pub started_at: SystemTime,
+    pub ended_at: Option<SystemTime>,
+    pub guesses: Vec<GuessRecord>,
+}
+
+impl Session {
+    pub fn new(mode: GameMode, seed: u64, exercise: Option<Exercise>) -> Self {
+        Self {
+            mode,
+            seed: Some(seed),
+            exercise,
+            started_at: SystemTime::now(),
+            ended_at: None,
+            guesses: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, note_guess: &NoteGuess) {
+        self.guesses.push(GuessRecord::from(note_guess));
+    }
+
+    pub fn end(&mut self) {
+        self.ended_at.get_or_insert_with(SystemTime::now);
+    }
+}
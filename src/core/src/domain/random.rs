@@ -1,19 +1,33 @@
-use rand::{distr::StandardUniform, prelude::*};
+use rand::{
+    distr::{StandardUniform, weighted::WeightedIndex},
+    prelude::*,
+};
 use rand_chacha::ChaCha12Rng;
-use std::cmp::{max, min};
+use std::{collections::BTreeSet, fmt, ops::RangeInclusive, str::FromStr};
 
+use anyhow::{Context, Result, bail};
 use tracing::debug;
 
-use super::notes::{Accidental, Note, NoteLetter};
+use super::{
+    chord::{Chord, ChordQuality},
+    dictation::{Melody, MelodyNote},
+    interval::Interval,
+    notes::{Accidental, KeySignature, Note, NoteLetter, parse_spelling},
+    scale::{Scale, ScaleKind, ScaleRun},
+    statistics::RandomModeStatistics,
+};
 
+// a source of exercises, either a single pair of notes or a whole run
 pub trait NoteSequence: Send {
-    fn next_note(&mut self) -> NoteTuple;
+    type Item;
+
+    fn next_note(&mut self) -> Self::Item;
 }
 
 impl Distribution<Note> for StandardUniform {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Note {
-        let letter: NoteLetter = rand::random();
-        let accidental: Accidental = rand::random();
+        let letter: NoteLetter = rng.random();
+        let accidental: Accidental = rng.random();
         let octave = rng.random_range(1..=4);
 
         Note {
@@ -59,9 +73,9 @@ pub struct NoteTuple {
 }
 
 impl NoteTuple {
-    fn try_new(ref_note: Note, div_note: Note) -> NoteTuple {
-        let diff = (ref_note.to_midi_number() as i8 - div_note.to_midi_number() as i8).abs();
-        debug!("Reference: {ref_note}, divergence: {div_note}. Difference: {diff}");
+    pub(crate) fn new(ref_note: Note, div_note: Note) -> NoteTuple {
+        let interval = div_note - ref_note;
+        debug!("Reference: {ref_note}, divergence: {div_note}. Interval: {interval}");
 
         NoteTuple {
             reference: ref_note,
@@ -76,33 +90,785 @@ impl NoteTuple {
     pub fn divergence(&self) -> Note {
         self.divergence
     }
+
+    pub fn interval(&self) -> Interval {
+        self.divergence - self.reference
+    }
+
+    pub fn is_ascending(&self) -> bool {
+        self.divergence >= self.reference
+    }
+}
+
+// which pairs of notes the random note sequences may pick, both notes lie
+// within the range and come from the pool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RandomNoteSequenceConfig {
+    pub lowest: Note,
+    pub highest: Note,
+    pub pool: NotePool,
+    // semitones between the two notes, either way
+    pub min_offset: u8,
+    pub max_offset: u8,
+    // how often the divergence lies above the reference, in percent
+    pub ascending: u8,
+    pub allow_unison: bool,
+}
+
+impl Default for RandomNoteSequenceConfig {
+    // the range of a bass
+    fn default() -> Self {
+        Self {
+            lowest: Note::new(NoteLetter::E, Accidental::Natural, 1),
+            highest: Note::new(NoteLetter::F, Accidental::Natural, 4),
+            pool: NotePool::default(),
+            min_offset: 0,
+            max_offset: 12,
+            ascending: 50,
+            allow_unison: true,
+        }
+    }
+}
+
+impl RandomNoteSequenceConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.lowest.pitch() >= self.highest.pitch() {
+            bail!(
+                "The lowest note {} has to be below the highest note {}",
+                self.lowest,
+                self.highest
+            );
+        }
+        if self.min_offset > self.max_offset {
+            bail!(
+                "The smallest offset {} is larger than the largest one {}",
+                self.min_offset,
+                self.max_offset
+            );
+        }
+        if self.ascending > 100 {
+            bail!(
+                "At most 100% of the notes can be ascending, got {}%",
+                self.ascending
+            );
+        }
+        if self.references().is_empty() {
+            bail!(
+                "No pair of notes from the pool {} fits between {} and {}",
+                self.pool,
+                self.lowest,
+                self.highest
+            );
+        }
+        Ok(())
+    }
+
+    // every spelling within the range that the pool allows and that at least
+    // one divergence can be paired with
+    pub fn references(&self) -> Vec<Note> {
+        (self.lowest.pitch()..=self.highest.pitch())
+            .flat_map(|pitch| self.pool.spellings(pitch))
+            .filter(|note| !self.offsets(*note).is_empty())
+            .collect()
+    }
+
+    // the offsets that keep the divergence within the range and the pool,
+    // weighted by the direction
+    fn offsets(&self, reference: Note) -> Vec<(i8, f64)> {
+        let max_offset = self.max_offset.min(i8::MAX as u8) as i8;
+        (-max_offset..=max_offset)
+            .filter(|offset| offset.unsigned_abs() >= self.min_offset)
+            .filter(|offset| *offset != 0 || self.allow_unison)
+            .filter_map(|offset| {
+                let weight = match offset {
+                    o if o > 0 => self.ascending as f64,
+                    o if o < 0 => (100 - self.ascending.min(100)) as f64,
+                    _ => 50.0,
+                };
+                let pitch = reference.pitch() + offset as i16;
+                let fits = pitch >= self.lowest.pitch()
+                    && pitch <= self.highest.pitch()
+                    && !self.pool.spellings(pitch).is_empty();
+                (weight > 0.0 && fits).then_some((offset, weight))
+            })
+            .collect()
+    }
+
+    // the reference moved by the offset, spelled the way the pool spells it
+    fn divergence(&self, ref_note: Note, offset: i8) -> Note {
+        let pitch = ref_note.pitch() + offset as i16;
+        self.pool
+            .spellings(pitch)
+            .first()
+            .copied()
+            .or_else(|| Note::from_midi_number(pitch.clamp(0, 127) as u8))
+            .expect("the pitch is clamped to the MIDI range")
+    }
+}
+
+// the notes a random note sequence draws from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotePool {
+    // every pitch, spelled with the accidentals
+    Accidentals(Accidentals),
+    // only these pitch classes, counted in semitones above C
+    PitchClasses(BTreeSet<u8>),
+    // the notes of the key, spelled as in the key
+    Key(KeySignature),
+}
+
+impl Default for NotePool {
+    fn default() -> Self {
+        NotePool::Accidentals(Accidentals::default())
+    }
+}
+
+impl NotePool {
+    // the ways the pool spells a pitch, none if it leaves the pitch out
+    fn spellings(&self, pitch: i16) -> Vec<Note> {
+        let Some(note) = u8::try_from(pitch).ok().and_then(Note::from_midi_number) else {
+            return Vec::new();
+        };
+        let respellings = || {
+            NoteLetter::ALL
+                .into_iter()
+                .filter_map(move |letter| note.respell(letter))
+        };
+
+        match self {
+            NotePool::Accidentals(accidentals) => {
+                // white keys are only spelled as naturals, never as B#, E#, Cb
+                // or Fb
+                let black_key = respellings().all(|note| note.accidental != Accidental::Natural);
+                respellings()
+                    .filter(|note| black_key || note.accidental == Accidental::Natural)
+                    .filter(|note| accidentals.allowed().contains(&note.accidental))
+                    .collect()
+            }
+            NotePool::PitchClasses(pitch_classes) => {
+                if pitch_classes.contains(&(pitch.rem_euclid(12) as u8)) {
+                    vec![note]
+                } else {
+                    Vec::new()
+                }
+            }
+            NotePool::Key(key) => respellings()
+                .filter(|note| note.accidental == key.accidental(note.letter))
+                .collect(),
+        }
+    }
+}
+
+// "sharps", "Eb major" or "C, E, G"
+impl fmt::Display for NotePool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotePool::Accidentals(accidentals) => write!(f, "{accidentals}"),
+            NotePool::PitchClasses(pitch_classes) => {
+                let notes: Vec<String> = pitch_classes
+                    .iter()
+                    .filter_map(|pitch_class| Note::from_midi_number(*pitch_class))
+                    .map(|note| format!("{}{}", note.letter, note.accidental))
+                    .collect();
+                write!(f, "{}", notes.join(", "))
+            }
+            NotePool::Key(key) => write!(f, "{key}"),
+        }
+    }
+}
+
+impl FromStr for NotePool {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if let Ok(accidentals) = s.parse() {
+            return Ok(NotePool::Accidentals(accidentals));
+        }
+        if s.ends_with("major") {
+            return s.parse().map(NotePool::Key);
+        }
+        s.split(',')
+            .map(|name| {
+                let (letter, accidental) = parse_spelling(name).with_context(|| {
+                    format!("'{s}' is not naturals, sharps, flats, all, a major key or pitch classes like C, E, G")
+                })?;
+                Ok(Note::new(letter, accidental, 4).pitch().rem_euclid(12) as u8)
+            })
+            .collect::<Result<_>>()
+            .map(NotePool::PitchClasses)
+    }
+}
+
+// the accidentals the black keys may be spelled with, the white keys are
+// always naturals
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Accidentals {
+    Naturals,
+    Sharps,
+    Flats,
+    #[default]
+    All,
+}
+
+impl Accidentals {
+    pub const ALL: [Accidentals; 4] = [
+        Accidentals::Naturals,
+        Accidentals::Sharps,
+        Accidentals::Flats,
+        Accidentals::All,
+    ];
+
+    pub fn allowed(&self) -> &'static [Accidental] {
+        match self {
+            Accidentals::Naturals => &[Accidental::Natural],
+            Accidentals::Sharps => &[Accidental::Sharp, Accidental::Natural],
+            Accidentals::Flats => &[Accidental::Flat, Accidental::Natural],
+            Accidentals::All => &[Accidental::Sharp, Accidental::Flat, Accidental::Natural],
+        }
+    }
+}
+
+impl fmt::Display for Accidentals {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Accidentals::Naturals => "naturals",
+            Accidentals::Sharps => "sharps",
+            Accidentals::Flats => "flats",
+            Accidentals::All => "all",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Accidentals {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        Accidentals::ALL
+            .into_iter()
+            .find(|accidentals| accidentals.to_string().eq_ignore_ascii_case(s))
+            .with_context(|| format!("'{s}' is not one of naturals, sharps, flats or all"))
+    }
 }
 
 pub struct RandomNoteSequence {
     rng: ChaCha12Rng,
+    config: RandomNoteSequenceConfig,
+    references: Vec<Note>,
 }
 
 impl RandomNoteSequence {
     pub fn new(rng: ChaCha12Rng) -> RandomNoteSequence {
-        RandomNoteSequence { rng }
+        RandomNoteSequence::with_config(rng, RandomNoteSequenceConfig::default())
+    }
+
+    pub fn with_config(rng: ChaCha12Rng, config: RandomNoteSequenceConfig) -> RandomNoteSequence {
+        RandomNoteSequence {
+            rng,
+            references: config.references(),
+            config,
+        }
+    }
+
+    pub fn configure(&mut self, config: RandomNoteSequenceConfig) {
+        self.references = config.references();
+        self.config = config;
     }
 }
 
 impl NoteSequence for RandomNoteSequence {
+    type Item = NoteTuple;
+
     fn next_note(&mut self) -> NoteTuple {
-        let ref_note: Note = self.rng.random();
-        let octave: i8 = self.rng.random_range(-12..=12);
+        let ref_note = *self
+            .references
+            .choose(&mut self.rng)
+            .unwrap_or(&self.config.lowest);
+        let offset = self
+            .config
+            .offsets(ref_note)
+            .choose_weighted(&mut self.rng, |(_, weight)| *weight)
+            .map(|(offset, _)| *offset)
+            .unwrap_or_default();
 
-        let mut div_note = ref_note;
-        let min_note = Note::new(NoteLetter::E, Accidental::Natural, 1);
-        let max_note = Note::new(NoteLetter::F, Accidental::Natural, 4);
+        NoteTuple::new(ref_note, self.config.divergence(ref_note, offset))
+    }
+}
+
+// like `RandomNoteSequence`, but the offsets, registers and pitch classes the
+// player struggles with come up more often
+pub struct AdaptiveNoteSequence {
+    rng: ChaCha12Rng,
+    config: RandomNoteSequenceConfig,
+    references: Vec<Note>,
+    reference_weights: Option<WeightedIndex<f64>>,
+    // how much the player struggles with each offset, from -127 up
+    offset_weakness: Vec<f64>,
+}
+
+impl AdaptiveNoteSequence {
+    pub fn new(rng: ChaCha12Rng) -> AdaptiveNoteSequence {
+        AdaptiveNoteSequence::with_config(rng, RandomNoteSequenceConfig::default())
+    }
+
+    pub fn with_config(rng: ChaCha12Rng, config: RandomNoteSequenceConfig) -> AdaptiveNoteSequence {
+        let references = config.references();
+
+        AdaptiveNoteSequence {
+            rng,
+            reference_weights: WeightedIndex::new(vec![1.0; references.len()]).ok(),
+            offset_weakness: vec![1.0; 2 * i8::MAX as usize + 1],
+            references,
+            config,
+        }
+    }
 
-        if octave < 0 {
-            div_note = max(div_note - octave.unsigned_abs(), min_note);
+    // starts over with even weights, `adapt` has to be called again
+    pub fn configure(&mut self, config: RandomNoteSequenceConfig) {
+        *self = AdaptiveNoteSequence::with_config(self.rng.clone(), config);
+    }
+
+    pub fn adapt(&mut self, statistics: &RandomModeStatistics) {
+        let reference_weights = self.references.iter().map(|note| {
+            statistics.register_weakness(note.octave)
+                * statistics.pitch_class_weakness(note.pitch().rem_euclid(12) as u8)
+        });
+
+        self.reference_weights = WeightedIndex::new(reference_weights).ok();
+        self.offset_weakness = (-i8::MAX..=i8::MAX)
+            .map(|offset| statistics.interval_weakness(offset as i16))
+            .collect();
+    }
+}
+
+impl NoteSequence for AdaptiveNoteSequence {
+    type Item = NoteTuple;
+
+    fn next_note(&mut self) -> NoteTuple {
+        let ref_note = match &self.reference_weights {
+            Some(weights) => self.references[weights.sample(&mut self.rng)],
+            None => self.config.lowest,
+        };
+        let offset_weakness = &self.offset_weakness;
+        let offset = self
+            .config
+            .offsets(ref_note)
+            .choose_weighted(&mut self.rng, |(offset, weight)| {
+                weight * offset_weakness[(*offset as i16 + i8::MAX as i16) as usize]
+            })
+            .map(|(offset, _)| *offset)
+            .unwrap_or_default();
+
+        NoteTuple::new(ref_note, self.config.divergence(ref_note, offset))
+    }
+}
+
+// a root in the octaves spelled the way the pools spell it, without B#, E#,
+// Cb or Fb, so any simple interval above or below it keeps its name
+pub(crate) fn interval_root<R: Rng + ?Sized>(rng: &mut R, octaves: RangeInclusive<i8>) -> Note {
+    let lowest = Note::new(NoteLetter::C, Accidental::Natural, *octaves.start()).pitch();
+    let highest = Note::new(NoteLetter::B, Accidental::Natural, *octaves.end()).pitch();
+    let pitch = rng.random_range(lowest..=highest);
+    *NotePool::Accidentals(Accidentals::All)
+        .spellings(pitch)
+        .choose(rng)
+        .expect("every pitch has a spelling in the pool")
+}
+
+// pairs of notes that are a given interval apart, the reference is the root
+// and the divergence lies above or below it
+pub struct RandomIntervalSequence {
+    rng: ChaCha12Rng,
+    intervals: Vec<Interval>,
+}
+
+impl RandomIntervalSequence {
+    pub fn new(rng: ChaCha12Rng, intervals: Vec<Interval>) -> RandomIntervalSequence {
+        RandomIntervalSequence { rng, intervals }
+    }
+}
+
+impl NoteSequence for RandomIntervalSequence {
+    type Item = NoteTuple;
+
+    fn next_note(&mut self) -> NoteTuple {
+        let ref_note = interval_root(&mut self.rng, 3..=4);
+        let interval = *self
+            .intervals
+            .choose(&mut self.rng)
+            .unwrap_or(&Interval::PERFECT_UNISON);
+
+        let div_note = if self.rng.random() {
+            ref_note + interval
         } else {
-            div_note = min(div_note + octave.unsigned_abs(), max_note);
+            ref_note - interval
+        };
+
+        NoteTuple::new(ref_note, div_note)
+    }
+}
+
+// chords on a random root around the middle of the keyboard, in any inversion
+pub struct RandomChordSequence {
+    rng: ChaCha12Rng,
+    qualities: Vec<ChordQuality>,
+}
+
+impl RandomChordSequence {
+    pub fn new(rng: ChaCha12Rng, qualities: Vec<ChordQuality>) -> RandomChordSequence {
+        RandomChordSequence { rng, qualities }
+    }
+
+    pub fn next_chord(&mut self) -> Chord {
+        let mut root: Note = self.rng.random();
+        root.octave = 3;
+        let quality = *self
+            .qualities
+            .choose(&mut self.rng)
+            .unwrap_or(&ChordQuality::Major);
+        let inversion = self.rng.random_range(0..quality.size()) as u8;
+
+        let chord = Chord::new(root, quality, inversion).expect("inversion is within the chord");
+        debug!("Chord: {chord} ({})", chord.name());
+        chord
+    }
+}
+
+// scale runs over one octave starting around the middle of the keyboard
+pub struct RandomScaleSequence {
+    rng: ChaCha12Rng,
+    kinds: Vec<ScaleKind>,
+}
+
+impl RandomScaleSequence {
+    pub fn new(rng: ChaCha12Rng, kinds: Vec<ScaleKind>) -> RandomScaleSequence {
+        RandomScaleSequence { rng, kinds }
+    }
+}
+
+impl NoteSequence for RandomScaleSequence {
+    type Item = ScaleRun;
+
+    fn next_note(&mut self) -> ScaleRun {
+        let mut root: Note = self.rng.random();
+        root.octave = 3;
+        let kind = *self
+            .kinds
+            .choose(&mut self.rng)
+            .unwrap_or(&ScaleKind::Major);
+
+        let run = ScaleRun::new(Scale::new(root, kind), self.rng.random());
+        debug!("Scale: {}", run.scale());
+        run
+    }
+}
+
+// short melodies in a random key that stay between the lowest and highest note
+// and move mostly stepwise
+pub struct RandomMelodySequence {
+    rng: ChaCha12Rng,
+    lowest: Note,
+    highest: Note,
+    lengths: RangeInclusive<usize>,
+}
+
+impl RandomMelodySequence {
+    pub fn new(
+        rng: ChaCha12Rng,
+        lowest: Note,
+        highest: Note,
+        lengths: RangeInclusive<usize>,
+    ) -> RandomMelodySequence {
+        RandomMelodySequence {
+            rng,
+            lowest,
+            highest,
+            lengths,
+        }
+    }
+
+    fn scale_notes(&self, key: KeySignature) -> Vec<Note> {
+        (self.lowest.octave - 1..=self.highest.octave + 1)
+            .flat_map(|octave| {
+                NoteLetter::ALL
+                    .into_iter()
+                    .map(move |letter| Note::new(letter, key.accidental(letter), octave))
+            })
+            .filter(|note| *note >= self.lowest && *note <= self.highest)
+            .collect()
+    }
+}
+
+impl NoteSequence for RandomMelodySequence {
+    type Item = Melody;
+
+    fn next_note(&mut self) -> Melody {
+        let key = KeySignature::new(self.rng.random_range(-7..=7)).expect("valid key signature");
+        let scale_notes = self.scale_notes(key);
+        if scale_notes.is_empty() {
+            return Melody::new(key, Vec::new());
+        }
+
+        let length = self.rng.random_range(self.lengths.clone());
+        let mut index = self.rng.random_range(0..scale_notes.len());
+        let mut notes = Vec::with_capacity(length);
+        for position in 0..length {
+            let beats = if position + 1 == length || self.rng.random_ratio(1, 4) {
+                2
+            } else {
+                1
+            };
+            notes.push(MelodyNote::new(scale_notes[index], beats));
+
+            let step = *[-3, -2, -1, -1, 1, 1, 2, 3]
+                .choose(&mut self.rng)
+                .expect("steps are not empty");
+            // steps that would leave the range turn around instead
+            index = index
+                .checked_add_signed(step)
+                .filter(|next| *next < scale_notes.len())
+                .or_else(|| index.checked_add_signed(-step))
+                .unwrap_or(index)
+                .min(scale_notes.len() - 1);
+        }
+
+        let melody = Melody::new(key, notes);
+        debug!("Melody in {key}: {:?}", melody.notes());
+        melody
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, time::Duration};
+
+    use super::*;
+    use crate::{domain::statistics::Accuracy, interfaces::ui::IntervalGuess};
+
+    fn note(s: &str) -> Note {
+        s.parse().unwrap()
+    }
+
+    fn tuples(seed: u64, config: &RandomNoteSequenceConfig, count: usize) -> Vec<String> {
+        let mut sequence =
+            RandomNoteSequence::with_config(ChaCha12Rng::seed_from_u64(seed), config.clone());
+        (0..count)
+            .map(|_| {
+                let note_tuple = sequence.next_note();
+                format!("{} {}", note_tuple.reference(), note_tuple.divergence())
+            })
+            .collect()
+    }
+
+    fn key_config() -> RandomNoteSequenceConfig {
+        RandomNoteSequenceConfig {
+            lowest: note("C3"),
+            highest: note("C5"),
+            pool: NotePool::Key(KeySignature::new(-3).unwrap()),
+            min_offset: 3,
+            max_offset: 7,
+            ascending: 100,
+            allow_unison: false,
+        }
+    }
+
+    // exercises are shared as a seed, so a seed has to keep giving the same
+    // tuples
+    #[test]
+    fn seeded_sequences_are_pinned() {
+        assert_eq!(
+            tuples(7, &RandomNoteSequenceConfig::default(), 8),
+            [
+                "G#2 B1", "Eb2 G1", "E3 G3", "D2 C#3", "D3 G#3", "Db2 F#1", "F#2 F#2", "Eb3 D#2"
+            ]
+        );
+        assert_eq!(
+            tuples(7, &key_config(), 8),
+            [
+                "Ab3 C4", "F3 Ab3", "D4 G4", "F3 C4", "C4 G4", "F3 Ab3", "G3 C4", "D4 F4"
+            ]
+        );
+        assert_ne!(tuples(8, &key_config(), 8), tuples(7, &key_config(), 8));
+    }
+
+    #[test]
+    fn accidentals_only_spell_the_black_keys() {
+        let spelled = |accidentals| {
+            let pool = NotePool::Accidentals(accidentals);
+            let notes: Vec<String> = (60..72)
+                .flat_map(|pitch| pool.spellings(pitch))
+                .map(|note| format!("{}{}", note.letter, note.accidental))
+                .collect();
+            notes.join(" ")
+        };
+        assert_eq!(spelled(Accidentals::Naturals), "C D E F G A B");
+        assert_eq!(spelled(Accidentals::Sharps), "C C# D D# E F F# G G# A A# B");
+        assert_eq!(spelled(Accidentals::Flats), "C Db D Eb E F Gb G Ab A Bb B");
+        assert_eq!(
+            spelled(Accidentals::All),
+            "C C# Db D D# Eb E F F# Gb G G# Ab A A# Bb B"
+        );
+    }
+
+    #[test]
+    fn pools_are_written_the_way_they_are_read() {
+        let mut pools: Vec<NotePool> = Accidentals::ALL
+            .into_iter()
+            .map(NotePool::Accidentals)
+            .collect();
+        pools.extend((-7..=7).map(|fifths| NotePool::Key(KeySignature::new(fifths).unwrap())));
+        pools.push(NotePool::PitchClasses(BTreeSet::from([0, 4, 7])));
+        pools.push(NotePool::PitchClasses(BTreeSet::from([1, 11])));
+        for pool in pools {
+            assert_eq!(
+                pool.to_string().parse::<NotePool>().unwrap(),
+                pool,
+                "{pool}"
+            );
+        }
+
+        let pool = |s: &str| s.parse::<NotePool>().unwrap();
+        assert_eq!(pool(" Flats "), NotePool::Accidentals(Accidentals::Flats));
+        assert_eq!(pool("Bb major").to_string(), "Bb major");
+        assert_eq!(
+            pool("Db, gb,B#"),
+            NotePool::PitchClasses(BTreeSet::from([0, 1, 6]))
+        );
+        for invalid in ["", "C, ", "X", "Fb major", "C#, minor", "D minor"] {
+            assert!(invalid.parse::<NotePool>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn tuples_keep_to_the_config() {
+        let config = key_config();
+        let key = KeySignature::new(-3).unwrap();
+        let mut sequence =
+            RandomNoteSequence::with_config(ChaCha12Rng::seed_from_u64(1), config.clone());
+        for _ in 0..500 {
+            let note_tuple = sequence.next_note();
+            for note in [note_tuple.reference(), note_tuple.divergence()] {
+                assert!((config.lowest.pitch()..=config.highest.pitch()).contains(&note.pitch()));
+                assert_eq!(note.accidental, key.accidental(note.letter), "{note}");
+            }
+            let offset = note_tuple.divergence().pitch() - note_tuple.reference().pitch();
+            assert!((3..=7).contains(&offset), "{offset}");
+        }
+
+        let config = RandomNoteSequenceConfig {
+            pool: NotePool::PitchClasses(BTreeSet::from([0, 4, 7])),
+            ascending: 0,
+            ..RandomNoteSequenceConfig::default()
+        };
+        let mut sequence =
+            RandomNoteSequence::with_config(ChaCha12Rng::seed_from_u64(1), config.clone());
+        for _ in 0..500 {
+            let note_tuple = sequence.next_note();
+            for note in [note_tuple.reference(), note_tuple.divergence()] {
+                assert!([0, 4, 7].contains(&note.pitch().rem_euclid(12)), "{note}");
+            }
+            assert!(note_tuple.divergence() <= note_tuple.reference());
+        }
+    }
+
+    #[test]
+    fn intervals_keep_their_names_on_any_root() {
+        for interval in Interval::SIMPLE {
+            let mut sequence =
+                RandomIntervalSequence::new(ChaCha12Rng::seed_from_u64(3), vec![interval]);
+            for _ in 0..200 {
+                let note_tuple = sequence.next_note();
+                let root = note_tuple.reference();
+                assert!((3..=4).contains(&root.octave), "{root}");
+                assert!(
+                    !["B#", "E#", "Cb", "Fb"]
+                        .contains(&format!("{}{}", root.letter, root.accidental).as_str()),
+                    "{root}"
+                );
+                assert_eq!(
+                    note_tuple.interval(),
+                    interval,
+                    "{root} {}",
+                    note_tuple.divergence()
+                );
+
+                let guess = IntervalGuess::new(note_tuple.clone(), interval);
+                assert!(guess.correct);
+                assert_eq!(guess.interval, interval);
+            }
+        }
+
+        // a tritone may be answered either way, but it is named as it was asked
+        let mut sequence = RandomIntervalSequence::new(
+            ChaCha12Rng::seed_from_u64(3),
+            vec![Interval::AUGMENTED_FOURTH],
+        );
+        let guess = IntervalGuess::new(sequence.next_note(), Interval::DIMINISHED_FIFTH);
+        assert!(guess.correct);
+        assert_eq!(guess.interval, Interval::AUGMENTED_FOURTH);
+        let guess = IntervalGuess::new(sequence.next_note(), Interval::PERFECT_FOURTH);
+        assert!(!guess.correct);
+    }
+
+    // the count of the key drawn most often, leaving out the weak one
+    fn most_often_but<K: Eq>(counts: &HashMap<K, u32>, weak: K) -> u32 {
+        counts
+            .iter()
+            .filter(|(key, _)| **key != weak)
+            .map(|(_, count)| *count)
+            .max()
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn weak_material_is_drawn_more_often() {
+        let accuracy = |correct| Accuracy {
+            attempts: 20,
+            correct,
+            total_response_time: Duration::ZERO,
+        };
+        // everything is answered right except fifths up, references in the
+        // second octave and references on D
+        let statistics = RandomModeStatistics::new(
+            accuracy(10),
+            (-12..=12)
+                .map(|offset| (offset, accuracy(if offset == 7 { 0 } else { 20 })))
+                .collect(),
+            (1..=4)
+                .map(|octave| (octave, accuracy(if octave == 2 { 0 } else { 20 })))
+                .collect(),
+            (0..12)
+                .map(|class| (class, accuracy(if class == 2 { 0 } else { 20 })))
+                .collect(),
+        );
+        let mut sequence = AdaptiveNoteSequence::new(ChaCha12Rng::seed_from_u64(7));
+        sequence.adapt(&statistics);
+
+        let mut offsets: HashMap<i16, u32> = HashMap::new();
+        let mut registers: HashMap<i8, u32> = HashMap::new();
+        let mut pitch_classes: HashMap<i16, u32> = HashMap::new();
+        for _ in 0..5000 {
+            let note_tuple = sequence.next_note();
+            let reference = note_tuple.reference();
+            *offsets
+                .entry(note_tuple.divergence().pitch() - reference.pitch())
+                .or_default() += 1;
+            *registers.entry(reference.octave).or_default() += 1;
+            *pitch_classes
+                .entry(reference.pitch().rem_euclid(12))
+                .or_default() += 1;
         }
 
-        NoteTuple::try_new(ref_note, div_note)
+        assert!(offsets[&7] > 5 * most_often_but(&offsets, 7), "{offsets:?}");
+        assert!(
+            registers[&2] > 5 * most_often_but(&registers, 2),
+            "{registers:?}"
+        );
+        assert!(
+            pitch_classes[&2] > 5 * most_often_but(&pitch_classes, 2),
+            "{pitch_classes:?}"
+        );
     }
 }
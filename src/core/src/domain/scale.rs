@@ -0,0 +1,277 @@
+use std::fmt;
+
+use super::{interval::Interval, notes::Note};
+
+// every kind sounds different, so there is only one right answer. the major
+// scale is the ionian mode and the natural minor the aeolian one
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum ScaleKind {
+    Major,
+    NaturalMinor,
+    HarmonicMinor,
+    MelodicMinor,
+    Dorian,
+    Phrygian,
+    Lydian,
+    Mixolydian,
+    Locrian,
+    MajorPentatonic,
+    MinorPentatonic,
+    Blues,
+    WholeTone,
+}
+
+impl fmt::Display for ScaleKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ScaleKind::Major => "major (ionian)",
+            ScaleKind::NaturalMinor => "natural minor (aeolian)",
+            ScaleKind::HarmonicMinor => "harmonic minor",
+            ScaleKind::MelodicMinor => "melodic minor",
+            ScaleKind::Dorian => "dorian",
+            ScaleKind::Phrygian => "phrygian",
+            ScaleKind::Lydian => "lydian",
+            ScaleKind::Mixolydian => "mixolydian",
+            ScaleKind::Locrian => "locrian",
+            ScaleKind::MajorPentatonic => "major pentatonic",
+            ScaleKind::MinorPentatonic => "minor pentatonic",
+            ScaleKind::Blues => "blues",
+            ScaleKind::WholeTone => "whole-tone",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl ScaleKind {
+    pub const ALL: [ScaleKind; 13] = [
+        ScaleKind::Major,
+        ScaleKind::NaturalMinor,
+        ScaleKind::HarmonicMinor,
+        ScaleKind::MelodicMinor,
+        ScaleKind::Dorian,
+        ScaleKind::Phrygian,
+        ScaleKind::Lydian,
+        ScaleKind::Mixolydian,
+        ScaleKind::Locrian,
+        ScaleKind::MajorPentatonic,
+        ScaleKind::MinorPentatonic,
+        ScaleKind::Blues,
+        ScaleKind::WholeTone,
+    ];
+
+    // the degrees above the root within one octave, the melodic minor is the
+    // ascending form
+    pub fn intervals(&self) -> &'static [Interval] {
+        match self {
+            ScaleKind::Major => &[
+                Interval::MAJOR_SECOND,
+                Interval::MAJOR_THIRD,
+                Interval::PERFECT_FOURTH,
+                Interval::PERFECT_FIFTH,
+                Interval::MAJOR_SIXTH,
+                Interval::MAJOR_SEVENTH,
+            ],
+            ScaleKind::NaturalMinor => &[
+                Interval::MAJOR_SECOND,
+                Interval::MINOR_THIRD,
+                Interval::PERFECT_FOURTH,
+                Interval::PERFECT_FIFTH,
+                Interval::MINOR_SIXTH,
+                Interval::MINOR_SEVENTH,
+            ],
+            ScaleKind::HarmonicMinor => &[
+                Interval::MAJOR_SECOND,
+                Interval::MINOR_THIRD,
+                Interval::PERFECT_FOURTH,
+                Interval::PERFECT_FIFTH,
+                Interval::MINOR_SIXTH,
+                Interval::MAJOR_SEVENTH,
+            ],
+            ScaleKind::MelodicMinor => &[
+                Interval::MAJOR_SECOND,
+                Interval::MINOR_THIRD,
+                Interval::PERFECT_FOURTH,
+                Interval::PERFECT_FIFTH,
+                Interval::MAJOR_SIXTH,
+                Interval::MAJOR_SEVENTH,
+            ],
+            ScaleKind::Dorian => &[
+                Interval::MAJOR_SECOND,
+                Interval::MINOR_THIRD,
+                Interval::PERFECT_FOURTH,
+                Interval::PERFECT_FIFTH,
+                Interval::MAJOR_SIXTH,
+                Interval::MINOR_SEVENTH,
+            ],
+            ScaleKind::Phrygian => &[
+                Interval::MINOR_SECOND,
+                Interval::MINOR_THIRD,
+                Interval::PERFECT_FOURTH,
+                Interval::PERFECT_FIFTH,
+                Interval::MINOR_SIXTH,
+                Interval::MINOR_SEVENTH,
+            ],
+            ScaleKind::Lydian => &[
+                Interval::MAJOR_SECOND,
+                Interval::MAJOR_THIRD,
+                Interval::AUGMENTED_FOURTH,
+                Interval::PERFECT_FIFTH,
+                Interval::MAJOR_SIXTH,
+                Interval::MAJOR_SEVENTH,
+            ],
+            ScaleKind::Mixolydian => &[
+                Interval::MAJOR_SECOND,
+                Interval::MAJOR_THIRD,
+                Interval::PERFECT_FOURTH,
+                Interval::PERFECT_FIFTH,
+                Interval::MAJOR_SIXTH,
+                Interval::MINOR_SEVENTH,
+            ],
+            ScaleKind::Locrian => &[
+                Interval::MINOR_SECOND,
+                Interval::MINOR_THIRD,
+                Interval::PERFECT_FOURTH,
+                Interval::DIMINISHED_FIFTH,
+                Interval::MINOR_SIXTH,
+                Interval::MINOR_SEVENTH,
+            ],
+            ScaleKind::MajorPentatonic => &[
+                Interval::MAJOR_SECOND,
+                Interval::MAJOR_THIRD,
+                Interval::PERFECT_FIFTH,
+                Interval::MAJOR_SIXTH,
+            ],
+            ScaleKind::MinorPentatonic => &[
+                Interval::MINOR_THIRD,
+                Interval::PERFECT_FOURTH,
+                Interval::PERFECT_FIFTH,
+                Interval::MINOR_SEVENTH,
+            ],
+            ScaleKind::Blues => &[
+                Interval::MINOR_THIRD,
+                Interval::PERFECT_FOURTH,
+                Interval::AUGMENTED_FOURTH,
+                Interval::PERFECT_FIFTH,
+                Interval::MINOR_SEVENTH,
+            ],
+            ScaleKind::WholeTone => &[
+                Interval::MAJOR_SECOND,
+                Interval::MAJOR_THIRD,
+                Interval::AUGMENTED_FOURTH,
+                Interval::AUGMENTED_FIFTH,
+                Interval::AUGMENTED_SIXTH,
+            ],
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct Scale {
+    root: Note,
+    kind: ScaleKind,
+}
+
+impl Scale {
+    pub fn new(root: Note, kind: ScaleKind) -> Self {
+        Scale { root, kind }
+    }
+
+    pub fn root(&self) -> Note {
+        self.root
+    }
+
+    pub fn kind(&self) -> ScaleKind {
+        self.kind
+    }
+
+    // one octave upwards, from the root to the root above
+    pub fn notes(&self) -> Vec<Note> {
+        std::iter::once(self.root)
+            .chain(
+                self.kind
+                    .intervals()
+                    .iter()
+                    .map(|interval| self.root + *interval),
+            )
+            .chain(std::iter::once(self.root + Interval::PERFECT_OCTAVE))
+            .collect()
+    }
+}
+
+impl fmt::Display for Scale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{} {}",
+            self.root.letter, self.root.accidental, self.kind
+        )
+    }
+}
+
+// a scale played over one octave in either direction
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct ScaleRun {
+    scale: Scale,
+    descending: bool,
+}
+
+impl ScaleRun {
+    pub fn new(scale: Scale, descending: bool) -> Self {
+        ScaleRun { scale, descending }
+    }
+
+    pub fn scale(&self) -> Scale {
+        self.scale
+    }
+
+    pub fn is_descending(&self) -> bool {
+        self.descending
+    }
+
+    pub fn notes(&self) -> Vec<Note> {
+        let mut notes = self.scale.notes();
+        if self.descending {
+            notes.reverse();
+        }
+        notes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn every_kind_sounds_different() {
+        let sizes: HashSet<Vec<u8>> = ScaleKind::ALL
+            .iter()
+            .map(|kind| {
+                kind.intervals()
+                    .iter()
+                    .map(|interval| interval.semitones())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(sizes.len(), ScaleKind::ALL.len());
+    }
+
+    #[test]
+    fn scales_are_spelled_from_the_root() {
+        let note = |s: &str| s.parse::<Note>().unwrap();
+        let notes = |names: &str| names.split(' ').map(note).collect::<Vec<_>>();
+        assert_eq!(
+            Scale::new(note("D3"), ScaleKind::Major).notes(),
+            notes("D3 E3 F#3 G3 A3 B3 C#4 D4")
+        );
+        assert_eq!(
+            Scale::new(note("C3"), ScaleKind::NaturalMinor).notes(),
+            notes("C3 D3 Eb3 F3 G3 Ab3 Bb3 C4")
+        );
+        assert_eq!(
+            ScaleRun::new(Scale::new(note("A3"), ScaleKind::MinorPentatonic), true).notes(),
+            notes("A4 G4 E4 D4 C4 A3")
+        );
+    }
+}
@@ -0,0 +1,626 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        Arc,
+        atomic::{AtomicI64, Ordering},
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Result, bail};
+use rand_chacha::ChaCha12Rng;
+use tracing::debug;
+
+use crate::interfaces::ui::NoteGuess;
+
+use super::{
+    interval::Interval,
+    notes::Note,
+    random::{NoteSequence, NoteTuple, interval_root},
+};
+
+const INITIAL_EASE: f32 = 2.5;
+const MIN_EASE: f32 = 1.3;
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+// days since the unix epoch
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+pub struct Day(pub i64);
+
+impl Day {
+    pub fn plus(self, days: u32) -> Day {
+        Day(self.0 + days as i64)
+    }
+
+    pub fn minus(self, days: u32) -> Day {
+        Day(self.0 - days as i64)
+    }
+
+    // the monday of the week, the epoch fell on a thursday
+    pub fn week_start(self) -> Day {
+        Day(self.0 - (self.0 + 3).rem_euclid(7))
+    }
+}
+
+// the proleptic gregorian date, as in Howard Hinnant's civil_from_days
+impl fmt::Display for Day {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let days = self.0 + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        write!(f, "{year:04}-{month:02}-{day:02}")
+    }
+}
+
+impl From<SystemTime> for Day {
+    fn from(time: SystemTime) -> Self {
+        let seconds = time
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        Day((seconds / SECONDS_PER_DAY) as i64)
+    }
+}
+
+pub trait Clock: Send {
+    fn today(&self) -> Day;
+}
+
+// days change at midnight UTC
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn today(&self) -> Day {
+        Day::from(SystemTime::now())
+    }
+}
+
+// a clock that only moves when told to, clones share the same day
+#[derive(Debug, Default, Clone)]
+pub struct ManualClock {
+    day: Arc<AtomicI64>,
+}
+
+impl ManualClock {
+    pub fn new(day: Day) -> Self {
+        Self {
+            day: Arc::new(AtomicI64::new(day.0)),
+        }
+    }
+
+    pub fn set(&self, day: Day) {
+        self.day.store(day.0, Ordering::Relaxed);
+    }
+
+    pub fn advance(&self, days: u32) {
+        self.day.fetch_add(days as i64, Ordering::Relaxed);
+    }
+}
+
+impl Clock for ManualClock {
+    fn today(&self) -> Day {
+        Day(self.day.load(Ordering::Relaxed))
+    }
+}
+
+// what is being learned, either a concrete pair of notes or an interval on
+// any root
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum Card {
+    NotePair { reference: Note, divergence: Note },
+    Interval(Interval),
+}
+
+impl From<&NoteTuple> for Card {
+    fn from(note_tuple: &NoteTuple) -> Self {
+        Card::NotePair {
+            reference: note_tuple.reference(),
+            divergence: note_tuple.divergence(),
+        }
+    }
+}
+
+// how well a review went on the SM-2 scale, 0 is a blackout, 3 and above
+// count as remembered
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+pub struct Grade(u8);
+
+impl Grade {
+    pub const BLACKOUT: Grade = Grade(0);
+    pub const WRONG: Grade = Grade(1);
+    pub const ALMOST: Grade = Grade(2);
+    pub const HARD: Grade = Grade(3);
+    pub const GOOD: Grade = Grade(4);
+    pub const PERFECT: Grade = Grade(5);
+
+    pub fn new(grade: u8) -> Result<Self> {
+        if grade > 5 {
+            bail!("Grades go from 0 to 5, got {grade}");
+        }
+        Ok(Grade(grade))
+    }
+
+    pub fn value(&self) -> u8 {
+        self.0
+    }
+
+    pub fn is_pass(&self) -> bool {
+        self.0 >= 3
+    }
+}
+
+// a right note is good, a wrong one is almost right if it was no more than a
+// semitone off
+impl From<&NoteGuess> for Grade {
+    fn from(note_guess: &NoteGuess) -> Self {
+        if note_guess.correct {
+            Grade::GOOD
+        } else if note_guess
+            .true_note_tuple
+            .reference()
+            .distance(&note_guess.note_played)
+            <= 1
+        {
+            Grade::ALMOST
+        } else {
+            Grade::WRONG
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct ReviewState {
+    pub repetitions: u32,
+    pub ease: f32,
+    pub interval_days: u32,
+    pub due: Day,
+}
+
+impl ReviewState {
+    pub fn new(due: Day) -> Self {
+        Self {
+            repetitions: 0,
+            ease: INITIAL_EASE,
+            interval_days: 0,
+            due,
+        }
+    }
+
+    // the SM-2 update, failed cards start over but keep their lowered ease
+    fn review(&mut self, grade: Grade, today: Day) {
+        if grade.is_pass() {
+            self.repetitions += 1;
+            self.interval_days = match self.repetitions {
+                1 => 1,
+                2 => 6,
+                _ => (self.interval_days as f32 * self.ease).round() as u32,
+            };
+        } else {
+            self.repetitions = 0;
+            self.interval_days = 1;
+        }
+
+        let miss = (5 - grade.value()) as f32;
+        self.ease = (self.ease + 0.1 - miss * (0.08 + miss * 0.02)).max(MIN_EASE);
+        self.due = today.plus(self.interval_days);
+    }
+}
+
+pub struct Scheduler {
+    clock: Box<dyn Clock>,
+    cards: HashMap<Card, ReviewState>,
+}
+
+impl Scheduler {
+    pub fn new(clock: Box<dyn Clock>) -> Self {
+        Self::with_cards(clock, HashMap::new())
+    }
+
+    pub fn with_cards(clock: Box<dyn Clock>, cards: HashMap<Card, ReviewState>) -> Self {
+        Self { clock, cards }
+    }
+
+    pub fn today(&self) -> Day {
+        self.clock.today()
+    }
+
+    pub fn cards(&self) -> &HashMap<Card, ReviewState> {
+        &self.cards
+    }
+
+    pub fn contains(&self, card: &Card) -> bool {
+        self.cards.contains_key(card)
+    }
+
+    // new cards are due right away
+    pub fn add(&mut self, card: Card) {
+        let today = self.today();
+        self.cards
+            .entry(card)
+            .or_insert_with(|| ReviewState::new(today));
+    }
+
+    pub fn review(&mut self, card: Card, grade: Grade) {
+        let today = self.today();
+        let state = self
+            .cards
+            .entry(card)
+            .or_insert_with(|| ReviewState::new(today));
+        state.review(grade, today);
+        debug!(?card, ?state, "Reviewed card");
+    }
+
+    // everything due today or overdue, the most overdue first
+    pub fn due_today(&self) -> Vec<Card> {
+        let today = self.today();
+        let mut due: Vec<(&Card, &ReviewState)> = self
+            .cards
+            .iter()
+            .filter(|(_, state)| state.due <= today)
+            .collect();
+        due.sort_by_key(|(_, state)| state.due);
+        due.into_iter().map(|(card, _)| *card).collect()
+    }
+
+    pub fn next_due(&self) -> Option<Card> {
+        self.cards
+            .iter()
+            .min_by_key(|(_, state)| state.due)
+            .map(|(card, _)| *card)
+    }
+}
+
+// how many unseen cards were introduced on a day, it is kept with the deck so
+// the daily limit holds across sessions
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub struct NewCards {
+    pub day: Day,
+    pub count: usize,
+}
+
+// the cards that are due today, with up to `new_per_day` unseen pairs from
+// the source mixed in once they run out, after that cards are reviewed early
+pub struct DueNoteSequence {
+    rng: ChaCha12Rng,
+    scheduler: Scheduler,
+    new_cards: Box<dyn NoteSequence<Item = NoteTuple>>,
+    new_per_day: usize,
+    introduced: NewCards,
+    // the card the last tuple was made from, an interval card is played on
+    // any reference so it can't be told from the tuple
+    served: Option<Card>,
+}
+
+impl DueNoteSequence {
+    pub fn new(
+        rng: ChaCha12Rng,
+        scheduler: Scheduler,
+        new_cards: Box<dyn NoteSequence<Item = NoteTuple>>,
+        new_per_day: usize,
+        introduced: NewCards,
+    ) -> Self {
+        Self {
+            rng,
+            scheduler,
+            new_cards,
+            new_per_day,
+            introduced,
+            served: None,
+        }
+    }
+
+    pub fn scheduler(&self) -> &Scheduler {
+        &self.scheduler
+    }
+
+    pub fn introduced(&self) -> NewCards {
+        self.introduced
+    }
+
+    pub fn due_today(&self) -> usize {
+        self.scheduler.due_today().len()
+    }
+
+    // grades the card the tuple was made from
+    pub fn review(&mut self, note_guess: &NoteGuess) {
+        let card = self
+            .served
+            .take()
+            .unwrap_or_else(|| Card::from(&note_guess.true_note_tuple));
+        self.scheduler.review(card, Grade::from(note_guess));
+    }
+
+    fn introduce(&mut self) -> Option<NoteTuple> {
+        let today = self.scheduler.today();
+        if self.introduced.day != today {
+            self.introduced = NewCards {
+                day: today,
+                count: 0,
+            };
+        }
+        if self.introduced.count >= self.new_per_day {
+            return None;
+        }
+
+        let note_tuple = self.new_cards.next_note();
+        self.introduced.count += 1;
+        self.scheduler.add(Card::from(&note_tuple));
+        Some(note_tuple)
+    }
+
+    fn note_tuple(&mut self, card: Card) -> NoteTuple {
+        self.served = Some(card);
+        match card {
+            Card::NotePair {
+                reference,
+                divergence,
+            } => NoteTuple::new(reference, divergence),
+            Card::Interval(interval) => {
+                let reference = interval_root(&mut self.rng, 3..=4);
+                NoteTuple::new(reference, reference + interval)
+            }
+        }
+    }
+}
+
+impl NoteSequence for DueNoteSequence {
+    type Item = NoteTuple;
+
+    fn next_note(&mut self) -> NoteTuple {
+        if let Some(card) = self.scheduler.due_today().first() {
+            return self.note_tuple(*card);
+        }
+        if let Some(note_tuple) = self.introduce() {
+            self.served = Some(Card::from(&note_tuple));
+            return note_tuple;
+        }
+        match self.scheduler.next_due() {
+            Some(card) => self.note_tuple(card),
+            None => {
+                let note_tuple = self.new_cards.next_note();
+                self.served = Some(Card::from(&note_tuple));
+                note_tuple
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rand::SeedableRng;
+
+    use super::*;
+
+    const START: Day = Day(20_000);
+
+    fn note(s: &str) -> Note {
+        s.parse().unwrap()
+    }
+
+    fn pair(reference: &str, divergence: &str) -> Card {
+        Card::NotePair {
+            reference: note(reference),
+            divergence: note(divergence),
+        }
+    }
+
+    fn scheduler_on(clock: &ManualClock) -> Scheduler {
+        Scheduler::new(Box::new(clock.clone()))
+    }
+
+    // reviews the card whenever it is due and returns the intervals it got
+    fn intervals(scheduler: &mut Scheduler, clock: &ManualClock, grades: &[Grade]) -> Vec<u32> {
+        let card = pair("C4", "E4");
+        grades
+            .iter()
+            .map(|grade| {
+                if let Some(state) = scheduler.cards().get(&card) {
+                    clock.set(state.due);
+                }
+                scheduler.review(card, *grade);
+                scheduler.cards()[&card].interval_days
+            })
+            .collect()
+    }
+
+    #[test]
+    fn passed_cards_wait_one_then_six_days_then_scale_with_the_ease() {
+        let clock = ManualClock::new(START);
+        let mut scheduler = scheduler_on(&clock);
+        // a good answer keeps the ease at 2.5
+        let grades = [Grade::GOOD; 5];
+        assert_eq!(
+            intervals(&mut scheduler, &clock, &grades),
+            [1, 6, 15, 38, 95]
+        );
+        let state = scheduler.cards()[&pair("C4", "E4")];
+        assert_eq!(state.repetitions, 5);
+        assert_eq!(state.ease, INITIAL_EASE);
+        assert_eq!(state.due, clock.today().plus(95));
+
+        // a perfect one raises it to 2.6, a hard one lowers it to 2.36
+        let clock = ManualClock::new(START);
+        let mut scheduler = scheduler_on(&clock);
+        let grades = [Grade::PERFECT, Grade::PERFECT, Grade::PERFECT];
+        assert_eq!(intervals(&mut scheduler, &clock, &grades), [1, 6, 16]);
+        let clock = ManualClock::new(START);
+        let mut scheduler = scheduler_on(&clock);
+        let grades = [Grade::HARD, Grade::HARD, Grade::HARD];
+        assert_eq!(intervals(&mut scheduler, &clock, &grades), [1, 6, 13]);
+    }
+
+    #[test]
+    fn the_ease_never_drops_below_the_floor() {
+        let clock = ManualClock::new(START);
+        let mut scheduler = scheduler_on(&clock);
+        intervals(&mut scheduler, &clock, &[Grade::BLACKOUT; 10]);
+        assert_eq!(scheduler.cards()[&pair("C4", "E4")].ease, MIN_EASE);
+
+        let grades = [Grade::HARD, Grade::HARD, Grade::HARD];
+        let passed = intervals(&mut scheduler, &clock, &grades);
+        assert_eq!(passed, [1, 6, 8]);
+        assert_eq!(scheduler.cards()[&pair("C4", "E4")].ease, MIN_EASE);
+    }
+
+    #[test]
+    fn failed_cards_start_over_with_a_lower_ease() {
+        let clock = ManualClock::new(START);
+        let mut scheduler = scheduler_on(&clock);
+        let grades = [Grade::GOOD, Grade::GOOD, Grade::GOOD, Grade::WRONG];
+        assert_eq!(intervals(&mut scheduler, &clock, &grades), [1, 6, 15, 1]);
+        let state = scheduler.cards()[&pair("C4", "E4")];
+        assert_eq!(state.repetitions, 0);
+        assert_eq!(state.due, clock.today().plus(1));
+        assert!((state.ease - 1.96).abs() < 1e-5);
+
+        let grades = [Grade::GOOD, Grade::GOOD, Grade::GOOD];
+        assert_eq!(intervals(&mut scheduler, &clock, &grades), [1, 6, 12]);
+    }
+
+    #[test]
+    fn due_cards_come_most_overdue_first() {
+        let clock = ManualClock::new(START);
+        let cards = HashMap::from([
+            (pair("C4", "D4"), ReviewState::new(START.plus(2))),
+            (pair("C4", "E4"), ReviewState::new(START)),
+            (pair("C4", "F4"), ReviewState::new(START.minus(3))),
+            (pair("C4", "G4"), ReviewState::new(START.minus(1))),
+        ]);
+        let scheduler = Scheduler::with_cards(Box::new(clock.clone()), cards);
+        assert_eq!(
+            scheduler.due_today(),
+            [pair("C4", "F4"), pair("C4", "G4"), pair("C4", "E4")]
+        );
+        assert_eq!(scheduler.next_due(), Some(pair("C4", "F4")));
+
+        clock.advance(2);
+        assert_eq!(scheduler.due_today().len(), 4);
+        assert_eq!(scheduler.due_today().last(), Some(&pair("C4", "D4")));
+    }
+
+    // pairs from C4 to every note above it, one after another
+    struct Ascending(u8);
+
+    impl NoteSequence for Ascending {
+        type Item = NoteTuple;
+
+        fn next_note(&mut self) -> NoteTuple {
+            self.0 += 1;
+            NoteTuple::new(
+                Note::from_midi_number(60).unwrap(),
+                Note::from_midi_number(60 + self.0).unwrap(),
+            )
+        }
+    }
+
+    fn due_sequence(
+        clock: &ManualClock,
+        cards: HashMap<Card, ReviewState>,
+        introduced: NewCards,
+    ) -> DueNoteSequence {
+        // the source moves on from the pairs that are already known
+        let known = cards.len() as u8;
+        DueNoteSequence::new(
+            ChaCha12Rng::seed_from_u64(0),
+            Scheduler::with_cards(Box::new(clock.clone()), cards),
+            Box::new(Ascending(known)),
+            2,
+            introduced,
+        )
+    }
+
+    // answers right and returns the tuple that was asked
+    fn answer(sequence: &mut DueNoteSequence) -> NoteTuple {
+        let note_tuple = sequence.next_note();
+        sequence.review(&NoteGuess::new(
+            note_tuple.clone(),
+            note_tuple.reference(),
+            Duration::from_secs(1),
+        ));
+        note_tuple
+    }
+
+    #[test]
+    fn only_so_many_new_cards_are_introduced_a_day() {
+        let clock = ManualClock::new(START);
+        let mut sequence = due_sequence(&clock, HashMap::new(), NewCards::default());
+        answer(&mut sequence);
+        answer(&mut sequence);
+        // once the limit is reached the known cards are asked early
+        for _ in 0..3 {
+            sequence.next_note();
+        }
+        assert_eq!(sequence.scheduler().cards().len(), 2);
+        assert_eq!(
+            sequence.introduced(),
+            NewCards {
+                day: START,
+                count: 2
+            }
+        );
+
+        // the count carries over to the next session on the same day
+        let cards = sequence.scheduler().cards().clone();
+        let mut sequence = due_sequence(&clock, cards, sequence.introduced());
+        for _ in 0..3 {
+            sequence.next_note();
+        }
+        assert_eq!(sequence.scheduler().cards().len(), 2);
+
+        // the due cards come first the next day, then new ones again
+        clock.advance(1);
+        let cards = sequence.scheduler().cards().clone();
+        let mut sequence = due_sequence(&clock, cards, sequence.introduced());
+        let due = [answer(&mut sequence), answer(&mut sequence)];
+        assert!(
+            due.iter()
+                .all(|note_tuple| note_tuple.divergence() <= note("D4"))
+        );
+        answer(&mut sequence);
+        answer(&mut sequence);
+        assert_eq!(sequence.scheduler().cards().len(), 4);
+        assert_eq!(
+            sequence.introduced(),
+            NewCards {
+                day: START.plus(1),
+                count: 2
+            }
+        );
+    }
+
+    #[test]
+    fn interval_cards_are_graded_on_any_reference() {
+        let clock = ManualClock::new(START);
+        let minor_third: Interval = "m3".parse().unwrap();
+        let third = Card::Interval(minor_third);
+        let cards = HashMap::from([(third, ReviewState::new(START))]);
+        let mut sequence = due_sequence(&clock, cards, NewCards::default());
+        let note_tuple = answer(&mut sequence);
+        assert_eq!(
+            note_tuple.divergence(),
+            note_tuple.reference() + minor_third
+        );
+
+        // the card moves off today instead of being asked again
+        let state = sequence.scheduler().cards()[&third];
+        assert_eq!(state.repetitions, 1);
+        assert_eq!(state.due, START.plus(1));
+        assert!(sequence.scheduler().due_today().is_empty());
+        assert_eq!(sequence.scheduler().cards().len(), 1);
+    }
+}
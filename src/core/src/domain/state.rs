@@ -1,12 +1,27 @@
+use std::{collections::HashMap, time::Instant};
+
 use anyhow::{Result, bail};
-use rand::SeedableRng;
+use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha12Rng;
 
-use crate::interfaces::ui::NoteGuess;
+use crate::interfaces::ui::{
+    ChordGuess, DictationGuess, GameMode, IntervalGuess, NoteGuess, ScaleGuess,
+};
 
 use super::{
-    notes::Note,
-    random::{NoteSequence, NoteTuple, RandomNoteSequence},
+    chord::{Chord, ChordQuality},
+    dictation::{AnswerNote, Melody},
+    exercise::Exercise,
+    interval::{Interval, IntervalPlayback},
+    notes::{Accidental, Note, NoteLetter},
+    profile::{Profile, Settings},
+    random::{
+        AdaptiveNoteSequence, NoteSequence, NoteTuple, RandomChordSequence, RandomIntervalSequence,
+        RandomMelodySequence, RandomNoteSequence, RandomNoteSequenceConfig, RandomScaleSequence,
+    },
+    scale::{ScaleKind, ScaleRun},
+    srs::{Card, DueNoteSequence, NewCards, ReviewState, Scheduler, SystemClock},
+    statistics::RandomModeStatistics,
 };
 
 pub enum State {
@@ -25,48 +40,434 @@ pub struct MainMenuState {}
 
 pub enum GameModeState {
     RandomMode(Box<RandomMode>),
+    IntervalMode(Box<IntervalMode>),
+    ChordMode(Box<ChordMode>),
+    ScaleMode(Box<ScaleMode>),
+    DictationMode(Box<DictationMode>),
+    ReviewMode(Box<ReviewMode>),
 }
 
 impl Default for GameModeState {
     fn default() -> Self {
-        GameModeState::RandomMode(Box::default())
+        GameModeState::new(GameMode::default())
+    }
+}
+
+impl GameModeState {
+    pub fn new(mode: GameMode) -> Self {
+        match mode {
+            GameMode::Random => GameModeState::RandomMode(Box::default()),
+            GameMode::Interval => GameModeState::IntervalMode(Box::default()),
+            GameMode::Chord => GameModeState::ChordMode(Box::default()),
+            GameMode::Scale => GameModeState::ScaleMode(Box::default()),
+            GameMode::Dictation => GameModeState::DictationMode(Box::default()),
+            GameMode::Review => GameModeState::ReviewMode(Box::default()),
+        }
+    }
+
+    // picks up where the player left off in the modes that keep progress, the
+    // same seed gives the same exercises for the same progress
+    pub fn restore(mode: GameMode, profile: &Profile, settings: &Settings, seed: u64) -> Self {
+        let rng = ChaCha12Rng::seed_from_u64(seed);
+        match mode {
+            GameMode::Random => GameModeState::RandomMode(Box::new(RandomMode::new(
+                profile.statistics.clone(),
+                settings.note_sequence(),
+                rng,
+            ))),
+            GameMode::Interval => {
+                GameModeState::IntervalMode(Box::new(IntervalMode::with_rng(rng)))
+            }
+            GameMode::Chord => GameModeState::ChordMode(Box::new(ChordMode::with_rng(rng))),
+            GameMode::Scale => GameModeState::ScaleMode(Box::new(ScaleMode::with_rng(rng))),
+            GameMode::Dictation => {
+                GameModeState::DictationMode(Box::new(DictationMode::with_rng(rng)))
+            }
+            GameMode::Review => GameModeState::ReviewMode(Box::new(ReviewMode::new(
+                profile.deck.clone(),
+                profile.new_cards,
+                settings.new_cards_per_day,
+                settings.note_sequence(),
+                rng,
+            ))),
+        }
     }
 }
 
+// a shared exercise plays the same tuples for everyone, so it does not adapt
+enum RandomModeSequence {
+    Adaptive(AdaptiveNoteSequence),
+    Exercise(RandomNoteSequence),
+}
+
 pub struct RandomMode {
-    note_generator: RandomNoteSequence,
+    note_generator: RandomModeSequence,
     current_note_tuple: Option<NoteTuple>,
-    _statistics: RandomModeStatistics,
+    asked_at: Instant,
+    // only the first answer to the current tuple is counted
+    answered: bool,
+    statistics: RandomModeStatistics,
 }
 
 impl Default for RandomMode {
     fn default() -> Self {
-        let rng = ChaCha12Rng::from_os_rng();
-        let note_generator = RandomNoteSequence::new(rng);
+        Self::new(
+            RandomModeStatistics::default(),
+            RandomNoteSequenceConfig::default(),
+            ChaCha12Rng::from_os_rng(),
+        )
+    }
+}
+
+impl RandomMode {
+    pub fn new(
+        statistics: RandomModeStatistics,
+        config: RandomNoteSequenceConfig,
+        rng: ChaCha12Rng,
+    ) -> Self {
+        let mut note_generator = AdaptiveNoteSequence::with_config(rng, config);
+        note_generator.adapt(&statistics);
         Self {
-            note_generator,
+            note_generator: RandomModeSequence::Adaptive(note_generator),
+            current_note_tuple: None,
+            asked_at: Instant::now(),
+            answered: false,
+            statistics,
+        }
+    }
+
+    // the answers still count towards the statistics
+    pub fn exercise(exercise: &Exercise, statistics: RandomModeStatistics) -> Self {
+        Self {
+            note_generator: RandomModeSequence::Exercise(exercise.note_sequence()),
             current_note_tuple: None,
-            _statistics: RandomModeStatistics,
+            asked_at: Instant::now(),
+            answered: false,
+            statistics,
+        }
+    }
+
+    pub fn next_note(&mut self) -> NoteTuple {
+        let note_tuple = match &mut self.note_generator {
+            RandomModeSequence::Adaptive(sequence) => sequence.next_note(),
+            RandomModeSequence::Exercise(sequence) => sequence.next_note(),
+        };
+        self.current_note_tuple = Some(note_tuple.clone());
+        self.asked_at = Instant::now();
+        self.answered = false;
+        note_tuple
+    }
+
+    pub fn note_guess(&mut self, note: Note) -> Result<NoteGuess> {
+        match &self.current_note_tuple {
+            Some(note_tuple) => {
+                let note_guess = NoteGuess::new(note_tuple.clone(), note, self.asked_at.elapsed());
+                if !self.answered {
+                    self.answered = true;
+                    self.statistics.record(
+                        note_tuple.reference(),
+                        note_tuple.divergence(),
+                        note_guess.correct,
+                        note_guess.response_time,
+                    );
+                    if let RandomModeSequence::Adaptive(sequence) = &mut self.note_generator {
+                        sequence.adapt(&self.statistics);
+                    }
+                }
+                Ok(note_guess)
+            }
+            None => bail!("Can not compare notes because no tuple was created first"),
+        }
+    }
+
+    pub fn statistics(&self) -> &RandomModeStatistics {
+        &self.statistics
+    }
+
+    // an exercise keeps its own notes
+    pub fn configure(&mut self, config: RandomNoteSequenceConfig) {
+        if let RandomModeSequence::Adaptive(sequence) = &mut self.note_generator {
+            sequence.configure(config);
+            sequence.adapt(&self.statistics);
         }
     }
 }
 
-impl RandomMode {
+// plays the note tuples that are due for review, the first answer to each
+// one is graded
+pub struct ReviewMode {
+    note_generator: DueNoteSequence,
+    current_note_tuple: Option<NoteTuple>,
+    asked_at: Instant,
+    reviewed: bool,
+}
+
+impl Default for ReviewMode {
+    fn default() -> Self {
+        let settings = Settings::default();
+        Self::new(
+            HashMap::new(),
+            NewCards::default(),
+            settings.new_cards_per_day,
+            settings.note_sequence(),
+            ChaCha12Rng::from_os_rng(),
+        )
+    }
+}
+
+impl ReviewMode {
+    // new cards are drawn from the configured notes, due ones are played as
+    // they were introduced
+    pub fn new(
+        cards: HashMap<Card, ReviewState>,
+        introduced: NewCards,
+        new_per_day: usize,
+        config: RandomNoteSequenceConfig,
+        mut rng: ChaCha12Rng,
+    ) -> Self {
+        let new_cards = AdaptiveNoteSequence::with_config(ChaCha12Rng::from_rng(&mut rng), config);
+        let note_generator = DueNoteSequence::new(
+            rng,
+            Scheduler::with_cards(Box::new(SystemClock), cards),
+            Box::new(new_cards),
+            new_per_day,
+            introduced,
+        );
+        Self {
+            note_generator,
+            current_note_tuple: None,
+            asked_at: Instant::now(),
+            reviewed: false,
+        }
+    }
+
     pub fn next_note(&mut self) -> NoteTuple {
         let note_tuple = self.note_generator.next_note();
         self.current_note_tuple = Some(note_tuple.clone());
+        self.asked_at = Instant::now();
+        self.reviewed = false;
         note_tuple
     }
 
     pub fn note_guess(&mut self, note: Note) -> Result<NoteGuess> {
         match &self.current_note_tuple {
-            Some(note_tuple) => Ok(NoteGuess::new(note_tuple.clone(), note)),
+            Some(note_tuple) => {
+                let note_guess = NoteGuess::new(note_tuple.clone(), note, self.asked_at.elapsed());
+                if !self.reviewed {
+                    self.note_generator.review(&note_guess);
+                    self.reviewed = true;
+                }
+                Ok(note_guess)
+            }
             None => bail!("Can not compare notes because no tuple was created first"),
         }
     }
+
+    pub fn due_today(&self) -> usize {
+        self.note_generator.due_today()
+    }
+
+    pub fn scheduler(&self) -> &Scheduler {
+        self.note_generator.scheduler()
+    }
+
+    pub fn introduced(&self) -> NewCards {
+        self.note_generator.introduced()
+    }
 }
 
-//TODO: fill with content
-// -> can be used for some adaptive learning approach
-#[derive(Default)]
-pub struct RandomModeStatistics;
+pub struct IntervalMode {
+    interval_generator: RandomIntervalSequence,
+    rng: ChaCha12Rng,
+    intervals: Vec<Interval>,
+    current_note_tuple: Option<NoteTuple>,
+}
+
+impl Default for IntervalMode {
+    fn default() -> Self {
+        Self::with_rng(ChaCha12Rng::from_os_rng())
+    }
+}
+
+impl IntervalMode {
+    pub fn with_rng(mut rng: ChaCha12Rng) -> Self {
+        let intervals = Interval::SIMPLE.to_vec();
+        let interval_generator =
+            RandomIntervalSequence::new(ChaCha12Rng::from_rng(&mut rng), intervals.clone());
+        Self {
+            interval_generator,
+            rng,
+            intervals,
+            current_note_tuple: None,
+        }
+    }
+
+    pub fn choices(&self) -> Vec<Interval> {
+        self.intervals.clone()
+    }
+
+    pub fn next_interval(&mut self) -> (NoteTuple, IntervalPlayback) {
+        let note_tuple = self.interval_generator.next_note();
+        let playback = if self.rng.random_ratio(1, 3) {
+            IntervalPlayback::Harmonic
+        } else if note_tuple.is_ascending() {
+            IntervalPlayback::Ascending
+        } else {
+            IntervalPlayback::Descending
+        };
+        self.current_note_tuple = Some(note_tuple.clone());
+        (note_tuple, playback)
+    }
+
+    pub fn interval_guess(&mut self, interval: Interval) -> Result<IntervalGuess> {
+        match &self.current_note_tuple {
+            Some(note_tuple) => Ok(IntervalGuess::new(note_tuple.clone(), interval)),
+            None => bail!("Can not judge the interval because no tuple was created first"),
+        }
+    }
+}
+
+pub struct ChordMode {
+    chord_generator: RandomChordSequence,
+    qualities: Vec<ChordQuality>,
+    current_chord: Option<Chord>,
+}
+
+impl Default for ChordMode {
+    fn default() -> Self {
+        Self::with_rng(ChaCha12Rng::from_os_rng())
+    }
+}
+
+impl ChordMode {
+    pub fn with_rng(rng: ChaCha12Rng) -> Self {
+        let qualities = ChordQuality::ALL.to_vec();
+        let chord_generator = RandomChordSequence::new(rng, qualities.clone());
+        Self {
+            chord_generator,
+            qualities,
+            current_chord: None,
+        }
+    }
+
+    pub fn choices(&self) -> Vec<ChordQuality> {
+        self.qualities.clone()
+    }
+
+    pub fn next_chord(&mut self) -> Chord {
+        let chord = self.chord_generator.next_chord();
+        self.current_chord = Some(chord);
+        chord
+    }
+
+    pub fn chord_guess(&mut self, quality: ChordQuality) -> Result<ChordGuess> {
+        match &self.current_chord {
+            Some(chord) => Ok(ChordGuess::new(*chord, quality)),
+            None => bail!("Can not judge the chord because no chord was created first"),
+        }
+    }
+}
+
+pub struct ScaleMode {
+    scale_generator: RandomScaleSequence,
+    kinds: Vec<ScaleKind>,
+    current_run: Option<ScaleRun>,
+}
+
+impl Default for ScaleMode {
+    fn default() -> Self {
+        Self::with_rng(ChaCha12Rng::from_os_rng())
+    }
+}
+
+impl ScaleMode {
+    pub fn with_rng(rng: ChaCha12Rng) -> Self {
+        let kinds = ScaleKind::ALL.to_vec();
+        let scale_generator = RandomScaleSequence::new(rng, kinds.clone());
+        Self {
+            scale_generator,
+            kinds,
+            current_run: None,
+        }
+    }
+
+    pub fn choices(&self) -> Vec<ScaleKind> {
+        self.kinds.clone()
+    }
+
+    pub fn next_run(&mut self) -> ScaleRun {
+        let run = self.scale_generator.next_note();
+        self.current_run = Some(run);
+        run
+    }
+
+    pub fn scale_guess(&mut self, kind: ScaleKind) -> Result<ScaleGuess> {
+        match &self.current_run {
+            Some(run) => Ok(ScaleGuess::new(*run, kind)),
+            None => bail!("Can not judge the scale because no run was created first"),
+        }
+    }
+}
+
+pub struct DictationMode {
+    melody_generator: RandomMelodySequence,
+    compare_rhythm: bool,
+    current_melody: Option<Melody>,
+    heard: Vec<AnswerNote>,
+}
+
+impl Default for DictationMode {
+    fn default() -> Self {
+        Self::with_rng(ChaCha12Rng::from_os_rng())
+    }
+}
+
+impl DictationMode {
+    pub fn with_rng(rng: ChaCha12Rng) -> Self {
+        let melody_generator = RandomMelodySequence::new(
+            rng,
+            Note::new(NoteLetter::C, Accidental::Natural, 3),
+            Note::new(NoteLetter::C, Accidental::Natural, 5),
+            3..=8,
+        );
+        Self {
+            melody_generator,
+            compare_rhythm: true,
+            current_melody: None,
+            heard: Vec::new(),
+        }
+    }
+
+    pub fn next_melody(&mut self) -> Melody {
+        let melody = self.melody_generator.next_note();
+        self.current_melody = Some(melody.clone());
+        self.heard.clear();
+        melody
+    }
+
+    pub fn dictation_guess(&mut self, answer: Vec<AnswerNote>) -> Result<DictationGuess> {
+        self.heard.clear();
+        match &self.current_melody {
+            Some(melody) => Ok(DictationGuess::new(
+                melody.clone(),
+                answer,
+                self.compare_rhythm,
+            )),
+            None => bail!("Can not compare the answer because no melody was created first"),
+        }
+    }
+
+    // collects played notes until there are as many as in the melody, then
+    // judges them like a typed answer
+    pub fn note_heard(&mut self, note: Note) -> Result<Option<DictationGuess>> {
+        let Some(melody) = &self.current_melody else {
+            bail!("Can not compare the note because no melody was created first");
+        };
+        self.heard.push(AnswerNote::new(note, None));
+        if self.heard.len() < melody.len() {
+            return Ok(None);
+        }
+        let answer = std::mem::take(&mut self.heard);
+        self.dictation_guess(answer).map(Some)
+    }
+}
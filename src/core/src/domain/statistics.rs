@@ -0,0 +1,450 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Duration,
+};
+
+use super::{notes::Note, profile::Session, srs::Day};
+
+// a miss counts this much more than being as slow as twice the average
+const SLOWNESS_FACTOR: f64 = 0.5;
+// keeps mastered material in rotation
+const MIN_WEAKNESS: f64 = 0.05;
+const REPORT_DAYS: u32 = 30;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Accuracy {
+    pub attempts: u32,
+    pub correct: u32,
+    pub total_response_time: Duration,
+}
+
+impl Accuracy {
+    pub fn record(&mut self, correct: bool, response_time: Duration) {
+        self.attempts += 1;
+        if correct {
+            self.correct += 1;
+        }
+        self.total_response_time += response_time;
+    }
+
+    pub fn ratio(&self) -> Option<f64> {
+        (self.attempts > 0).then(|| self.correct as f64 / self.attempts as f64)
+    }
+
+    pub fn mean_response_time(&self) -> Option<Duration> {
+        (self.attempts > 0).then(|| self.total_response_time / self.attempts)
+    }
+
+    // how much practice this needs between MIN_WEAKNESS and about 1.5, unseen
+    // material starts in the middle so it gets tried early
+    pub fn weakness(&self, overall: &Accuracy) -> f64 {
+        let smoothed = (self.correct as f64 + 1.0) / (self.attempts as f64 + 2.0);
+        let slowness = match (self.mean_response_time(), overall.mean_response_time()) {
+            (Some(mean), Some(overall)) if !overall.is_zero() => {
+                (mean.as_secs_f64() / overall.as_secs_f64() - 1.0).clamp(0.0, 1.0)
+            }
+            _ => 0.0,
+        };
+        (1.0 - smoothed + SLOWNESS_FACTOR * slowness).max(MIN_WEAKNESS)
+    }
+}
+
+// accuracy of the first answer to every note tuple, split up by the offset of
+// the divergence, the octave and the pitch class of the reference
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RandomModeStatistics {
+    overall: Accuracy,
+    intervals: HashMap<i16, Accuracy>,
+    registers: HashMap<i8, Accuracy>,
+    pitch_classes: HashMap<u8, Accuracy>,
+}
+
+impl RandomModeStatistics {
+    pub fn new(
+        overall: Accuracy,
+        intervals: HashMap<i16, Accuracy>,
+        registers: HashMap<i8, Accuracy>,
+        pitch_classes: HashMap<u8, Accuracy>,
+    ) -> Self {
+        Self {
+            overall,
+            intervals,
+            registers,
+            pitch_classes,
+        }
+    }
+
+    pub fn record(
+        &mut self,
+        reference: Note,
+        divergence: Note,
+        correct: bool,
+        response_time: Duration,
+    ) {
+        let offset = divergence.pitch() - reference.pitch();
+        let pitch_class = reference.pitch().rem_euclid(12) as u8;
+
+        self.overall.record(correct, response_time);
+        for accuracy in [
+            self.intervals.entry(offset).or_default(),
+            self.registers.entry(reference.octave).or_default(),
+            self.pitch_classes.entry(pitch_class).or_default(),
+        ] {
+            accuracy.record(correct, response_time);
+        }
+    }
+
+    pub fn overall(&self) -> Accuracy {
+        self.overall
+    }
+
+    // keyed by semitones from the reference to the divergence
+    pub fn interval(&self, offset: i16) -> Accuracy {
+        self.intervals.get(&offset).copied().unwrap_or_default()
+    }
+
+    pub fn register(&self, octave: i8) -> Accuracy {
+        self.registers.get(&octave).copied().unwrap_or_default()
+    }
+
+    pub fn pitch_class(&self, pitch_class: u8) -> Accuracy {
+        self.pitch_classes
+            .get(&pitch_class)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn intervals(&self) -> &HashMap<i16, Accuracy> {
+        &self.intervals
+    }
+
+    pub fn registers(&self) -> &HashMap<i8, Accuracy> {
+        &self.registers
+    }
+
+    pub fn pitch_classes(&self) -> &HashMap<u8, Accuracy> {
+        &self.pitch_classes
+    }
+
+    pub fn interval_weakness(&self, offset: i16) -> f64 {
+        self.interval(offset).weakness(&self.overall)
+    }
+
+    pub fn register_weakness(&self, octave: i8) -> f64 {
+        self.register(octave).weakness(&self.overall)
+    }
+
+    pub fn pitch_class_weakness(&self, pitch_class: u8) -> f64 {
+        self.pitch_class(pitch_class).weakness(&self.overall)
+    }
+}
+
+// what the dashboard shows, the breakdowns come from the first answers in the
+// random mode and the history from every saved answer
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StatisticsReport {
+    pub overall: Accuracy,
+    pub intervals: Vec<(i16, Accuracy)>,
+    pub registers: Vec<(i8, Accuracy)>,
+    // the last REPORT_DAYS days up to today, oldest first
+    pub daily: Vec<(Day, Accuracy)>,
+    // every week with answers by its monday, oldest first
+    pub weekly: Vec<(Day, Accuracy)>,
+    pub current_streak: u32,
+    pub best_streak: u32,
+    // days in a row with answers, today may still be missing
+    pub practice_days: u32,
+}
+
+impl StatisticsReport {
+    pub fn new(statistics: &RandomModeStatistics, sessions: &[Session], today: Day) -> Self {
+        let mut guesses: Vec<_> = sessions
+            .iter()
+            .flat_map(|session| &session.guesses)
+            .collect();
+        guesses.sort_by_key(|guess| guess.at);
+
+        let mut days: BTreeMap<Day, Accuracy> = BTreeMap::new();
+        let mut weeks: BTreeMap<Day, Accuracy> = BTreeMap::new();
+        let (mut current_streak, mut best_streak) = (0, 0);
+        for guess in guesses {
+            let day = Day::from(guess.at);
+            days.entry(day)
+                .or_default()
+                .record(guess.correct, guess.response_time);
+            weeks
+                .entry(day.week_start())
+                .or_default()
+                .record(guess.correct, guess.response_time);
+
+            if guess.correct {
+                current_streak += 1;
+                best_streak = best_streak.max(current_streak);
+            } else {
+                current_streak = 0;
+            }
+        }
+
+        let daily = (0..REPORT_DAYS)
+            .rev()
+            .map(|ago| {
+                let day = today.minus(ago);
+                (day, days.get(&day).copied().unwrap_or_default())
+            })
+            .collect();
+        let last_day = if days.contains_key(&today) {
+            today
+        } else {
+            today.minus(1)
+        };
+        let practice_days = (0..)
+            .take_while(|ago| days.contains_key(&last_day.minus(*ago)))
+            .count() as u32;
+
+        Self {
+            overall: statistics.overall,
+            intervals: sorted(&statistics.intervals),
+            registers: sorted(&statistics.registers),
+            daily,
+            weekly: weeks.into_iter().collect(),
+            current_streak,
+            best_streak,
+            practice_days,
+        }
+    }
+}
+
+fn sorted<K: Ord + Copy>(accuracies: &HashMap<K, Accuracy>) -> Vec<(K, Accuracy)> {
+    let mut sorted: Vec<_> = accuracies
+        .iter()
+        .map(|(key, accuracy)| (*key, *accuracy))
+        .collect();
+    sorted.sort_by_key(|(key, _)| *key);
+    sorted
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use super::*;
+    use crate::{
+        domain::{
+            profile::GuessRecord,
+            srs::{Clock, ManualClock},
+        },
+        interfaces::ui::GameMode,
+    };
+
+    fn accuracy(attempts: u32, correct: u32, seconds: f64) -> Accuracy {
+        Accuracy {
+            attempts,
+            correct,
+            total_response_time: Duration::from_secs_f64(seconds * attempts as f64),
+        }
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn unseen_material_is_half_weak() {
+        let unseen = Accuracy::default();
+        assert_eq!(unseen.ratio(), None);
+        assert_eq!(unseen.mean_response_time(), None);
+        assert!(close(unseen.weakness(&Accuracy::default()), 0.5));
+        assert!(close(unseen.weakness(&accuracy(10, 5, 1.0)), 0.5));
+    }
+
+    #[test]
+    fn misses_make_material_weaker() {
+        let overall = accuracy(100, 50, 1.0);
+        // smoothed as if one right and one wrong answer came first
+        assert!(close(accuracy(8, 0, 1.0).weakness(&overall), 0.9));
+        assert!(close(accuracy(8, 4, 1.0).weakness(&overall), 0.5));
+        assert!(close(accuracy(8, 6, 1.0).weakness(&overall), 0.3));
+        // mastered material stays in rotation
+        assert_eq!(accuracy(98, 98, 1.0).weakness(&overall), MIN_WEAKNESS);
+    }
+
+    #[test]
+    fn slow_answers_make_material_weaker() {
+        let overall = accuracy(100, 50, 1.0);
+        let weakness = |seconds| accuracy(8, 4, seconds).weakness(&overall);
+        assert!(close(weakness(0.5), 0.5));
+        assert!(close(weakness(1.0), 0.5));
+        assert!(close(weakness(1.5), 0.5 + SLOWNESS_FACTOR * 0.5));
+        assert!(close(weakness(2.0), 0.5 + SLOWNESS_FACTOR));
+        // being slower than twice the average counts no more
+        assert!(close(weakness(10.0), 0.5 + SLOWNESS_FACTOR));
+        // without times only the misses count
+        let untimed = accuracy(100, 50, 0.0);
+        assert!(close(accuracy(8, 4, 2.0).weakness(&untimed), 0.5));
+    }
+
+    #[test]
+    fn answers_are_recorded_by_offset_register_and_pitch_class() {
+        let note = |s: &str| s.parse::<Note>().unwrap();
+        let mut statistics = RandomModeStatistics::default();
+        let second = Duration::from_secs(1);
+        statistics.record(note("Db3"), note("F3"), true, second);
+        statistics.record(note("C#2"), note("A1"), false, 3 * second);
+        statistics.record(note("E3"), note("G#3"), false, second);
+
+        assert_eq!(statistics.overall().attempts, 3);
+        assert_eq!(statistics.overall().correct, 1);
+        assert_eq!(
+            statistics.overall().mean_response_time(),
+            Some(5 * second / 3)
+        );
+
+        assert_eq!(statistics.interval(4).attempts, 2);
+        assert_eq!(statistics.interval(4).correct, 1);
+        assert_eq!(statistics.interval(-4).attempts, 1);
+        assert_eq!(statistics.interval(3), Accuracy::default());
+        assert_eq!(statistics.register(3).attempts, 2);
+        assert_eq!(statistics.register(2).correct, 0);
+        // Db and C# are the same pitch class
+        assert_eq!(statistics.pitch_class(1).attempts, 2);
+        assert_eq!(statistics.pitch_class(4).attempts, 1);
+        assert_eq!(statistics.intervals().len(), 2);
+
+        assert!(statistics.interval_weakness(-4) > statistics.interval_weakness(4));
+        assert!(close(statistics.interval_weakness(3), 0.5));
+    }
+
+    // a monday
+    const WEEK: Day = Day(19_996);
+
+    fn guess(day: Day, hour: u64, correct: bool, seconds: u64) -> GuessRecord {
+        let note = "C4".parse().unwrap();
+        GuessRecord {
+            reference: note,
+            divergence: note,
+            played: note,
+            correct,
+            at: UNIX_EPOCH + Duration::from_secs(day.0 as u64 * 86_400 + hour * 3_600),
+            response_time: Duration::from_secs(seconds),
+        }
+    }
+
+    fn session(guesses: Vec<GuessRecord>) -> Session {
+        Session {
+            mode: GameMode::Random,
+            seed: None,
+            exercise: None,
+            started_at: guesses[0].at,
+            ended_at: None,
+            guesses,
+        }
+    }
+
+    // two weeks of practice with a gap, the later session was saved first
+    fn sessions() -> Vec<Session> {
+        vec![
+            session(vec![
+                guess(WEEK.plus(7), 9, true, 2),
+                guess(WEEK.plus(9), 9, false, 1),
+                guess(WEEK.plus(9), 10, true, 2),
+            ]),
+            session(vec![
+                guess(WEEK, 9, true, 1),
+                guess(WEEK, 9, true, 2),
+                guess(WEEK, 10, false, 3),
+                guess(WEEK.plus(1), 20, true, 1),
+                guess(WEEK.plus(6), 23, true, 1),
+                guess(WEEK.plus(6), 23, true, 1),
+            ]),
+        ]
+    }
+
+    #[test]
+    fn the_report_counts_answers_by_day_and_week() {
+        let clock = ManualClock::new(WEEK.plus(10));
+        let report = StatisticsReport::new(&Default::default(), &sessions(), clock.today());
+
+        assert_eq!(report.daily.len(), REPORT_DAYS as usize);
+        assert_eq!(report.daily[0].0, WEEK.minus(19));
+        assert_eq!(report.daily[29], (WEEK.plus(10), Accuracy::default()));
+        assert_eq!(report.daily[19], (WEEK, accuracy(3, 2, 2.0)));
+        assert_eq!(report.daily[20], (WEEK.plus(1), accuracy(1, 1, 1.0)));
+        assert_eq!(report.daily[28], (WEEK.plus(9), accuracy(2, 1, 1.5)));
+        let practiced: Vec<Day> = report
+            .daily
+            .iter()
+            .filter(|(_, accuracy)| accuracy.attempts > 0)
+            .map(|(day, _)| *day)
+            .collect();
+        assert_eq!(
+            practiced,
+            [WEEK, WEEK.plus(1), WEEK.plus(6), WEEK.plus(7), WEEK.plus(9)]
+        );
+
+        // sunday still belongs to the week before
+        assert_eq!(
+            report.weekly,
+            [
+                (WEEK, accuracy(6, 5, 1.5)),
+                (WEEK.plus(7), accuracy(3, 2, 5.0 / 3.0))
+            ]
+        );
+        assert_eq!(WEEK.plus(6).week_start(), WEEK);
+        assert_eq!(WEEK.plus(7).week_start(), WEEK.plus(7));
+    }
+
+    #[test]
+    fn streaks_follow_the_answers_in_order() {
+        let report = StatisticsReport::new(&Default::default(), &sessions(), WEEK.plus(10));
+        // right, right, wrong, then four right, a wrong and a right
+        assert_eq!(report.best_streak, 4);
+        assert_eq!(report.current_streak, 1);
+
+        let report = StatisticsReport::new(&Default::default(), &[], WEEK);
+        assert_eq!(report.best_streak, 0);
+        assert_eq!(report.current_streak, 0);
+        assert_eq!(report.practice_days, 0);
+        assert!(report.weekly.is_empty());
+    }
+
+    #[test]
+    fn practice_days_may_still_be_missing_today() {
+        let clock = ManualClock::new(WEEK.plus(9));
+        let mut sessions = sessions();
+        let practice_days = |sessions: &[Session], today| {
+            StatisticsReport::new(&Default::default(), sessions, today).practice_days
+        };
+        // the gap the day before breaks the run
+        assert_eq!(practice_days(&sessions, clock.today()), 1);
+        // nothing yet today, the run up to yesterday still counts
+        clock.advance(1);
+        assert_eq!(practice_days(&sessions, clock.today()), 1);
+        sessions.push(session(vec![guess(clock.today(), 8, true, 1)]));
+        assert_eq!(practice_days(&sessions, clock.today()), 2);
+        // a day without practice ends it
+        clock.advance(2);
+        assert_eq!(practice_days(&sessions, clock.today()), 0);
+
+        // the week before has a run of two days
+        assert_eq!(practice_days(&sessions, WEEK.plus(1)), 2);
+        assert_eq!(practice_days(&sessions, WEEK.plus(2)), 2);
+        assert_eq!(practice_days(&sessions, WEEK.plus(3)), 0);
+        assert_eq!(practice_days(&sessions, WEEK.plus(7)), 2);
+    }
+
+    #[test]
+    fn the_report_sorts_the_breakdowns() {
+        let note = |s: &str| s.parse::<Note>().unwrap();
+        let mut statistics = RandomModeStatistics::default();
+        let second = Duration::from_secs(1);
+        statistics.record(note("C4"), note("G4"), true, second);
+        statistics.record(note("C2"), note("A1"), false, second);
+        statistics.record(note("C3"), note("E3"), true, second);
+        let report = StatisticsReport::new(&statistics, &[], WEEK);
+        assert_eq!(report.overall, statistics.overall());
+        let offsets: Vec<i16> = report.intervals.iter().map(|(offset, _)| *offset).collect();
+        assert_eq!(offsets, [-3, 4, 7]);
+        let octaves: Vec<i8> = report.registers.iter().map(|(octave, _)| *octave).collect();
+        assert_eq!(octaves, [2, 3, 4]);
+    }
+}
@@ -1,7 +1,271 @@
-use std::time::Duration;
+use std::{fmt, path::PathBuf, str::FromStr, time::Duration};
 
-use crate::domain::notes::Note;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+use tracing::error;
+
+use crate::domain::{
+    instrument::{Instrument, Sound},
+    notes::{Note, Tuning},
+};
 
 pub trait AudioInterfaceTrait: std::fmt::Debug + Send {
-    fn play_note(&mut self, note: Note, interval: Duration);
+    // takes effect from the next phrase on, the old settings stay in place if
+    // the new ones can not be applied
+    fn configure(&mut self, _settings: &AudioSettings) -> Result<()> {
+        Ok(())
+    }
+
+    // reports on the phrases that are played in the background, outputs that
+    // play while they are called have nothing to report
+    fn events(&mut self) -> Option<&mut UnboundedReceiver<AudioOutputMessage>> {
+        None
+    }
+
+    // plays the events one after another without gaps, a phrase that is
+    // still playing is cut short
+    fn play_phrase(&mut self, phrase: &[NoteEvent]);
+
+    // plays all notes at once for the given interval
+    fn play_notes(&mut self, notes: &[Note], interval: Duration) {
+        self.play_phrase(&[NoteEvent::new(notes.to_vec(), interval)]);
+    }
+
+    fn play_note(&mut self, note: Note, interval: Duration) {
+        self.play_notes(&[note], interval);
+    }
+}
+
+// notes that start together and sound for the duration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoteEvent {
+    pub notes: Vec<Note>,
+    pub duration: Duration,
+}
+
+impl NoteEvent {
+    pub fn new(notes: Vec<Note>, duration: Duration) -> Self {
+        Self { notes, duration }
+    }
+
+    pub fn note(note: Note, duration: Duration) -> Self {
+        Self::new(vec![note], duration)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioSettings {
+    pub output_device: Option<String>,
+    pub sound: Sound,
+    // the built-in soundfont if there is none
+    pub soundfont: Option<PathBuf>,
+    pub instrument: Instrument,
+    // how hard the notes are struck, from 1 to 127
+    pub velocity: u8,
+    // in percent
+    pub volume: u8,
+    pub concert_a: u16,
+}
+
+impl AudioSettings {
+    pub fn tuning(&self) -> Tuning {
+        Tuning::new(self.concert_a as f32)
+    }
+}
+
+// what is given up when the audio settings can't be applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioFallback {
+    pub default_device: bool,
+    pub built_in_soundfont: bool,
+    pub sine: bool,
+}
+
+impl AudioFallback {
+    // tried in this order, the last one only needs some output device
+    pub const ALL: [AudioFallback; 4] = [
+        AudioFallback {
+            default_device: true,
+            built_in_soundfont: false,
+            sine: false,
+        },
+        AudioFallback {
+            default_device: false,
+            built_in_soundfont: true,
+            sine: false,
+        },
+        AudioFallback {
+            default_device: true,
+            built_in_soundfont: true,
+            sine: false,
+        },
+        AudioFallback {
+            default_device: true,
+            built_in_soundfont: false,
+            sine: true,
+        },
+    ];
+
+    pub fn apply(&self, settings: &AudioSettings) -> AudioSettings {
+        let mut settings = settings.clone();
+        if self.default_device {
+            settings.output_device = None;
+        }
+        if self.built_in_soundfont {
+            settings.soundfont = None;
+            settings.instrument = Instrument::default();
+        }
+        if self.sine {
+            settings.sound = Sound::Sine;
+        }
+        settings
+    }
+}
+
+// "the default device and a sine wave"
+impl fmt::Display for AudioFallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<&str> = [
+            (self.default_device, "the default device"),
+            (self.built_in_soundfont, "the built-in soundfont"),
+            (self.sine, "a sine wave"),
+        ]
+        .into_iter()
+        .filter(|(given_up, _)| *given_up)
+        .map(|(_, part)| part)
+        .collect();
+        write!(f, "{}", parts.join(" and "))
+    }
+}
+
+// waits forever if the output has nothing to report
+pub async fn receive_output(audio: &mut dyn AudioInterfaceTrait) -> AudioOutputMessage {
+    match audio.events() {
+        Some(events) => events
+            .recv()
+            .await
+            .expect("infinite stream from audio output"),
+        None => std::future::pending().await,
+    }
+}
+
+#[async_trait]
+pub trait AudioInputInterfaceTrait: std::fmt::Debug + Send {
+    async fn receive(&mut self) -> AudioInputMessage;
+    fn send(&mut self, command: AudioInputCommand);
+}
+
+#[derive(Debug)]
+pub struct AudioInput {
+    sender: UnboundedSender<AudioInputCommand>,
+    receiver: UnboundedReceiver<AudioInputMessage>,
+}
+
+impl AudioInput {
+    pub fn new() -> (
+        Self,
+        UnboundedSender<AudioInputMessage>,
+        UnboundedReceiver<AudioInputCommand>,
+    ) {
+        let (command_sender, command_receiver) = unbounded_channel();
+        let (input_sender, input_receiver) = unbounded_channel();
+        let input = Self {
+            sender: command_sender,
+            receiver: input_receiver,
+        };
+
+        (input, input_sender, command_receiver)
+    }
+}
+
+#[async_trait]
+impl AudioInputInterfaceTrait for AudioInput {
+    async fn receive(&mut self) -> AudioInputMessage {
+        self.receiver
+            .recv()
+            .await
+            .expect("infinite stream from audio input")
+    }
+
+    fn send(&mut self, command: AudioInputCommand) {
+        if let Err(err) = self.sender.send(command) {
+            error!(?err, "Failed to send command to audio input");
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AudioInputCommand {
+    Start,
+    Stop,
+    Configure(InputSettings),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputSettings {
+    pub input_device: Option<String>,
+    pub concert_a: u16,
+    pub pitch_detection: PitchDetection,
+}
+
+impl InputSettings {
+    pub fn tuning(&self) -> Tuning {
+        Tuning::new(self.concert_a as f32)
+    }
+}
+
+// how the input finds the pitch of what is played into it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PitchDetection {
+    // in the time domain, the most precise for clean monophonic input
+    #[default]
+    Yin,
+    // from the harmonics in the spectrum, keeps working for bass notes with a
+    // weak or missing fundamental
+    Spectrum,
+}
+
+impl PitchDetection {
+    pub const ALL: [PitchDetection; 2] = [PitchDetection::Yin, PitchDetection::Spectrum];
+}
+
+impl fmt::Display for PitchDetection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PitchDetection::Yin => "yin",
+            PitchDetection::Spectrum => "spectrum",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for PitchDetection {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        PitchDetection::ALL
+            .into_iter()
+            .find(|detection| detection.to_string().eq_ignore_ascii_case(s))
+            .with_context(|| format!("'{s}' is not one of yin or spectrum"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AudioInputMessage {
+    NoteDetected(Note),
+    // the device went away, the default input listens from now on unless it
+    // couldn't be opened either
+    DeviceLost { recovered: bool },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioOutputMessage {
+    PhraseStarted,
+    // played to the end or cut short by the next phrase
+    PhraseFinished,
+    // the device went away, e.g. it was unplugged, and nothing plays until
+    // the output is configured again
+    DeviceLost,
 }
@@ -1,2 +1,3 @@
 pub mod audio;
+pub mod storage;
 pub mod ui;
@@ -0,0 +1,90 @@
+use anyhow::Result;
+
+use crate::domain::profile::{Profile, Session, Settings};
+
+pub trait StorageInterfaceTrait: std::fmt::Debug + Send {
+    fn load_profile(&mut self) -> Result<Profile>;
+    fn save_profile(&mut self, profile: &Profile) -> Result<()>;
+
+    fn load_settings(&mut self) -> Result<Settings>;
+    fn save_settings(&mut self, settings: &Settings) -> Result<()>;
+
+    // sessions are only ever appended, oldest first
+    fn save_session(&mut self, session: &Session) -> Result<()>;
+    fn sessions(&mut self) -> Result<Vec<Session>>;
+}
+
+// keeps everything until dropped, for tests and runs that should leave no
+// trace
+#[derive(Debug, Default, Clone)]
+pub struct InMemoryStorage {
+    profile: Profile,
+    settings: Settings,
+    sessions: Vec<Session>,
+}
+
+impl StorageInterfaceTrait for InMemoryStorage {
+    fn load_profile(&mut self) -> Result<Profile> {
+        Ok(self.profile.clone())
+    }
+
+    fn save_profile(&mut self, profile: &Profile) -> Result<()> {
+        self.profile = profile.clone();
+        Ok(())
+    }
+
+    fn load_settings(&mut self) -> Result<Settings> {
+        Ok(self.settings.clone())
+    }
+
+    fn save_settings(&mut self, settings: &Settings) -> Result<()> {
+        self.settings = settings.clone();
+        Ok(())
+    }
+
+    fn save_session(&mut self, session: &Session) -> Result<()> {
+        self.sessions.push(session.clone());
+        Ok(())
+    }
+
+    fn sessions(&mut self) -> Result<Vec<Session>> {
+        Ok(self.sessions.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        domain::random::{Accidentals, NotePool},
+        interfaces::ui::GameMode,
+    };
+
+    use super::*;
+
+    #[test]
+    fn in_memory_storage_keeps_what_was_saved() {
+        let mut storage = InMemoryStorage::default();
+        assert_eq!(storage.load_settings().unwrap(), Settings::default());
+        assert_eq!(storage.load_profile().unwrap(), Profile::default());
+        assert!(storage.sessions().unwrap().is_empty());
+
+        let settings = Settings {
+            pool: NotePool::Accidentals(Accidentals::Flats),
+            seed: Some(3),
+            ..Settings::default()
+        };
+        storage.save_settings(&settings).unwrap();
+        assert_eq!(storage.load_settings().unwrap(), settings);
+
+        let mut profile = Profile::default();
+        profile.new_cards.count = 4;
+        storage.save_profile(&profile).unwrap();
+        assert_eq!(storage.load_profile().unwrap(), profile);
+
+        let first = Session::new(GameMode::Interval, 1, None);
+        let second = Session::new(GameMode::Chord, 2, None);
+        storage.save_session(&first).unwrap();
+        storage.save_session(&second).unwrap();
+        assert_eq!(storage.sessions().unwrap(), [first, second]);
+    }
+}
@@ -1,8 +1,21 @@
+use std::time::Duration;
+
 use async_trait::async_trait;
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
 use tracing::error;
 
-use crate::domain::{notes::Note, random::NoteTuple};
+use crate::domain::{
+    chord::{Chord, ChordQuality},
+    dictation::{Alignment, AnswerNote, Melody},
+    exercise::Exercise,
+    interval::{Interval, IntervalPlayback},
+    notes::{KeySignature, Note},
+    profile::Settings,
+    random::NoteTuple,
+    scale::{ScaleKind, ScaleRun},
+    statistics::StatisticsReport,
+};
+use crate::interfaces::audio::AudioFallback;
 
 #[async_trait]
 pub trait UserInterfaceTrait: std::fmt::Debug + Send {
@@ -50,30 +63,155 @@ impl UserInterfaceTrait for UserInterface {
 pub enum UserInterfaceMessage {
     MainMenuMessage(UIMainMenuMessage),
     GameMessage(UIGameMessage),
+    StatisticsMessage(UIStatisticsMessage),
+    ConfigMessage(UIConfigMessage),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum UIMainMenuMessage {
-    Start,
+    Start(GameMode),
     Quit,
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GameMode {
+    #[default]
+    Random,
+    Interval,
+    Chord,
+    Scale,
+    Dictation,
+    Review,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 6] = [
+        GameMode::Random,
+        GameMode::Interval,
+        GameMode::Chord,
+        GameMode::Scale,
+        GameMode::Dictation,
+        GameMode::Review,
+    ];
+
+    // how the mode is written in the settings
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Random => "random",
+            GameMode::Interval => "interval",
+            GameMode::Chord => "chord",
+            GameMode::Scale => "scale",
+            GameMode::Dictation => "dictation",
+            GameMode::Review => "review",
+        }
+    }
+}
+
+impl std::str::FromStr for GameMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        GameMode::ALL
+            .into_iter()
+            .find(|mode| mode.name() == s.to_lowercase())
+            .ok_or_else(|| {
+                let names: Vec<&str> = GameMode::ALL.iter().map(GameMode::name).collect();
+                anyhow::anyhow!("There is no game mode '{s}', try {}", names.join(", "))
+            })
+    }
+}
+
+impl std::fmt::Display for GameMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            GameMode::Random => "Random notes",
+            GameMode::Interval => "Intervals",
+            GameMode::Chord => "Chords",
+            GameMode::Scale => "Scales",
+            GameMode::Dictation => "Dictation",
+            GameMode::Review => "Review",
+        };
+        write!(f, "{name}")
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum UIGameMessage {
     NoteRequest,
     StopRequest,
+    IntervalGuess(Interval),
+    ChordGuess(ChordQuality),
+    ScaleGuess(ScaleKind),
+    DictationAnswer(Vec<AnswerNote>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UIStatisticsMessage {
+    ReportRequest,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UIConfigMessage {
+    SettingsRequest,
+    SaveRequest(Settings),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CoreMessage {
     MainMenuMessage(CoreMainMenuMessage),
     GameMessage(CoreGameMessage),
+    StatisticsMessage(CoreStatisticsMessage),
+    ConfigMessage(CoreConfigMessage),
+    AudioMessage(CoreAudioMessage),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoreAudioMessage {
+    // the output device, by name if it was not the default, went away. the
+    // default device plays from now on unless it couldn't be opened either
+    OutputDeviceLost {
+        device: Option<String>,
+        recovered: bool,
+    },
+    // the same for the input device
+    InputDeviceLost {
+        device: Option<String>,
+        recovered: bool,
+    },
+    // the audio settings could not be applied as they are, playback goes on
+    // with the fallback or, if there is none, as it did before
+    AudioFallback(Option<AudioFallback>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoreStatisticsMessage {
+    ReportResponse(StatisticsReport),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoreConfigMessage {
+    SettingsResponse(Settings),
+    // the settings that are in place now
+    SaveResponse(Settings),
+    // why the settings could not be applied or saved
+    SaveRejected(String),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CoreGameMessage {
     NoteResponse(NextNoteTuple),
     GuessResponse(NoteGuess),
+    IntervalResponse(NextInterval),
+    IntervalGuessResponse(IntervalGuess),
+    ChordResponse(NextChord),
+    ChordGuessResponse(ChordGuess),
+    ScaleResponse(NextScale),
+    ScaleGuessResponse(ScaleGuess),
+    DictationResponse(NextMelody),
+    DictationNoteHeard(Note),
+    DictationGuessResponse(DictationGuess),
+    // the exercise the random mode plays, none if it adapts to the player
+    ExerciseResponse(Option<Exercise>),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -87,22 +225,150 @@ impl NextNoteTuple {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NextInterval {
+    pub choices: Vec<Interval>,
+    pub playback: IntervalPlayback,
+}
+
+impl NextInterval {
+    pub fn new(choices: Vec<Interval>, playback: IntervalPlayback) -> Self {
+        Self { choices, playback }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NextChord {
+    pub choices: Vec<ChordQuality>,
+}
+
+impl NextChord {
+    pub fn new(choices: Vec<ChordQuality>) -> Self {
+        Self { choices }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NextScale {
+    pub choices: Vec<ScaleKind>,
+}
+
+impl NextScale {
+    pub fn new(choices: Vec<ScaleKind>) -> Self {
+        Self { choices }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NextMelody {
+    pub key: KeySignature,
+    pub length: usize,
+}
+
+impl NextMelody {
+    pub fn new(key: KeySignature, length: usize) -> Self {
+        Self { key, length }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct NoteGuess {
     pub true_note_tuple: NoteTuple,
     pub note_played: Note,
     pub correct: bool,
     pub score: u8,
+    // since the tuple was asked
+    pub response_time: Duration,
 }
 
 impl NoteGuess {
-    pub fn new(note_tuple: NoteTuple, note: Note) -> Self {
-        let correct = note_tuple.reference().eq(&note);
+    pub fn new(note_tuple: NoteTuple, note: Note, response_time: Duration) -> Self {
+        let correct = note_tuple.reference().is_enharmonic(&note);
         Self {
             true_note_tuple: note_tuple.clone(),
             note_played: note,
             correct,
             score: note_tuple.divergence().distance(&note),
+            response_time,
+        }
+    }
+}
+
+// enharmonic intervals like the augmented fourth and diminished fifth sound the
+// same, so a guess only has to match the size
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalGuess {
+    pub note_tuple: NoteTuple,
+    pub interval: Interval,
+    pub guessed: Interval,
+    pub correct: bool,
+}
+
+impl IntervalGuess {
+    pub fn new(note_tuple: NoteTuple, guessed: Interval) -> Self {
+        let interval = note_tuple.interval();
+        Self {
+            note_tuple,
+            interval,
+            guessed,
+            correct: interval.semitones() == guessed.semitones(),
+        }
+    }
+}
+
+// a guess counts if the chord sounds like the guessed quality, so a sus2 chord
+// is also accepted as the sus4 chord on its fifth
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChordGuess {
+    pub chord: Chord,
+    pub guessed: ChordQuality,
+    pub correct: bool,
+}
+
+impl ChordGuess {
+    pub fn new(chord: Chord, guessed: ChordQuality) -> Self {
+        Self {
+            chord,
+            guessed,
+            correct: chord.sounds_like(guessed),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScaleGuess {
+    pub run: ScaleRun,
+    pub guessed: ScaleKind,
+    pub correct: bool,
+}
+
+impl ScaleGuess {
+    pub fn new(run: ScaleRun, guessed: ScaleKind) -> Self {
+        Self {
+            run,
+            guessed,
+            correct: run.scale().kind() == guessed,
+        }
+    }
+}
+
+// the answer aligned to the melody, with a verdict for every note
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DictationGuess {
+    pub melody: Melody,
+    pub alignment: Alignment,
+    pub correct: bool,
+}
+
+impl DictationGuess {
+    pub fn new(melody: Melody, answer: Vec<AnswerNote>, compare_rhythm: bool) -> Self {
+        let alignment = Alignment::new(melody.notes(), &answer, compare_rhythm);
+        let correct =
+            alignment.correct_count() == melody.len() && alignment.notes().len() == melody.len();
+        Self {
+            melody,
+            alignment,
+            correct,
         }
     }
 }
@@ -1,6 +1,16 @@
-use domain::state::State;
-use interfaces::{audio::AudioInterfaceTrait, ui::UserInterfaceTrait};
-use tracing::debug;
+use anyhow::Result;
+use domain::{
+    exercise::Exercise,
+    profile::{Profile, Session, Settings},
+    state::State,
+};
+use interfaces::{
+    audio::{AudioInputInterfaceTrait, AudioInterfaceTrait, receive_output},
+    storage::StorageInterfaceTrait,
+    ui::UserInterfaceTrait,
+};
+use tracing::{debug, error};
+use use_cases::config::apply_settings;
 
 pub mod domain;
 pub mod interfaces;
@@ -11,10 +21,29 @@ pub trait UIMessageHandler {
     fn handle(self, model: &mut CoreModel);
 }
 
+// handles messages from the audio input
+pub trait AudioInputMessageHandler {
+    fn handle(self, model: &mut CoreModel);
+}
+
+// handles messages from the audio output
+pub trait AudioOutputMessageHandler {
+    fn handle(self, model: &mut CoreModel);
+}
+
 pub struct CoreModel {
     audio: Box<dyn AudioInterfaceTrait>,
+    input: Box<dyn AudioInputInterfaceTrait>,
     ui: Box<dyn UserInterfaceTrait>,
+    storage: Box<dyn StorageInterfaceTrait>,
     state: State,
+    profile: Profile,
+    settings: Settings,
+    // replaces the adaptive random mode until the program ends
+    exercise: Option<Exercise>,
+    session: Option<Session>,
+    // the microphone would pick up the phrase that is playing
+    playing: bool,
     running: bool,
 }
 
@@ -23,19 +52,63 @@ pub struct Core {
 }
 
 impl Core {
-    pub fn new(audio: Box<dyn AudioInterfaceTrait>, ui: Box<dyn UserInterfaceTrait>) -> Self {
-        Self {
-            model: CoreModel {
-                audio,
-                ui,
-                state: State::default(),
-                running: true,
-            },
-        }
+    pub fn new(
+        audio: Box<dyn AudioInterfaceTrait>,
+        input: Box<dyn AudioInputInterfaceTrait>,
+        ui: Box<dyn UserInterfaceTrait>,
+        mut storage: Box<dyn StorageInterfaceTrait>,
+    ) -> Self {
+        let profile = storage.load_profile().unwrap_or_else(|err| {
+            error!(?err, "Failed to load profile, starting a new one");
+            Profile::default()
+        });
+        let settings = storage
+            .load_settings()
+            .and_then(|settings| settings.validate().map(|_| settings))
+            .unwrap_or_else(|err| {
+                error!(?err, "Failed to load settings, using the defaults");
+                Settings::default()
+            });
+
+        let mut model = CoreModel {
+            audio,
+            input,
+            ui,
+            storage,
+            state: State::default(),
+            profile,
+            settings,
+            exercise: None,
+            session: None,
+            playing: false,
+            running: true,
+        };
+        apply_settings(&mut model);
+
+        Self { model }
     }
 }
 
 impl Core {
+    // every random mode session plays this exercise
+    pub fn with_exercise(mut self, exercise: Exercise) -> Self {
+        self.model.exercise = Some(exercise);
+        self
+    }
+
+    // replaces the stored settings for this run, they are only saved if they
+    // are saved from the config page
+    pub fn with_settings(mut self, settings: Settings) -> Result<Self> {
+        settings.validate()?;
+        self.model.settings = settings;
+        apply_settings(&mut self.model);
+        Ok(self)
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.model.settings
+    }
+
     pub async fn run(&mut self) {
         while self.model.running {
             tokio::select! {
@@ -44,8 +117,18 @@ impl Core {
                     match ui_event {
                         interfaces::ui::UserInterfaceMessage::MainMenuMessage(msg) => msg.handle(&mut self.model),
                         interfaces::ui::UserInterfaceMessage::GameMessage(msg) => msg.handle(&mut self.model),
+                        interfaces::ui::UserInterfaceMessage::StatisticsMessage(msg) => msg.handle(&mut self.model),
+                        interfaces::ui::UserInterfaceMessage::ConfigMessage(msg) => msg.handle(&mut self.model),
                     }
                 }
+                input_event = self.model.input.receive() => {
+                    debug!(?input_event, "Received audio input event");
+                    input_event.handle(&mut self.model);
+                }
+                output_event = receive_output(self.model.audio.as_mut()) => {
+                    debug!(?output_event, "Received audio output event");
+                    output_event.handle(&mut self.model);
+                }
             }
         }
     }
@@ -0,0 +1,213 @@
+use anyhow::{Context, Result};
+use tracing::{error, info};
+
+use crate::CoreModel;
+use crate::UIMessageHandler;
+use crate::domain::profile::Settings;
+use crate::domain::state::GameModeState;
+use crate::domain::state::State;
+use crate::interfaces::audio::AudioFallback;
+use crate::interfaces::audio::AudioInputCommand;
+use crate::interfaces::audio::AudioInterfaceTrait;
+use crate::interfaces::audio::AudioSettings;
+use crate::interfaces::ui::CoreAudioMessage;
+use crate::interfaces::ui::CoreConfigMessage;
+use crate::interfaces::ui::CoreMessage;
+use crate::interfaces::ui::GameMode;
+use crate::interfaces::ui::UIConfigMessage;
+
+impl UIMessageHandler for UIConfigMessage {
+    fn handle(self, model: &mut CoreModel) {
+        match self {
+            UIConfigMessage::SettingsRequest => {
+                model.ui.send(CoreMessage::ConfigMessage(
+                    CoreConfigMessage::SettingsResponse(model.settings.clone()),
+                ));
+            }
+            UIConfigMessage::SaveRequest(settings) => {
+                let response = match save_settings(model, settings) {
+                    Ok(()) => CoreConfigMessage::SaveResponse(model.settings.clone()),
+                    Err(err) => {
+                        error!(?err, "Failed to save settings");
+                        CoreConfigMessage::SaveRejected(format!("{err:#}"))
+                    }
+                };
+                model.ui.send(CoreMessage::ConfigMessage(response));
+            }
+        }
+    }
+}
+
+// invalid settings are rejected before anything changes
+fn save_settings(model: &mut CoreModel, settings: Settings) -> Result<()> {
+    settings.validate()?;
+    let mode = model.session.as_ref().map(|session| session.mode);
+    model.audio.configure(&settings.audio(mode))?;
+    model
+        .input
+        .send(AudioInputCommand::Configure(settings.input()));
+    if let State::GameModeState(GameModeState::RandomMode(rm)) = &mut model.state {
+        rm.configure(settings.note_sequence());
+    }
+
+    model.settings = settings;
+    info!(settings = ?model.settings, "Applied settings");
+    model
+        .storage
+        .save_settings(&model.settings)
+        .context("The settings are in use until the next start, but could not be saved")
+}
+
+// brings the devices in line with the settings loaded at startup
+pub(crate) fn apply_settings(model: &mut CoreModel) {
+    let mode = model.session.as_ref().map(|session| session.mode);
+    apply_audio_settings(model, mode);
+    model
+        .input
+        .send(AudioInputCommand::Configure(model.settings.input()));
+}
+
+// switches to the instrument of the game mode. if that fails the player is
+// told what playback fell back to, the settings stay as they are
+pub(crate) fn apply_audio_settings(model: &mut CoreModel, mode: Option<GameMode>) {
+    let settings = model.settings.audio(mode);
+    let Err(err) = model.audio.configure(&settings) else {
+        return;
+    };
+    error!(?err, "Failed to apply audio settings");
+
+    let fallback = fall_back(model.audio.as_mut(), &settings);
+    model
+        .ui
+        .send(CoreMessage::AudioMessage(CoreAudioMessage::AudioFallback(
+            fallback,
+        )));
+}
+
+// the first fallback the output accepts. the default device replaces one
+// that is gone, the built-in soundfont one that can't be used, then both
+// together, and last a sine wave on the default device
+pub fn fall_back(
+    audio: &mut dyn AudioInterfaceTrait,
+    settings: &AudioSettings,
+) -> Option<AudioFallback> {
+    let mut tried = vec![settings.clone()];
+    for fallback in AudioFallback::ALL {
+        let fallback_settings = fallback.apply(settings);
+        if tried.contains(&fallback_settings) {
+            continue;
+        }
+        match audio.configure(&fallback_settings) {
+            Ok(()) => {
+                info!(%fallback, "Fell back");
+                return Some(fallback);
+            }
+            Err(err) => error!(?err, %fallback, "Failed to fall back"),
+        }
+        tried.push(fallback_settings);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::bail;
+
+    use super::*;
+    use crate::domain::instrument::{Instrument, Sound};
+    use crate::interfaces::audio::NoteEvent;
+
+    // accepts only the settings the check lets through
+    #[derive(Debug)]
+    struct Output {
+        accepts: fn(&AudioSettings) -> bool,
+        tried: Vec<AudioSettings>,
+    }
+
+    impl AudioInterfaceTrait for Output {
+        fn configure(&mut self, settings: &AudioSettings) -> Result<()> {
+            self.tried.push(settings.clone());
+            if !(self.accepts)(settings) {
+                bail!("The output can't play with these settings");
+            }
+            Ok(())
+        }
+
+        fn play_phrase(&mut self, _phrase: &[NoteEvent]) {}
+    }
+
+    fn settings() -> AudioSettings {
+        AudioSettings {
+            output_device: Some("usb".to_string()),
+            soundfont: Some("broken.sf2".into()),
+            instrument: Instrument::new(8, 1).unwrap(),
+            ..Settings::default().audio(None)
+        }
+    }
+
+    fn fall_back_with(
+        settings: &AudioSettings,
+        accepts: fn(&AudioSettings) -> bool,
+    ) -> (Option<AudioFallback>, Vec<AudioSettings>) {
+        let mut output = Output {
+            accepts,
+            tried: Vec::new(),
+        };
+        let fallback = fall_back(&mut output, settings);
+        (fallback, output.tried)
+    }
+
+    #[test]
+    fn falls_back_as_little_as_possible() {
+        let settings = settings();
+        let (fallback, _) = fall_back_with(&settings, |settings| settings.output_device.is_none());
+        assert_eq!(fallback, Some(AudioFallback::ALL[0]));
+
+        let (fallback, tried) = fall_back_with(&settings, |settings| settings.soundfont.is_none());
+        assert_eq!(fallback, Some(AudioFallback::ALL[1]));
+        assert_eq!(tried.len(), 2);
+        assert_eq!(tried[1].output_device, settings.output_device);
+        assert_eq!(tried[1].instrument, Instrument::default());
+    }
+
+    #[test]
+    fn fallbacks_compose() {
+        let settings = settings();
+        let (fallback, tried) = fall_back_with(&settings, |settings| {
+            settings.output_device.is_none() && settings.soundfont.is_none()
+        });
+        assert_eq!(fallback, Some(AudioFallback::ALL[2]));
+        assert_eq!(tried.len(), 3);
+
+        let (fallback, tried) = fall_back_with(&settings, |settings| {
+            settings.output_device.is_none() && settings.sound == Sound::Sine
+        });
+        assert_eq!(fallback, Some(AudioFallback::ALL[3]));
+        assert_eq!(
+            tried.last(),
+            Some(&AudioSettings {
+                output_device: None,
+                sound: Sound::Sine,
+                ..settings.clone()
+            })
+        );
+        assert_eq!(
+            fallback.unwrap().to_string(),
+            "the default device and a sine wave"
+        );
+    }
+
+    #[test]
+    fn reports_when_nothing_works() {
+        let (fallback, tried) = fall_back_with(&settings(), |_| false);
+        assert_eq!(fallback, None);
+        assert_eq!(tried.len(), AudioFallback::ALL.len());
+
+        // fallbacks that change nothing are not tried
+        let settings = Settings::default().audio(None);
+        let (fallback, tried) = fall_back_with(&settings, |_| false);
+        assert_eq!(fallback, None);
+        assert_eq!(tried.len(), 1);
+        assert_eq!(tried[0].sound, Sound::Sine);
+    }
+}
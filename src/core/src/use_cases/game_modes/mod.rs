@@ -1,38 +1,182 @@
 use std::time::Duration;
 
+use tracing::debug;
+
 use crate::CoreModel;
 use crate::UIMessageHandler;
+use crate::domain::interval::IntervalPlayback;
 use crate::domain::state::GameModeState;
 use crate::domain::state::MainMenuState;
 use crate::domain::state::State;
+use crate::interfaces::audio::AudioInputCommand;
+use crate::interfaces::audio::NoteEvent;
 use crate::interfaces::ui::CoreGameMessage;
 use crate::interfaces::ui::CoreMessage;
+use crate::interfaces::ui::NextChord;
+use crate::interfaces::ui::NextInterval;
+use crate::interfaces::ui::NextMelody;
 use crate::interfaces::ui::NextNoteTuple;
+use crate::interfaces::ui::NextScale;
 use crate::interfaces::ui::UIGameMessage;
+use crate::use_cases::playback::play_phrase;
+use crate::use_cases::session::end_session;
+
+const CHORD_DURATION: Duration = Duration::from_secs(2);
+const SCALE_NOTE_DURATION: Duration = Duration::from_millis(500);
+const BEAT_DURATION: Duration = Duration::from_millis(500);
 
 impl UIMessageHandler for UIGameMessage {
     fn handle(self, model: &mut CoreModel) {
         match self {
-            UIGameMessage::NoteRequest => {
-                if let State::GameModeState(GameModeState::RandomMode(rm)) = &mut model.state {
+            UIGameMessage::NoteRequest => match &mut model.state {
+                State::GameModeState(GameModeState::RandomMode(rm)) => {
+                    let note_tuple = rm.next_note();
+                    model
+                        .ui
+                        .send(CoreMessage::GameMessage(CoreGameMessage::NoteResponse(
+                            NextNoteTuple::new(note_tuple.clone()),
+                        )));
+
+                    play_phrase(
+                        model,
+                        &[
+                            NoteEvent::note(note_tuple.reference(), model.settings.tone_duration),
+                            NoteEvent::note(note_tuple.divergence(), model.settings.tone_duration),
+                        ],
+                    );
+                }
+                State::GameModeState(GameModeState::ReviewMode(rm)) => {
                     let note_tuple = rm.next_note();
+                    debug!(due = rm.due_today(), "Reviewing note tuple");
                     model
                         .ui
                         .send(CoreMessage::GameMessage(CoreGameMessage::NoteResponse(
                             NextNoteTuple::new(note_tuple.clone()),
                         )));
 
-                    //TODO: move requests to another thread with some limits
+                    play_phrase(
+                        model,
+                        &[
+                            NoteEvent::note(note_tuple.reference(), model.settings.tone_duration),
+                            NoteEvent::note(note_tuple.divergence(), model.settings.tone_duration),
+                        ],
+                    );
+                }
+                State::GameModeState(GameModeState::IntervalMode(im)) => {
+                    let (note_tuple, playback) = im.next_interval();
+                    model
+                        .ui
+                        .send(CoreMessage::GameMessage(CoreGameMessage::IntervalResponse(
+                            NextInterval::new(im.choices(), playback),
+                        )));
+
+                    let (lower, upper) = if note_tuple.is_ascending() {
+                        (note_tuple.reference(), note_tuple.divergence())
+                    } else {
+                        (note_tuple.divergence(), note_tuple.reference())
+                    };
+                    let phrase = match playback {
+                        IntervalPlayback::Ascending => vec![
+                            NoteEvent::note(lower, model.settings.tone_duration),
+                            NoteEvent::note(upper, model.settings.tone_duration),
+                        ],
+                        IntervalPlayback::Descending => vec![
+                            NoteEvent::note(upper, model.settings.tone_duration),
+                            NoteEvent::note(lower, model.settings.tone_duration),
+                        ],
+                        IntervalPlayback::Harmonic => {
+                            vec![NoteEvent::new(vec![lower, upper], CHORD_DURATION)]
+                        }
+                    };
+                    play_phrase(model, &phrase);
+                }
+                State::GameModeState(GameModeState::ChordMode(cm)) => {
+                    let chord = cm.next_chord();
                     model
-                        .audio
-                        .play_note(note_tuple.reference(), Duration::from_secs(1));
+                        .ui
+                        .send(CoreMessage::GameMessage(CoreGameMessage::ChordResponse(
+                            NextChord::new(cm.choices()),
+                        )));
+
+                    play_phrase(model, &[NoteEvent::new(chord.notes(), CHORD_DURATION)]);
+                }
+                State::GameModeState(GameModeState::ScaleMode(sm)) => {
+                    let run = sm.next_run();
                     model
-                        .audio
-                        .play_note(note_tuple.divergence(), Duration::from_secs(1));
+                        .ui
+                        .send(CoreMessage::GameMessage(CoreGameMessage::ScaleResponse(
+                            NextScale::new(sm.choices()),
+                        )));
+
+                    let phrase: Vec<NoteEvent> = run
+                        .notes()
+                        .into_iter()
+                        .map(|note| NoteEvent::note(note, SCALE_NOTE_DURATION))
+                        .collect();
+                    play_phrase(model, &phrase);
+                }
+                State::GameModeState(GameModeState::DictationMode(dm)) => {
+                    let melody = dm.next_melody();
+                    model.ui.send(CoreMessage::GameMessage(
+                        CoreGameMessage::DictationResponse(NextMelody::new(
+                            melody.key(),
+                            melody.len(),
+                        )),
+                    ));
+
+                    let phrase: Vec<NoteEvent> = melody
+                        .notes()
+                        .iter()
+                        .map(|note| NoteEvent::note(note.note, BEAT_DURATION * note.beats as u32))
+                        .collect();
+                    play_phrase(model, &phrase);
                 }
                 //TODO: handle unexpected case
+                State::MainMenuState(_) => {}
+            },
+            UIGameMessage::IntervalGuess(interval) => {
+                if let State::GameModeState(GameModeState::IntervalMode(im)) = &mut model.state {
+                    match im.interval_guess(interval) {
+                        Ok(interval_guess) => model.ui.send(CoreMessage::GameMessage(
+                            CoreGameMessage::IntervalGuessResponse(interval_guess),
+                        )),
+                        Err(err) => debug!(?err, %interval, "Ignoring interval guess"),
+                    }
+                }
+            }
+            UIGameMessage::ChordGuess(quality) => {
+                if let State::GameModeState(GameModeState::ChordMode(cm)) = &mut model.state {
+                    match cm.chord_guess(quality) {
+                        Ok(chord_guess) => model.ui.send(CoreMessage::GameMessage(
+                            CoreGameMessage::ChordGuessResponse(chord_guess),
+                        )),
+                        Err(err) => debug!(?err, %quality, "Ignoring chord guess"),
+                    }
+                }
+            }
+            UIGameMessage::ScaleGuess(kind) => {
+                if let State::GameModeState(GameModeState::ScaleMode(sm)) = &mut model.state {
+                    match sm.scale_guess(kind) {
+                        Ok(scale_guess) => model.ui.send(CoreMessage::GameMessage(
+                            CoreGameMessage::ScaleGuessResponse(scale_guess),
+                        )),
+                        Err(err) => debug!(?err, %kind, "Ignoring scale guess"),
+                    }
+                }
+            }
+            UIGameMessage::DictationAnswer(answer) => {
+                if let State::GameModeState(GameModeState::DictationMode(dm)) = &mut model.state {
+                    match dm.dictation_guess(answer) {
+                        Ok(dictation_guess) => model.ui.send(CoreMessage::GameMessage(
+                            CoreGameMessage::DictationGuessResponse(dictation_guess),
+                        )),
+                        Err(err) => debug!(?err, "Ignoring dictation answer"),
+                    }
+                }
             }
             UIGameMessage::StopRequest => {
+                end_session(model);
+                model.input.send(AudioInputCommand::Stop);
                 model.state = State::MainMenuState(MainMenuState::default())
             }
         }
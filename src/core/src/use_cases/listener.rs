@@ -0,0 +1,69 @@
+use tracing::debug;
+
+use crate::AudioInputMessageHandler;
+use crate::CoreModel;
+use crate::domain::state::GameModeState;
+use crate::domain::state::State;
+use crate::interfaces::audio::AudioInputMessage;
+use crate::interfaces::ui::CoreAudioMessage;
+use crate::interfaces::ui::CoreGameMessage;
+use crate::interfaces::ui::CoreMessage;
+use crate::use_cases::session::record_guess;
+
+impl AudioInputMessageHandler for AudioInputMessage {
+    fn handle(self, model: &mut CoreModel) {
+        match self {
+            AudioInputMessage::NoteDetected(note) if model.playing => {
+                debug!(%note, "Ignoring detected note while a phrase plays")
+            }
+            AudioInputMessage::NoteDetected(note) => match &mut model.state {
+                State::GameModeState(GameModeState::RandomMode(rm)) => match rm.note_guess(note) {
+                    Ok(note_guess) => {
+                        record_guess(model, &note_guess);
+                        model
+                            .ui
+                            .send(CoreMessage::GameMessage(CoreGameMessage::GuessResponse(
+                                note_guess,
+                            )))
+                    }
+                    Err(err) => debug!(?err, %note, "Ignoring detected note"),
+                },
+                State::GameModeState(GameModeState::ReviewMode(rm)) => match rm.note_guess(note) {
+                    Ok(note_guess) => {
+                        record_guess(model, &note_guess);
+                        model
+                            .ui
+                            .send(CoreMessage::GameMessage(CoreGameMessage::GuessResponse(
+                                note_guess,
+                            )))
+                    }
+                    Err(err) => debug!(?err, %note, "Ignoring detected note"),
+                },
+                State::GameModeState(GameModeState::DictationMode(dm)) => {
+                    match dm.note_heard(note) {
+                        Ok(dictation_guess) => {
+                            model.ui.send(CoreMessage::GameMessage(
+                                CoreGameMessage::DictationNoteHeard(note),
+                            ));
+                            if let Some(dictation_guess) = dictation_guess {
+                                model.ui.send(CoreMessage::GameMessage(
+                                    CoreGameMessage::DictationGuessResponse(dictation_guess),
+                                ));
+                            }
+                        }
+                        Err(err) => debug!(?err, %note, "Ignoring detected note"),
+                    }
+                }
+                _ => {}
+            },
+            // the settings keep the lost device so it is tried again whenever
+            // they are applied
+            AudioInputMessage::DeviceLost { recovered } => model.ui.send(
+                CoreMessage::AudioMessage(CoreAudioMessage::InputDeviceLost {
+                    device: model.settings.input_device.clone(),
+                    recovered,
+                }),
+            ),
+        }
+    }
+}
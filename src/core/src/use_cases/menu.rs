@@ -1,17 +1,22 @@
 use crate::CoreModel;
 use crate::UIMessageHandler;
-use crate::domain::state::GameModeState;
-use crate::domain::state::State;
+use crate::interfaces::audio::AudioInputCommand;
 use crate::interfaces::ui::UIMainMenuMessage;
+use crate::use_cases::session::end_session;
+use crate::use_cases::session::start_session;
 
 impl UIMessageHandler for UIMainMenuMessage {
     fn handle(self, model: &mut CoreModel) {
         match self {
-            UIMainMenuMessage::Start => {
-                model.state = State::GameModeState(GameModeState::default());
-                //TODO: start listener
+            UIMainMenuMessage::Start(mode) => {
+                start_session(model, mode);
+                model.input.send(AudioInputCommand::Start);
+            }
+            UIMainMenuMessage::Quit => {
+                end_session(model);
+                model.input.send(AudioInputCommand::Stop);
+                model.running = false
             }
-            UIMainMenuMessage::Quit => model.running = false,
         }
     }
 }
@@ -1,2 +1,7 @@
+pub mod config;
 pub mod game_modes;
+pub mod listener;
 pub mod menu;
+pub mod playback;
+pub mod session;
+pub mod statistics;
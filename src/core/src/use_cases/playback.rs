@@ -0,0 +1,58 @@
+use tracing::{error, info};
+
+use crate::AudioOutputMessageHandler;
+use crate::CoreModel;
+use crate::interfaces::audio::AudioOutputMessage;
+use crate::interfaces::audio::AudioSettings;
+use crate::interfaces::audio::NoteEvent;
+use crate::interfaces::ui::CoreAudioMessage;
+use crate::interfaces::ui::CoreMessage;
+
+impl AudioOutputMessageHandler for AudioOutputMessage {
+    fn handle(self, model: &mut CoreModel) {
+        match self {
+            AudioOutputMessage::PhraseStarted => model.playing = true,
+            AudioOutputMessage::PhraseFinished => model.playing = false,
+            AudioOutputMessage::DeviceLost => {
+                model.playing = false;
+                recover_output(model);
+            }
+        }
+    }
+}
+
+// the microphone keeps listening, notes it hears are ignored until the output
+// reports the end of the phrase. the flag is set right away because the start
+// is only reported once the stream picks the phrase up
+pub(crate) fn play_phrase(model: &mut CoreModel, phrase: &[NoteEvent]) {
+    if model.audio.events().is_some() {
+        model.playing = true;
+    }
+    model.audio.play_phrase(phrase);
+}
+
+// plays on the default device from now on, the settings keep the lost one so
+// it is tried again whenever they are applied
+fn recover_output(model: &mut CoreModel) {
+    let mode = model.session.as_ref().map(|session| session.mode);
+    let settings = AudioSettings {
+        output_device: None,
+        ..model.settings.audio(mode)
+    };
+    let recovered = match model.audio.configure(&settings) {
+        Ok(()) => {
+            info!("Switched to the default output device");
+            true
+        }
+        Err(err) => {
+            error!(?err, "Failed to open the default output device");
+            false
+        }
+    };
+    model.ui.send(CoreMessage::AudioMessage(
+        CoreAudioMessage::OutputDeviceLost {
+            device: model.settings.output_device.clone(),
+            recovered,
+        },
+    ));
+}
@@ -0,0 +1,84 @@
+use tracing::{error, info};
+
+use crate::CoreModel;
+use crate::domain::exercise::Exercise;
+use crate::domain::profile::Session;
+use crate::domain::state::GameModeState;
+use crate::domain::state::RandomMode;
+use crate::domain::state::State;
+use crate::interfaces::ui::CoreGameMessage;
+use crate::interfaces::ui::CoreMessage;
+use crate::interfaces::ui::GameMode;
+use crate::interfaces::ui::NoteGuess;
+use crate::use_cases::config::apply_audio_settings;
+
+pub(crate) fn start_session(model: &mut CoreModel, mode: GameMode) {
+    end_session(model);
+
+    let exercise = match mode {
+        GameMode::Random => current_exercise(model),
+        _ => None,
+    };
+    let seed = exercise
+        .as_ref()
+        .map(Exercise::seed)
+        .or(model.settings.seed)
+        .unwrap_or_else(rand::random);
+    let state = match &exercise {
+        Some(exercise) => GameModeState::RandomMode(Box::new(RandomMode::exercise(
+            exercise,
+            model.profile.statistics.clone(),
+        ))),
+        None => GameModeState::restore(mode, &model.profile, &model.settings, seed),
+    };
+    info!(?mode, seed, exercise = ?exercise.as_ref().map(Exercise::share_code), "Starting session");
+
+    model.state = State::GameModeState(state);
+    apply_audio_settings(model, Some(mode));
+    model
+        .ui
+        .send(CoreMessage::GameMessage(CoreGameMessage::ExerciseResponse(
+            exercise.clone(),
+        )));
+    model.session = Some(Session::new(mode, seed, exercise));
+}
+
+// the exercise that was handed in, or the one the configured seed makes
+fn current_exercise(model: &CoreModel) -> Option<Exercise> {
+    model.exercise.clone().or_else(|| {
+        model
+            .settings
+            .seed
+            .map(|seed| Exercise::new(seed, model.settings.note_sequence()))
+    })
+}
+
+// keeps the progress of the current game mode and stores the session
+pub(crate) fn end_session(model: &mut CoreModel) {
+    match &model.state {
+        State::GameModeState(GameModeState::RandomMode(rm)) => {
+            model.profile.statistics = rm.statistics().clone();
+        }
+        State::GameModeState(GameModeState::ReviewMode(rm)) => {
+            model.profile.deck = rm.scheduler().cards().clone();
+            model.profile.new_cards = rm.introduced();
+        }
+        _ => {}
+    }
+
+    if let Some(mut session) = model.session.take() {
+        session.end();
+        if let Err(err) = model.storage.save_session(&session) {
+            error!(?err, "Failed to save session");
+        }
+        if let Err(err) = model.storage.save_profile(&model.profile) {
+            error!(?err, "Failed to save profile");
+        }
+    }
+}
+
+pub(crate) fn record_guess(model: &mut CoreModel, note_guess: &NoteGuess) {
+    if let Some(session) = &mut model.session {
+        session.record(note_guess);
+    }
+}
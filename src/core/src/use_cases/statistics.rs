@@ -0,0 +1,36 @@
+use tracing::error;
+
+use crate::CoreModel;
+use crate::UIMessageHandler;
+use crate::domain::srs::Clock;
+use crate::domain::srs::SystemClock;
+use crate::domain::state::GameModeState;
+use crate::domain::state::State;
+use crate::domain::statistics::StatisticsReport;
+use crate::interfaces::ui::CoreMessage;
+use crate::interfaces::ui::CoreStatisticsMessage;
+use crate::interfaces::ui::UIStatisticsMessage;
+
+impl UIMessageHandler for UIStatisticsMessage {
+    fn handle(self, model: &mut CoreModel) {
+        match self {
+            UIStatisticsMessage::ReportRequest => {
+                let mut sessions = model.storage.sessions().unwrap_or_else(|err| {
+                    error!(?err, "Failed to load the session history");
+                    Vec::new()
+                });
+                sessions.extend(model.session.clone());
+
+                // the running random mode is ahead of the saved profile
+                let statistics = match &model.state {
+                    State::GameModeState(GameModeState::RandomMode(rm)) => rm.statistics(),
+                    _ => &model.profile.statistics,
+                };
+                let report = StatisticsReport::new(statistics, &sessions, SystemClock.today());
+                model.ui.send(CoreMessage::StatisticsMessage(
+                    CoreStatisticsMessage::ReportResponse(report),
+                ));
+            }
+        }
+    }
+}
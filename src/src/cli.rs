@@ -0,0 +1,218 @@
+use std::path::PathBuf;
+use zankyou_core::{
+    domain::{
+        exercise::Exercise,
+        instrument::{Instrument, Sound},
+        notes::Note,
+        profile::Settings,
+        random::NotePool,
+    },
+    interfaces::{audio::PitchDetection, ui::GameMode},
+};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+// without a subcommand the trainer is started, as with `play`
+#[derive(Debug, Parser)]
+#[command(version, about = "Ear training in the terminal")]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub play: PlayArgs,
+
+    /// Write the log to this file, a new one is started every day
+    #[arg(long, global = true, value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
+
+    /// The log level or filter, e.g. debug or audio=trace, instead of RUST_LOG
+    #[arg(long, global = true, value_name = "FILTER")]
+    pub log_level: Option<String>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Start the trainer
+    Play(PlayArgs),
+    /// List the audio devices of every host with the configurations they
+    /// support
+    ListDevices,
+    /// Write the note pairs of an exercise to a WAV file
+    Render(RenderArgs),
+    /// Print the saved sessions and statistics
+    Stats,
+    /// List the instruments of a SoundFont as bank:preset
+    Presets(PresetsArgs),
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct PlayArgs {
+    /// Start right away in this game mode
+    #[arg(long, value_enum)]
+    pub mode: Option<Mode>,
+
+    #[command(flatten)]
+    pub drill: DrillArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct RenderArgs {
+    /// The WAV file to write
+    #[arg(short, long, value_name = "PATH")]
+    pub output: PathBuf,
+
+    /// How many note pairs to render
+    #[arg(short, long, default_value_t = 20)]
+    pub count: usize,
+
+    #[command(flatten)]
+    pub drill: DrillArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct PresetsArgs {
+    /// The SoundFont to look into instead of the built-in one
+    #[arg(long, value_name = "PATH")]
+    pub soundfont: Option<PathBuf>,
+}
+
+// overrides for the stored settings, they are only kept for this run
+#[derive(Debug, Clone, Args)]
+pub struct DrillArgs {
+    /// Seed every session so it can be repeated
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Play the exercise with this share code in the random mode
+    #[arg(long, value_name = "CODE")]
+    pub exercise: Option<Exercise>,
+
+    /// The lowest note of the range, e.g. E1
+    #[arg(long, value_name = "NOTE")]
+    pub lowest: Option<Note>,
+
+    /// The highest note of the range, e.g. F4
+    #[arg(long, value_name = "NOTE")]
+    pub highest: Option<Note>,
+
+    /// The notes to draw from: naturals, sharps, flats, all, a major key
+    /// like "Eb major" or pitch classes like "C, E, G"
+    #[arg(long)]
+    pub pool: Option<NotePool>,
+
+    /// The smallest distance between the two notes, in semitones
+    #[arg(long, value_name = "SEMITONES")]
+    pub min_offset: Option<u8>,
+
+    /// How often the second note lies above the first, in percent
+    #[arg(long, value_name = "PERCENT")]
+    pub ascending: Option<u8>,
+
+    /// Whether both notes may be the same
+    #[arg(long, value_name = "BOOL")]
+    pub allow_unison: Option<bool>,
+
+    /// The pitch of A4 in Hz
+    #[arg(long, value_name = "HZ")]
+    pub concert_a: Option<u16>,
+
+    /// Find the pitch that is played with yin, or with spectrum for bass
+    /// notes with a weak fundamental
+    #[arg(long)]
+    pub pitch_detection: Option<PitchDetection>,
+
+    /// Play with the SoundFont or with an oscillator: sine, triangle, saw,
+    /// organ or clarinet
+    #[arg(long)]
+    pub sound: Option<Sound>,
+
+    /// Play on the output device with this name, see list-devices
+    #[arg(long, value_name = "NAME")]
+    pub output_device: Option<String>,
+
+    /// Listen to the input device with this name, see list-devices
+    #[arg(long, value_name = "NAME")]
+    pub input_device: Option<String>,
+
+    /// Play with this SoundFont instead of the built-in one
+    #[arg(long, value_name = "PATH")]
+    pub soundfont: Option<PathBuf>,
+
+    /// Play every game mode with this instrument, as bank:preset or preset
+    #[arg(long, value_name = "INSTRUMENT")]
+    pub instrument: Option<Instrument>,
+}
+
+impl DrillArgs {
+    pub fn settings(&self, mut settings: Settings) -> Settings {
+        if let Some(seed) = self.seed {
+            settings.seed = Some(seed);
+        }
+        if let Some(lowest) = self.lowest {
+            settings.lowest_note = lowest;
+        }
+        if let Some(highest) = self.highest {
+            settings.highest_note = highest;
+        }
+        if let Some(pool) = &self.pool {
+            settings.pool = pool.clone();
+        }
+        if let Some(min_offset) = self.min_offset {
+            settings.min_offset = min_offset;
+        }
+        if let Some(ascending) = self.ascending {
+            settings.ascending = ascending;
+        }
+        if let Some(allow_unison) = self.allow_unison {
+            settings.allow_unison = allow_unison;
+        }
+        if let Some(concert_a) = self.concert_a {
+            settings.concert_a = concert_a;
+        }
+        if let Some(pitch_detection) = self.pitch_detection {
+            settings.pitch_detection = pitch_detection;
+        }
+        if let Some(output_device) = &self.output_device {
+            settings.output_device = Some(output_device.clone());
+        }
+        if let Some(input_device) = &self.input_device {
+            settings.input_device = Some(input_device.clone());
+        }
+        if let Some(sound) = self.sound {
+            settings.sound = sound;
+        }
+        if let Some(soundfont) = &self.soundfont {
+            settings.soundfont = Some(soundfont.clone());
+        }
+        if let Some(instrument) = self.instrument {
+            settings.instrument = instrument;
+            settings.mode_instruments = Default::default();
+        }
+        settings
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Mode {
+    Random,
+    Interval,
+    Chord,
+    Scale,
+    Dictation,
+    Review,
+}
+
+impl From<Mode> for GameMode {
+    fn from(mode: Mode) -> Self {
+        match mode {
+            Mode::Random => GameMode::Random,
+            Mode::Interval => GameMode::Interval,
+            Mode::Chord => GameMode::Chord,
+            Mode::Scale => GameMode::Scale,
+            Mode::Dictation => GameMode::Dictation,
+            Mode::Review => GameMode::Review,
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{RollingFileAppender, Rotation},
+};
+use tracing_subscriber::EnvFilter;
+
+const APP_DIR: &str = "zankyou";
+const LOG_FILE: &str = "zankyou.log";
+const DEFAULT_LEVEL: &str = "info";
+// days of logs that are kept
+const KEPT_LOG_FILES: usize = 7;
+
+// logs go to a file that is rotated every day, the terminal belongs to the
+// tui. the level comes from the command line, then from RUST_LOG. the guard
+// flushes the log when it is dropped, so it has to live until the end of main
+pub fn init(path: Option<&Path>, level: Option<&str>) -> Result<WorkerGuard> {
+    let filter = match level {
+        Some(level) => EnvFilter::try_new(level).context("Invalid log level")?,
+        None => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_LEVEL)),
+    };
+
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => default_path()?,
+    };
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("{} is not a file", path.display()))?
+        .to_string_lossy();
+
+    // the appender looks for old logs to remove before it creates the directory
+    fs::create_dir_all(directory)
+        .with_context(|| format!("Couldn't create the log directory {}", directory.display()))?;
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(file_name)
+        .max_log_files(KEPT_LOG_FILES)
+        .build(directory)
+        .with_context(|| format!("Couldn't open the log in {}", directory.display()))?;
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(writer)
+        .with_ansi(false)
+        .init();
+    Ok(guard)
+}
+
+// next to the rest of the data, the state dir only exists on linux
+fn default_path() -> Result<PathBuf> {
+    let directory = dirs::state_dir()
+        .or_else(dirs::data_dir)
+        .context("No directory for logs on this platform")?;
+    Ok(directory.join(APP_DIR).join(LOG_FILE))
+}
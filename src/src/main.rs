@@ -1,13 +1,187 @@
-use anyhow::Result;
-use audio::AudioDevice;
-use core::Core;
+use zankyou_core::{
+    Core,
+    domain::{
+        exercise::Exercise,
+        random::NoteSequence,
+        srs::{Clock, Day, SystemClock},
+        statistics::{Accuracy, StatisticsReport},
+    },
+    interfaces::{
+        audio::NoteEvent,
+        storage::{InMemoryStorage, StorageInterfaceTrait},
+        ui::GameMode,
+    },
+};
+
+use anyhow::{Context, Result};
+use audio::{AudioDevice, DeviceInfo, MicrophoneListener};
+use clap::Parser;
+use cli::{Cli, Command, PlayArgs, PresetsArgs, RenderArgs};
+use storage::JsonStorage;
+use tracing::error;
 use ui::RatatuiView;
 
+mod cli;
+mod logging;
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let _log_guard = logging::init(cli.log_file.as_deref(), cli.log_level.as_deref())?;
+
+    match cli.command {
+        None => play(cli.play).await,
+        Some(Command::Play(args)) => play(args).await,
+        Some(Command::ListDevices) => list_devices(),
+        Some(Command::Render(args)) => render(args),
+        Some(Command::Stats) => stats(),
+        Some(Command::Presets(args)) => presets(args),
+    }
+}
+
+fn storage() -> Box<dyn StorageInterfaceTrait> {
+    match JsonStorage::new() {
+        Ok(storage) => Box::new(storage),
+        Err(err) => {
+            error!(?err, "Progress will not be saved");
+            Box::new(InMemoryStorage::default())
+        }
+    }
+}
+
+async fn play(args: PlayArgs) -> Result<()> {
     let audio = AudioDevice::new()?;
-    let (ui, handle) = RatatuiView::create();
-    let mut core = Core::new(Box::new(audio), Box::new(ui));
+    let input = MicrophoneListener::create();
+    let (ui, handle) = RatatuiView::create(args.mode.map(Into::into));
+    let mut core = Core::new(Box::new(audio), Box::new(input), Box::new(ui), storage());
+    let settings = args.drill.settings(core.settings().clone());
+    core = core
+        .with_settings(settings)
+        .context("Invalid options for the settings")?;
+    if let Some(exercise) = args.drill.exercise {
+        core = core.with_exercise(exercise);
+    }
     tokio::task::spawn(async move { core.run().await });
     handle.await
 }
+
+// every host with its devices and what they support, the defaults are
+// marked with a star
+fn list_devices() -> Result<()> {
+    let marker = |default: bool| if default { "*" } else { " " };
+    let print = |title: &str, devices: &[DeviceInfo]| {
+        println!("  {title}:");
+        for device in devices {
+            println!("   {} {}", marker(device.default), device.name);
+            for config in &device.configs {
+                println!("       {config}");
+            }
+        }
+    };
+    for host in audio::list_devices() {
+        println!("{} {}:", marker(host.default), host.host);
+        print("Output devices", &host.outputs);
+        print("Input devices", &host.inputs);
+    }
+    Ok(())
+}
+
+// the note pairs are played as in the random mode, with a pause of the same
+// length after every pair, the answers are printed
+fn render(args: RenderArgs) -> Result<()> {
+    let stored = storage().load_settings().unwrap_or_else(|err| {
+        error!(?err, "Failed to load settings, using the defaults");
+        Default::default()
+    });
+    let settings = args.drill.settings(stored);
+    settings
+        .validate()
+        .context("Invalid options for the settings")?;
+    let exercise = match args.drill.exercise {
+        Some(exercise) => exercise,
+        None => Exercise::new(
+            settings.seed.unwrap_or_else(rand::random),
+            settings.note_sequence(),
+        ),
+    };
+
+    let mut note_sequence = exercise.note_sequence();
+    let mut phrase = Vec::new();
+    println!("Exercise {exercise}");
+    for number in 1..=args.count {
+        let note_tuple = note_sequence.next_note();
+        println!(
+            "{number:>3}. {} {}",
+            note_tuple.reference(),
+            note_tuple.divergence()
+        );
+        phrase.extend([
+            NoteEvent::note(note_tuple.reference(), settings.tone_duration),
+            NoteEvent::note(note_tuple.divergence(), settings.tone_duration),
+            NoteEvent::new(Vec::new(), settings.tone_duration),
+        ]);
+    }
+
+    let fallback = audio::render_wav(
+        &args.output,
+        &phrase,
+        &settings.audio(Some(GameMode::Random)),
+    )?;
+    if let Some(fallback) = fallback {
+        println!("The audio settings could not be applied, rendered with {fallback} instead");
+    }
+    println!("Wrote {}", args.output.display());
+    Ok(())
+}
+
+fn stats() -> Result<()> {
+    let mut storage = storage();
+    let profile = storage.load_profile()?;
+    let sessions = storage.sessions()?;
+
+    println!("Sessions:");
+    for session in &sessions {
+        let mut accuracy = Accuracy::default();
+        for guess in &session.guesses {
+            accuracy.record(guess.correct, guess.response_time);
+        }
+        let mut line = format!(
+            "  {} {}: {}",
+            Day::from(session.started_at),
+            session.mode,
+            summary(&accuracy)
+        );
+        match (&session.exercise, session.seed) {
+            (Some(exercise), _) => line.push_str(&format!(", exercise {exercise}")),
+            (None, Some(seed)) => line.push_str(&format!(", seed {seed}")),
+            (None, None) => {}
+        }
+        println!("{line}");
+    }
+
+    let report = StatisticsReport::new(&profile.statistics, &sessions, SystemClock.today());
+    println!("Random notes: {}", summary(&report.overall));
+    println!(
+        "Streak: {} right in a row (best {}), practiced {} days in a row",
+        report.current_streak, report.best_streak, report.practice_days
+    );
+    println!("Weekly accuracy:");
+    for (week, accuracy) in &report.weekly {
+        println!("  {week}: {}", summary(accuracy));
+    }
+    Ok(())
+}
+
+fn presets(args: PresetsArgs) -> Result<()> {
+    for (instrument, name) in audio::presets(args.soundfont.as_deref())? {
+        println!("{instrument:>7} {name}");
+    }
+    Ok(())
+}
+
+fn summary(accuracy: &Accuracy) -> String {
+    match accuracy.ratio() {
+        Some(ratio) => format!("{} answers, {:.0}% right", accuracy.attempts, ratio * 100.0),
+        None => "no answers".to_string(),
+    }
+}
@@ -0,0 +1,360 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use core::{
+    domain::profile::{Profile, Session, Settings},
+    interfaces::storage::StorageInterfaceTrait,
+};
+use serde::{Serialize, de::DeserializeOwned};
+use tracing::{debug, warn};
+
+use migrations::{DATA_MIGRATIONS, Migration, SETTINGS_MIGRATIONS, current_version, migrate};
+use schema::{DataFile, ProfileEntry, SessionEntry, SettingsFile};
+
+mod migrations;
+mod schema;
+
+const APP_DIR: &str = "zankyou";
+const DATA_FILE: &str = "data.json";
+const SETTINGS_FILE: &str = "settings.json";
+
+// progress and sessions go to the XDG data dir, settings to the config dir,
+// both as versioned JSON documents that are migrated when they are read
+#[derive(Debug)]
+pub struct JsonStorage {
+    data_path: PathBuf,
+    settings_path: PathBuf,
+    // read once, then every change is written through
+    data: Option<DataFile>,
+}
+
+impl JsonStorage {
+    pub fn new() -> Result<Self> {
+        let data_dir = dirs::data_dir().context("No data directory for this platform")?;
+        let config_dir = dirs::config_dir().context("No config directory for this platform")?;
+        Ok(Self::with_paths(
+            data_dir.join(APP_DIR).join(DATA_FILE),
+            config_dir.join(APP_DIR).join(SETTINGS_FILE),
+        ))
+    }
+
+    pub fn with_paths(data_path: PathBuf, settings_path: PathBuf) -> Self {
+        Self {
+            data_path,
+            settings_path,
+            data: None,
+        }
+    }
+
+    fn data(&mut self) -> Result<&mut DataFile> {
+        if self.data.is_none() {
+            let data = match read(&self.data_path, DATA_MIGRATIONS)? {
+                Some(data) => data,
+                None => DataFile {
+                    version: current_version(DATA_MIGRATIONS),
+                    ..DataFile::default()
+                },
+            };
+            self.data = Some(data);
+        }
+        Ok(self.data.get_or_insert_default())
+    }
+
+    fn write_data(&mut self) -> Result<()> {
+        let path = self.data_path.clone();
+        write(&path, self.data()?)
+    }
+}
+
+impl StorageInterfaceTrait for JsonStorage {
+    fn load_profile(&mut self) -> Result<Profile> {
+        Profile::try_from(&self.data()?.profile)
+    }
+
+    fn save_profile(&mut self, profile: &Profile) -> Result<()> {
+        self.data()?.profile = ProfileEntry::from(profile);
+        self.write_data()
+    }
+
+    fn load_settings(&mut self) -> Result<Settings> {
+        match read::<SettingsFile>(&self.settings_path, SETTINGS_MIGRATIONS)? {
+            Some(file) => file.settings(),
+            None => Ok(Settings::default()),
+        }
+    }
+
+    fn save_settings(&mut self, settings: &Settings) -> Result<()> {
+        let file = SettingsFile::new(current_version(SETTINGS_MIGRATIONS), settings);
+        write(&self.settings_path, &file)
+    }
+
+    fn save_session(&mut self, session: &Session) -> Result<()> {
+        self.data()?.sessions.push(SessionEntry::from(session));
+        self.write_data()
+    }
+
+    fn sessions(&mut self) -> Result<Vec<Session>> {
+        self.data()?
+            .sessions
+            .iter()
+            .map(Session::try_from)
+            .collect()
+    }
+}
+
+// the migrated document, or nothing if the file does not exist yet
+fn read<T: DeserializeOwned>(path: &Path, migrations: &[Migration]) -> Result<Option<T>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err).with_context(|| format!("Failed to read {}", path.display())),
+    };
+
+    let document = serde_json::from_str(&contents)
+        .map_err(anyhow::Error::from)
+        .and_then(|mut document| {
+            migrate(&mut document, migrations)?;
+            serde_json::from_value(document).context("The document does not match the schema")
+        });
+    match document {
+        Ok(document) => Ok(Some(document)),
+        Err(err) => {
+            // keep the unreadable file around instead of overwriting it later
+            let backup = path.with_extension("json.bak");
+            warn!(?err, path = %path.display(), backup = %backup.display(), "Moving unreadable file aside");
+            fs::rename(path, &backup)
+                .with_context(|| format!("Failed to move {} aside", path.display()))?;
+            Err(err.context(format!("Failed to load {}", path.display())))
+        }
+    }
+}
+
+// writes to a temporary file first so a crash never leaves half a document
+fn write<T: Serialize>(path: &Path, document: &T) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let temporary = path.with_extension("json.tmp");
+    let contents = serde_json::to_string_pretty(document)?;
+    fs::write(&temporary, contents)
+        .with_context(|| format!("Failed to write {}", temporary.display()))?;
+    fs::rename(&temporary, path)
+        .with_context(|| format!("Failed to replace {}", path.display()))?;
+    debug!(path = %path.display(), "Saved document");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        domain::{
+            exercise::Exercise,
+            instrument::{Instrument, Sound},
+            notes::Note,
+            profile::GuessRecord,
+            random::{Accidentals, NotePool, RandomNoteSequenceConfig},
+            srs::{Card, Day, NewCards, ReviewState},
+        },
+        interfaces::ui::GameMode,
+    };
+    use std::{
+        collections::HashMap,
+        time::{Duration, UNIX_EPOCH},
+    };
+
+    use super::*;
+
+    // a directory of its own for every test, removed when it is dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("zankyou-{}-{name}", std::process::id()));
+            fs::remove_dir_all(&dir).ok();
+            Self(dir)
+        }
+
+        fn storage(&self) -> JsonStorage {
+            JsonStorage::with_paths(self.0.join(DATA_FILE), self.0.join(SETTINGS_FILE))
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            fs::remove_dir_all(&self.0).ok();
+        }
+    }
+
+    fn note(s: &str) -> Note {
+        s.parse().unwrap()
+    }
+
+    fn settings() -> Settings {
+        Settings {
+            lowest_note: note("C2"),
+            highest_note: note("G#4"),
+            pool: "Eb major".parse().unwrap(),
+            min_offset: 2,
+            ascending: 70,
+            allow_unison: false,
+            volume: 80,
+            sound: Sound::Organ,
+            instrument: Instrument::new(0, 32).unwrap(),
+            mode_instruments: "chord=0:33".parse().unwrap(),
+            concert_a: 442,
+            output_device: Some("speakers".to_string()),
+            seed: Some(7),
+            ..Settings::default()
+        }
+    }
+
+    fn profile() -> Profile {
+        let mut profile = Profile {
+            deck: HashMap::from([
+                (
+                    Card::NotePair {
+                        reference: note("C4"),
+                        divergence: note("Eb4"),
+                    },
+                    ReviewState {
+                        repetitions: 2,
+                        ease: 2.36,
+                        interval_days: 6,
+                        due: Day(20_006),
+                    },
+                ),
+                (
+                    Card::Interval("m3".parse().unwrap()),
+                    ReviewState::new(Day(20_000)),
+                ),
+            ]),
+            new_cards: NewCards {
+                day: Day(20_000),
+                count: 3,
+            },
+            ..Profile::default()
+        };
+        profile
+            .statistics
+            .record(note("C4"), note("E4"), true, Duration::from_millis(1500));
+        profile
+            .statistics
+            .record(note("A2"), note("F2"), false, Duration::from_millis(700));
+        profile
+    }
+
+    fn session() -> Session {
+        Session {
+            mode: GameMode::Random,
+            seed: Some(42),
+            exercise: Some(Exercise::new(42, RandomNoteSequenceConfig::default())),
+            started_at: UNIX_EPOCH + Duration::from_millis(1_700_000_000_000),
+            ended_at: Some(UNIX_EPOCH + Duration::from_millis(1_700_000_060_500)),
+            guesses: vec![GuessRecord {
+                reference: note("C4"),
+                divergence: note("E4"),
+                played: note("C4"),
+                correct: true,
+                at: UNIX_EPOCH + Duration::from_millis(1_700_000_010_250),
+                response_time: Duration::from_millis(2250),
+            }],
+        }
+    }
+
+    #[test]
+    fn missing_files_give_the_defaults() {
+        let dir = TempDir::new("missing");
+        let mut storage = dir.storage();
+        assert_eq!(storage.load_settings().unwrap(), Settings::default());
+        assert_eq!(storage.load_profile().unwrap(), Profile::default());
+        assert!(storage.sessions().unwrap().is_empty());
+        assert!(!dir.0.exists());
+    }
+
+    #[test]
+    fn saved_documents_load_again() {
+        let dir = TempDir::new("round-trip");
+        let mut storage = dir.storage();
+        storage.save_settings(&settings()).unwrap();
+        storage.save_profile(&profile()).unwrap();
+        storage.save_session(&session()).unwrap();
+        storage.save_session(&session()).unwrap();
+
+        // read back from disk, not from what is kept in memory
+        let mut storage = dir.storage();
+        assert_eq!(storage.load_settings().unwrap(), settings());
+        assert_eq!(storage.load_profile().unwrap(), profile());
+        assert_eq!(storage.sessions().unwrap(), [session(), session()]);
+    }
+
+    #[test]
+    fn old_settings_files_are_migrated() {
+        let dir = TempDir::new("migration");
+        fs::create_dir_all(&dir.0).unwrap();
+        let path = dir.0.join(SETTINGS_FILE);
+        // from before the version was kept, with a General MIDI preset
+        fs::write(
+            &path,
+            r#"{ "lowest_note": "C2", "highest_note": "G4", "accidentals": "flats", "preset": 33, "volume": 80 }"#,
+        )
+        .unwrap();
+
+        let mut storage = dir.storage();
+        let settings = storage.load_settings().unwrap();
+        assert_eq!(settings.lowest_note, note("C2"));
+        assert_eq!(settings.highest_note, note("G4"));
+        assert_eq!(settings.pool, NotePool::Accidentals(Accidentals::Flats));
+        assert_eq!(settings.instrument, Instrument::new(0, 33).unwrap());
+        assert_eq!(settings.volume, 80);
+        assert_eq!(settings.concert_a, Settings::default().concert_a);
+
+        storage.save_settings(&settings).unwrap();
+        let saved: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved["version"], current_version(SETTINGS_MIGRATIONS));
+        assert_eq!(saved["instrument"], "0:33");
+        assert_eq!(saved["pool"], "flats");
+        assert!(saved.get("preset").is_none());
+        assert!(saved.get("accidentals").is_none());
+    }
+
+    #[test]
+    fn old_guesses_have_no_response_time() {
+        let dir = TempDir::new("response-time");
+        fs::create_dir_all(&dir.0).unwrap();
+        fs::write(
+            dir.0.join(DATA_FILE),
+            r#"{ "version": 1, "sessions": [{ "mode": "random", "started_at": 1700000000000,
+                "guesses": [{ "reference": "C4", "divergence": "E4", "played": "D4",
+                    "correct": false, "at": 1700000001000 }] }] }"#,
+        )
+        .unwrap();
+
+        let mut storage = dir.storage();
+        let sessions = storage.sessions().unwrap();
+        let guess = &sessions[0].guesses[0];
+        assert_eq!(guess.played, note("D4"));
+        assert_eq!(guess.response_time, Duration::ZERO);
+    }
+
+    #[test]
+    fn unreadable_files_are_moved_aside() {
+        let dir = TempDir::new("unreadable");
+        fs::create_dir_all(&dir.0).unwrap();
+        let path = dir.0.join(SETTINGS_FILE);
+        let newer = r#"{ "version": 1000 }"#;
+        fs::write(&path, newer).unwrap();
+
+        let mut storage = dir.storage();
+        assert!(storage.load_settings().is_err());
+        assert!(!path.exists());
+        assert_eq!(
+            fs::read_to_string(path.with_extension("json.bak")).unwrap(),
+            newer
+        );
+        assert_eq!(storage.load_settings().unwrap(), Settings::default());
+    }
+}
@@ -0,0 +1,111 @@
+use anyhow::{Context, Result, bail};
+use serde_json::Value;
+use tracing::info;
+
+// upgrades a document by one version, the migration at index i turns version
+// i + 1 into i + 2
+pub(crate) type Migration = fn(&mut Value) -> Result<()>;
+
+pub(crate) const DATA_MIGRATIONS: &[Migration] = &[];
+pub(crate) const SETTINGS_MIGRATIONS: &[Migration] = &[preset_to_instrument, accidentals_to_pool];
+
+pub(crate) fn current_version(migrations: &[Migration]) -> u64 {
+    migrations.len() as u64 + 1
+}
+
+// the preset was a General MIDI program, now banks can be chosen as well
+fn preset_to_instrument(document: &mut Value) -> Result<()> {
+    let settings = document
+        .as_object_mut()
+        .context("The settings are not an object")?;
+    if let Some(preset) = settings.remove("preset") {
+        let preset = preset.as_u64().context("The preset is not a number")?;
+        settings.insert("instrument".to_string(), Value::from(format!("0:{preset}")));
+    }
+    Ok(())
+}
+
+// the accidentals were the only way to choose the notes, they are one kind of
+// pool now and are written the same way
+fn accidentals_to_pool(document: &mut Value) -> Result<()> {
+    let settings = document
+        .as_object_mut()
+        .context("The settings are not an object")?;
+    if let Some(accidentals) = settings.remove("accidentals") {
+        settings.insert("pool".to_string(), accidentals);
+    }
+    Ok(())
+}
+
+// brings a document of any older version up to the current one
+pub(crate) fn migrate(document: &mut Value, migrations: &[Migration]) -> Result<()> {
+    // files written before the version was kept are version 0, they have the
+    // layout of version 1
+    let version = match document.get("version") {
+        Some(version) => version.as_u64().context("The version is not a number")?,
+        None => 0,
+    };
+    let current = current_version(migrations);
+    if version > current {
+        bail!("Version {version} is not supported, the newest one is {current}");
+    }
+
+    for (index, migration) in migrations
+        .iter()
+        .enumerate()
+        .skip(version.max(1) as usize - 1)
+    {
+        let next = index as u64 + 2;
+        migration(document).with_context(|| format!("Failed to migrate to version {next}"))?;
+        document["version"] = Value::from(next);
+        info!(version = next, "Migrated document");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn add_field(document: &mut Value) -> Result<()> {
+        document["added"] = Value::from(true);
+        Ok(())
+    }
+
+    const MIGRATIONS: &[Migration] = &[preset_to_instrument, add_field];
+
+    #[test]
+    fn documents_are_brought_up_to_the_current_version() {
+        for version in [json!(0), json!(1), Value::Null] {
+            let mut document = json!({ "version": version, "preset": 33 });
+            if version.is_null() {
+                document.as_object_mut().unwrap().remove("version");
+            }
+            migrate(&mut document, MIGRATIONS).unwrap();
+            assert_eq!(
+                document,
+                json!({ "version": 3, "instrument": "0:33", "added": true })
+            );
+        }
+
+        let mut document = json!({ "version": 2, "instrument": "8:1" });
+        migrate(&mut document, MIGRATIONS).unwrap();
+        assert_eq!(
+            document,
+            json!({ "version": 3, "instrument": "8:1", "added": true })
+        );
+
+        let mut document = json!({ "version": 3, "instrument": "8:1" });
+        migrate(&mut document, MIGRATIONS).unwrap();
+        assert_eq!(document, json!({ "version": 3, "instrument": "8:1" }));
+    }
+
+    #[test]
+    fn unknown_versions_are_rejected() {
+        assert!(migrate(&mut json!({ "version": 4 }), MIGRATIONS).is_err());
+        assert!(migrate(&mut json!({ "version": "1" }), MIGRATIONS).is_err());
+        assert!(migrate(&mut json!({ "version": 1, "preset": "bass" }), MIGRATIONS).is_err());
+    }
+}
@@ -0,0 +1,437 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use core::{
+    domain::{
+        exercise::Exercise,
+        profile::{GuessRecord, Profile, Session, Settings},
+        srs::{Card, Day, NewCards, ReviewState},
+        statistics::{Accuracy, RandomModeStatistics},
+    },
+    interfaces::ui::GameMode,
+};
+use serde::{Deserialize, Serialize};
+
+// the layout of the files on disk, notes and intervals are kept as their
+// names so the files stay readable
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct DataFile {
+    pub version: u64,
+    #[serde(default)]
+    pub profile: ProfileEntry,
+    #[serde(default)]
+    pub sessions: Vec<SessionEntry>,
+}
+
+// fields missing from older files fall back to their defaults
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct SettingsFile {
+    pub version: u64,
+    pub lowest_note: String,
+    pub highest_note: String,
+    // naturals, sharps, flats, all, a major key or pitch classes
+    pub pool: String,
+    pub octave_span: u8,
+    pub min_offset: u8,
+    pub ascending: u8,
+    pub allow_unison: bool,
+    pub tone_duration_ms: u64,
+    pub volume: u8,
+    pub sound: String,
+    pub soundfont: Option<PathBuf>,
+    // bank:preset
+    pub instrument: String,
+    // mode=bank:preset, separated by commas
+    pub mode_instruments: String,
+    pub velocity: u8,
+    pub concert_a: u16,
+    pub pitch_detection: String,
+    pub input_device: Option<String>,
+    pub output_device: Option<String>,
+    pub new_cards_per_day: usize,
+    pub seed: Option<u64>,
+}
+
+impl Default for SettingsFile {
+    fn default() -> Self {
+        SettingsFile::new(0, &Settings::default())
+    }
+}
+
+impl SettingsFile {
+    pub fn new(version: u64, settings: &Settings) -> Self {
+        Self {
+            version,
+            lowest_note: settings.lowest_note.to_string(),
+            highest_note: settings.highest_note.to_string(),
+            pool: settings.pool.to_string(),
+            octave_span: settings.octave_span,
+            min_offset: settings.min_offset,
+            ascending: settings.ascending,
+            allow_unison: settings.allow_unison,
+            tone_duration_ms: settings.tone_duration.as_millis() as u64,
+            volume: settings.volume,
+            sound: settings.sound.to_string(),
+            soundfont: settings.soundfont.clone(),
+            instrument: settings.instrument.to_string(),
+            mode_instruments: settings.mode_instruments.to_string(),
+            velocity: settings.velocity,
+            concert_a: settings.concert_a,
+            pitch_detection: settings.pitch_detection.to_string(),
+            input_device: settings.input_device.clone(),
+            output_device: settings.output_device.clone(),
+            new_cards_per_day: settings.new_cards_per_day,
+            seed: settings.seed,
+        }
+    }
+
+    pub fn settings(&self) -> Result<Settings> {
+        Ok(Settings {
+            lowest_note: self.lowest_note.parse()?,
+            highest_note: self.highest_note.parse()?,
+            pool: self.pool.parse()?,
+            octave_span: self.octave_span,
+            min_offset: self.min_offset,
+            ascending: self.ascending,
+            allow_unison: self.allow_unison,
+            tone_duration: Duration::from_millis(self.tone_duration_ms),
+            volume: self.volume,
+            sound: self.sound.parse()?,
+            soundfont: self.soundfont.clone(),
+            instrument: self.instrument.parse()?,
+            mode_instruments: self.mode_instruments.parse()?,
+            velocity: self.velocity,
+            concert_a: self.concert_a,
+            pitch_detection: self.pitch_detection.parse()?,
+            input_device: self.input_device.clone(),
+            output_device: self.output_device.clone(),
+            new_cards_per_day: self.new_cards_per_day,
+            seed: self.seed,
+        })
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct ProfileEntry {
+    pub statistics: StatisticsEntry,
+    pub deck: Vec<CardEntry>,
+    // profiles saved before the daily limit was kept have none
+    #[serde(default)]
+    pub new_cards: NewCardsEntry,
+}
+
+impl From<&Profile> for ProfileEntry {
+    fn from(profile: &Profile) -> Self {
+        Self {
+            statistics: StatisticsEntry::from(&profile.statistics),
+            deck: profile
+                .deck
+                .iter()
+                .map(|(card, state)| CardEntry::new(card, state))
+                .collect(),
+            new_cards: NewCardsEntry {
+                day: profile.new_cards.day.0,
+                count: profile.new_cards.count,
+            },
+        }
+    }
+}
+
+impl TryFrom<&ProfileEntry> for Profile {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: &ProfileEntry) -> Result<Self> {
+        let deck = entry
+            .deck
+            .iter()
+            .map(CardEntry::card)
+            .collect::<Result<HashMap<_, _>>>()?;
+        Ok(Profile {
+            statistics: RandomModeStatistics::from(&entry.statistics),
+            deck,
+            new_cards: NewCards {
+                day: Day(entry.new_cards.day),
+                count: entry.new_cards.count,
+            },
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub(crate) struct AccuracyEntry {
+    pub attempts: u32,
+    pub correct: u32,
+    pub total_response_time_ms: u64,
+}
+
+impl From<Accuracy> for AccuracyEntry {
+    fn from(accuracy: Accuracy) -> Self {
+        Self {
+            attempts: accuracy.attempts,
+            correct: accuracy.correct,
+            total_response_time_ms: accuracy.total_response_time.as_millis() as u64,
+        }
+    }
+}
+
+impl From<AccuracyEntry> for Accuracy {
+    fn from(entry: AccuracyEntry) -> Self {
+        Accuracy {
+            attempts: entry.attempts,
+            correct: entry.correct,
+            total_response_time: Duration::from_millis(entry.total_response_time_ms),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct StatisticsEntry {
+    pub overall: AccuracyEntry,
+    pub intervals: BTreeMap<i16, AccuracyEntry>,
+    pub registers: BTreeMap<i8, AccuracyEntry>,
+    pub pitch_classes: BTreeMap<u8, AccuracyEntry>,
+}
+
+impl From<&RandomModeStatistics> for StatisticsEntry {
+    fn from(statistics: &RandomModeStatistics) -> Self {
+        Self {
+            overall: statistics.overall().into(),
+            intervals: entries(statistics.intervals()),
+            registers: entries(statistics.registers()),
+            pitch_classes: entries(statistics.pitch_classes()),
+        }
+    }
+}
+
+impl From<&StatisticsEntry> for RandomModeStatistics {
+    fn from(entry: &StatisticsEntry) -> Self {
+        RandomModeStatistics::new(
+            entry.overall.into(),
+            accuracies(&entry.intervals),
+            accuracies(&entry.registers),
+            accuracies(&entry.pitch_classes),
+        )
+    }
+}
+
+fn entries<K: Ord + Copy>(accuracies: &HashMap<K, Accuracy>) -> BTreeMap<K, AccuracyEntry> {
+    accuracies
+        .iter()
+        .map(|(key, accuracy)| (*key, (*accuracy).into()))
+        .collect()
+}
+
+fn accuracies<K: std::hash::Hash + Eq + Copy>(
+    entries: &BTreeMap<K, AccuracyEntry>,
+) -> HashMap<K, Accuracy> {
+    entries
+        .iter()
+        .map(|(key, entry)| (*key, (*entry).into()))
+        .collect()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum CardKind {
+    NotePair {
+        reference: String,
+        divergence: String,
+    },
+    Interval {
+        interval: String,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct CardEntry {
+    #[serde(flatten)]
+    pub kind: CardKind,
+    pub repetitions: u32,
+    pub ease: f32,
+    pub interval_days: u32,
+    pub due: i64,
+}
+
+impl CardEntry {
+    fn new(card: &Card, state: &ReviewState) -> Self {
+        let kind = match card {
+            Card::NotePair {
+                reference,
+                divergence,
+            } => CardKind::NotePair {
+                reference: reference.to_string(),
+                divergence: divergence.to_string(),
+            },
+            Card::Interval(interval) => CardKind::Interval {
+                interval: interval.to_string(),
+            },
+        };
+        Self {
+            kind,
+            repetitions: state.repetitions,
+            ease: state.ease,
+            interval_days: state.interval_days,
+            due: state.due.0,
+        }
+    }
+
+    fn card(&self) -> Result<(Card, ReviewState)> {
+        let card = match &self.kind {
+            CardKind::NotePair {
+                reference,
+                divergence,
+            } => Card::NotePair {
+                reference: reference.parse()?,
+                divergence: divergence.parse()?,
+            },
+            CardKind::Interval { interval } => Card::Interval(interval.parse()?),
+        };
+        let state = ReviewState {
+            repetitions: self.repetitions,
+            ease: self.ease,
+            interval_days: self.interval_days,
+            due: Day(self.due),
+        };
+        Ok((card, state))
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct NewCardsEntry {
+    pub day: i64,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct SessionEntry {
+    pub mode: String,
+    // sessions saved before seeding have neither
+    #[serde(default)]
+    pub seed: Option<u64>,
+    // the share code of the exercise
+    #[serde(default)]
+    pub exercise: Option<String>,
+    pub started_at: u64,
+    pub ended_at: Option<u64>,
+    pub guesses: Vec<GuessEntry>,
+}
+
+impl From<&Session> for SessionEntry {
+    fn from(session: &Session) -> Self {
+        Self {
+            mode: mode_name(session.mode).to_string(),
+            seed: session.seed,
+            exercise: session.exercise.as_ref().map(Exercise::share_code),
+            started_at: timestamp(session.started_at),
+            ended_at: session.ended_at.map(timestamp),
+            guesses: session.guesses.iter().map(GuessEntry::from).collect(),
+        }
+    }
+}
+
+impl TryFrom<&SessionEntry> for Session {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: &SessionEntry) -> Result<Self> {
+        Ok(Session {
+            mode: game_mode(&entry.mode)?,
+            seed: entry.seed,
+            exercise: entry
+                .exercise
+                .as_deref()
+                .map(str::parse)
+                .transpose()
+                .context("A session has an invalid exercise")?,
+            started_at: system_time(entry.started_at),
+            ended_at: entry.ended_at.map(system_time),
+            guesses: entry
+                .guesses
+                .iter()
+                .map(GuessRecord::try_from)
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct GuessEntry {
+    pub reference: String,
+    pub divergence: String,
+    pub played: String,
+    pub correct: bool,
+    pub at: u64,
+    // guesses saved before response times were kept have none
+    #[serde(default)]
+    pub response_time_ms: u64,
+}
+
+impl From<&GuessRecord> for GuessEntry {
+    fn from(guess: &GuessRecord) -> Self {
+        Self {
+            reference: guess.reference.to_string(),
+            divergence: guess.divergence.to_string(),
+            played: guess.played.to_string(),
+            correct: guess.correct,
+            at: timestamp(guess.at),
+            response_time_ms: guess.response_time.as_millis() as u64,
+        }
+    }
+}
+
+impl TryFrom<&GuessEntry> for GuessRecord {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: &GuessEntry) -> Result<Self> {
+        Ok(GuessRecord {
+            reference: entry.reference.parse()?,
+            divergence: entry.divergence.parse()?,
+            played: entry.played.parse()?,
+            correct: entry.correct,
+            at: system_time(entry.at),
+            response_time: Duration::from_millis(entry.response_time_ms),
+        })
+    }
+}
+
+// kept apart from the display names so renaming a mode keeps old files readable
+const GAME_MODES: [(GameMode, &str); 6] = [
+    (GameMode::Random, "random"),
+    (GameMode::Interval, "interval"),
+    (GameMode::Chord, "chord"),
+    (GameMode::Scale, "scale"),
+    (GameMode::Dictation, "dictation"),
+    (GameMode::Review, "review"),
+];
+
+fn mode_name(mode: GameMode) -> &'static str {
+    GAME_MODES
+        .iter()
+        .find(|(known, _)| *known == mode)
+        .map(|(_, name)| *name)
+        .expect("every game mode has a name")
+}
+
+fn game_mode(name: &str) -> Result<GameMode> {
+    GAME_MODES
+        .iter()
+        .find(|(_, known)| *known == name)
+        .map(|(mode, _)| *mode)
+        .with_context(|| format!("There is no game mode called '{name}'"))
+}
+
+// milliseconds since the unix epoch
+fn timestamp(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn system_time(timestamp: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(timestamp)
+}
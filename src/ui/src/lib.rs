@@ -1,4 +1,7 @@
-use core::interfaces::ui::{CoreMessage, UIGameMessage, UserInterface, UserInterfaceMessage};
+use core::interfaces::ui::{
+    CoreAudioMessage, CoreMessage, GameMode, UIGameMessage, UIMainMenuMessage, UIStatisticsMessage,
+    UserInterface, UserInterfaceMessage,
+};
 use std::io::{self, Stdout};
 use std::pin::Pin;
 use std::time::Duration;
@@ -11,7 +14,6 @@ use crossterm::terminal::{
     EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
 };
 use futures::StreamExt;
-use gag::Gag;
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, Padding, Paragraph};
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
@@ -21,6 +23,7 @@ use widgets::config::{ConfigWidget, ConfigWidgetState};
 use widgets::game::{GameWidget, GameWidgetState};
 use widgets::icon::{IconWidget, IconWidgetState};
 use widgets::menu::{MenuWidget, SideMenuWidgetState};
+use widgets::statistics::{StatisticsWidget, StatisticsWidgetState};
 use widgets::{HIGHLIGHT_STYLE, Selection};
 
 mod widgets;
@@ -40,6 +43,49 @@ impl CoreMessageHandler for CoreMessage {
         match self {
             CoreMessage::MainMenuMessage(msg) => msg.handle(view),
             CoreMessage::GameMessage(msg) => msg.handle(view),
+            CoreMessage::StatisticsMessage(msg) => msg.handle(view),
+            CoreMessage::ConfigMessage(msg) => msg.handle(view),
+            CoreMessage::AudioMessage(msg) => msg.handle(view),
+        }
+    }
+}
+
+impl CoreMessageHandler for CoreAudioMessage {
+    fn handle(self, view: &mut RatatuiView) {
+        match self {
+            CoreAudioMessage::OutputDeviceLost { device, recovered } => {
+                let device = device.map_or("The output device".to_string(), |name| {
+                    format!("The output device '{name}'")
+                });
+                let notice = if recovered {
+                    format!("{device} is gone, playing on the default device")
+                } else {
+                    format!("{device} is gone and there is no other to play on")
+                };
+                view.app.notice = Some(notice);
+            }
+            CoreAudioMessage::InputDeviceLost { device, recovered } => {
+                let device = device.map_or("The input device".to_string(), |name| {
+                    format!("The input device '{name}'")
+                });
+                let notice = if recovered {
+                    format!("{device} is gone, listening to the default device")
+                } else {
+                    format!("{device} is gone and there is no other to listen to")
+                };
+                view.app.notice = Some(notice);
+            }
+            CoreAudioMessage::AudioFallback(fallback) => {
+                let notice = match fallback {
+                    Some(fallback) => format!(
+                        "The audio settings could not be applied, playing with {fallback} instead"
+                    ),
+                    None => {
+                        "The audio settings could not be applied, playing as before".to_string()
+                    }
+                };
+                view.app.notice = Some(notice);
+            }
         }
     }
 }
@@ -82,6 +128,7 @@ impl CoreInterface {
 #[derive(Debug, Clone)]
 pub enum MenuState {
     Game(MenuGame),
+    Statistics(MenuStatistics),
     Config(MenuConfig),
 }
 
@@ -94,6 +141,9 @@ impl Default for MenuState {
 #[derive(Default, Debug, Clone)]
 pub struct MenuGame {}
 
+#[derive(Default, Debug, Clone)]
+pub struct MenuStatistics {}
+
 #[derive(Default, Debug, Clone)]
 pub struct MenuConfig {}
 
@@ -127,16 +177,59 @@ impl EventHandler for CursorState {
 impl EventHandler for CursorMain {
     fn handle(self, event: Event, view: &mut crate::RatatuiView) {
         if let Event::Key(key_event) = event {
+            // the config form needs the arrow keys to move inside a field
+            let on_config = matches!(view.app.menu_state, MenuState::Config(_));
             match key_event.code {
+                KeyCode::Left if on_config => view.app.config_widget.input(key_event),
                 KeyCode::Left | KeyCode::Esc => {
                     view.app.transition_cursor(CursorState::Menu(CursorMenu {}));
                     view.app.game_widget.reset();
                 }
                 _ => match view.app.menu_state {
-                    MenuState::Game(_) => if let KeyCode::Char('n') = key_event.code { view.core_interface.send(
-                        UserInterfaceMessage::GameMessage(UIGameMessage::NoteRequest),
-                    ) },
-                    MenuState::Config(_) => {}
+                    MenuState::Game(_) => match key_event.code {
+                        KeyCode::Char('n') => view.core_interface.send(
+                            UserInterfaceMessage::GameMessage(UIGameMessage::NoteRequest),
+                        ),
+                        KeyCode::Char('m') => {
+                            view.app.game_widget.next_mode();
+                            view.core_interface
+                                .send(UserInterfaceMessage::MainMenuMessage(
+                                    UIMainMenuMessage::Start(view.app.game_widget.mode()),
+                                ));
+                        }
+                        KeyCode::Up => view.app.game_widget.previous_choice(),
+                        KeyCode::Down => view.app.game_widget.next_choice(),
+                        KeyCode::Enter => {
+                            if let Some(message) = view.app.game_widget.submit() {
+                                view.core_interface
+                                    .send(UserInterfaceMessage::GameMessage(message));
+                            }
+                        }
+                        KeyCode::Char(c) if view.app.game_widget.mode() == GameMode::Dictation => {
+                            view.app.game_widget.push_input(c)
+                        }
+                        KeyCode::Backspace => view.app.game_widget.pop_input(),
+                        _ => {}
+                    },
+                    MenuState::Statistics(_) => {
+                        if key_event.code == KeyCode::Char('r') {
+                            view.core_interface
+                                .send(UserInterfaceMessage::StatisticsMessage(
+                                    UIStatisticsMessage::ReportRequest,
+                                ));
+                        }
+                    }
+                    MenuState::Config(_) => match key_event.code {
+                        KeyCode::Up => view.app.config_widget.previous_field(),
+                        KeyCode::Down | KeyCode::Tab => view.app.config_widget.next_field(),
+                        KeyCode::Enter => {
+                            if let Some(message) = view.app.config_widget.submit() {
+                                view.core_interface
+                                    .send(UserInterfaceMessage::ConfigMessage(message));
+                            }
+                        }
+                        _ => view.app.config_widget.input(key_event),
+                    },
                 },
             }
         }
@@ -147,9 +240,12 @@ pub struct App {
     icon_widget: IconWidgetState,
     menu_widget: SideMenuWidgetState,
     game_widget: GameWidgetState,
+    statistics_widget: StatisticsWidgetState,
     config_widget: ConfigWidgetState,
     cursor_state: CursorState,
     menu_state: MenuState,
+    // shown in the footer until the program ends
+    notice: Option<String>,
     running: bool,
 }
 
@@ -161,9 +257,11 @@ impl App {
             icon_widget: IconWidgetState::default(),
             menu_widget,
             game_widget: GameWidgetState::default(),
+            statistics_widget: StatisticsWidgetState::default(),
             config_widget: ConfigWidgetState::default(),
             cursor_state: CursorState::default(),
             menu_state: MenuState::default(),
+            notice: None,
             running: true,
         }
     }
@@ -172,12 +270,16 @@ impl App {
         match (&self.cursor_state, &self.menu_state) {
             (CursorState::Menu(_), _) => self.menu_widget.unselect(),
             (CursorState::Main(_), MenuState::Game(_)) => self.game_widget.unselect(),
+            (CursorState::Main(_), MenuState::Statistics(_)) => self.statistics_widget.unselect(),
             (CursorState::Main(_), MenuState::Config(_)) => self.config_widget.unselect(),
         }
 
         match (&state, &self.menu_state) {
             (CursorState::Menu(_), _) => self.menu_widget.select(HIGHLIGHT_STYLE),
             (CursorState::Main(_), MenuState::Game(_)) => self.game_widget.select(HIGHLIGHT_STYLE),
+            (CursorState::Main(_), MenuState::Statistics(_)) => {
+                self.statistics_widget.select(HIGHLIGHT_STYLE)
+            }
             (CursorState::Main(_), MenuState::Config(_)) => {
                 self.config_widget.select(HIGHLIGHT_STYLE)
             }
@@ -198,7 +300,10 @@ impl Drop for RatatuiView {
 }
 
 impl RatatuiView {
-    pub fn create() -> (
+    // starts out in the game mode if there is one, otherwise in the menu
+    pub fn create(
+        mode: Option<GameMode>,
+    ) -> (
         UserInterface,
         Pin<Box<dyn Future<Output = anyhow::Result<()>>>>,
     ) {
@@ -209,6 +314,14 @@ impl RatatuiView {
             app,
             core_interface,
         };
+        if let Some(mode) = mode {
+            view.app.game_widget.set_mode(mode);
+            view.core_interface
+                .send(UserInterfaceMessage::MainMenuMessage(
+                    UIMainMenuMessage::Start(mode),
+                ));
+            view.app.transition_cursor(CursorState::Main(CursorMain {}));
+        }
         let handle = Box::pin(async move { view.run().await });
 
         (ui, handle)
@@ -218,11 +331,11 @@ impl RatatuiView {
         let mut terminal = Self::setup_terminal().expect("tui setup failed");
         let mut event = EventStream::new();
         let mut tick_rate = time::interval(Duration::from_millis(50));
-        let _suppress_stderr = Gag::stderr().unwrap();
 
         let original_hook = std::panic::take_hook();
         std::panic::set_hook(Box::new(move |panic| {
             Self::restore_terminal().ok();
+            error!(%panic, "Panicked");
             original_hook(panic);
         }));
 
@@ -296,7 +409,15 @@ impl RatatuiView {
             .gray()
             .padding(Padding::new(1, 0, 0, 0));
 
-        let quit_text = Paragraph::new(Text::raw("Press q to quit")).block(footer_block);
+        let mut footer = Line::raw("Press q to quit");
+        if let Some(notice) = &app.notice {
+            footer.push_span(Span::raw("  "));
+            footer.push_span(Span::styled(
+                notice.as_str(),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        let quit_text = Paragraph::new(footer).block(footer_block);
         f.render_stateful_widget(IconWidget {}, side_menu[0], &mut app.icon_widget);
         f.render_stateful_widget(MenuWidget {}, side_menu[1], &mut app.menu_widget);
         f.render_widget(quit_text, vertical_split[1]);
@@ -305,6 +426,11 @@ impl RatatuiView {
             MenuState::Game(_) => {
                 f.render_stateful_widget(GameWidget {}, horizontal_split[1], &mut app.game_widget)
             }
+            MenuState::Statistics(_) => f.render_stateful_widget(
+                StatisticsWidget {},
+                horizontal_split[1],
+                &mut app.statistics_widget,
+            ),
             MenuState::Config(_) => f.render_stateful_widget(
                 ConfigWidget {},
                 horizontal_split[1],
@@ -0,0 +1,19 @@
+use core::interfaces::ui::CoreConfigMessage;
+
+use crate::CoreMessageHandler;
+
+impl CoreMessageHandler for CoreConfigMessage {
+    fn handle(self, view: &mut crate::RatatuiView) {
+        match self {
+            CoreConfigMessage::SettingsResponse(settings) => {
+                view.app.config_widget.set_settings(settings);
+            }
+            CoreConfigMessage::SaveResponse(settings) => {
+                view.app.config_widget.saved(settings);
+            }
+            CoreConfigMessage::SaveRejected(reason) => {
+                view.app.config_widget.rejected(reason);
+            }
+        }
+    }
+}
@@ -1,18 +1,157 @@
+use core::{domain::profile::Settings, interfaces::ui::UIConfigMessage};
+use std::{fmt::Display, path::PathBuf, str::FromStr, time::Duration};
+
+use anyhow::{Result, anyhow};
+use crossterm::event::KeyEvent;
 use ratatui::{
     buffer::Buffer,
-    layout::{Constraint, Direction, Layout, Rect},
-    style::Style,
+    layout::{Constraint, Direction, Layout, Margin, Rect},
+    style::{Color, Modifier, Style},
     text::Text,
-    widgets::{Block, Borders, StatefulWidget, Widget},
+    widgets::{Block, Borders, Paragraph, StatefulWidget, Widget, Wrap},
 };
+use tui_textarea::TextArea;
+
+pub mod events;
 
 use super::Selection;
 
+const LABEL_WIDTH: u16 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    LowestNote,
+    HighestNote,
+    Pool,
+    OctaveSpan,
+    MinOffset,
+    Ascending,
+    AllowUnison,
+    ToneDuration,
+    Volume,
+    Sound,
+    Instrument,
+    ModeInstruments,
+    Velocity,
+    SoundFont,
+    ConcertA,
+    PitchDetection,
+    InputDevice,
+    OutputDevice,
+    Seed,
+}
+
+impl Field {
+    const ALL: [Field; 19] = [
+        Field::LowestNote,
+        Field::HighestNote,
+        Field::Pool,
+        Field::OctaveSpan,
+        Field::MinOffset,
+        Field::Ascending,
+        Field::AllowUnison,
+        Field::ToneDuration,
+        Field::Volume,
+        Field::Sound,
+        Field::Instrument,
+        Field::ModeInstruments,
+        Field::Velocity,
+        Field::SoundFont,
+        Field::ConcertA,
+        Field::PitchDetection,
+        Field::InputDevice,
+        Field::OutputDevice,
+        Field::Seed,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Field::LowestNote => "Lowest note",
+            Field::HighestNote => "Highest note",
+            Field::Pool => "Note pool",
+            Field::OctaveSpan => "Octave span",
+            Field::MinOffset => "Min offset",
+            Field::Ascending => "Ascending (%)",
+            Field::AllowUnison => "Allow unison",
+            Field::ToneDuration => "Tone duration (ms)",
+            Field::Volume => "Volume (%)",
+            Field::Sound => "Sound",
+            Field::Instrument => "Instrument",
+            Field::ModeInstruments => "Mode instruments",
+            Field::Velocity => "Velocity",
+            Field::SoundFont => "SoundFont",
+            Field::ConcertA => "Concert A (Hz)",
+            Field::PitchDetection => "Pitch detection",
+            Field::InputDevice => "Input device",
+            Field::OutputDevice => "Output device",
+            Field::Seed => "Seed",
+        }
+    }
+
+    fn placeholder(&self) -> &'static str {
+        match self {
+            Field::Pool => "naturals, sharps, flats, all, Eb major or C, E, G",
+            Field::MinOffset => "semitones",
+            Field::AllowUnison => "true or false",
+            Field::PitchDetection => "yin or spectrum",
+            Field::InputDevice | Field::OutputDevice => "system default",
+            Field::Sound => "soundfont, sine, triangle, saw, organ or clarinet",
+            Field::Instrument => "bank:preset",
+            Field::ModeInstruments => "e.g. chord=0:0, random=0:32",
+            Field::SoundFont => "built-in bass",
+            Field::Seed => "random",
+            _ => "",
+        }
+    }
+
+    fn value(&self, settings: &Settings) -> String {
+        match self {
+            Field::LowestNote => settings.lowest_note.to_string(),
+            Field::HighestNote => settings.highest_note.to_string(),
+            Field::Pool => settings.pool.to_string(),
+            Field::OctaveSpan => settings.octave_span.to_string(),
+            Field::MinOffset => settings.min_offset.to_string(),
+            Field::Ascending => settings.ascending.to_string(),
+            Field::AllowUnison => settings.allow_unison.to_string(),
+            Field::ToneDuration => settings.tone_duration.as_millis().to_string(),
+            Field::Volume => settings.volume.to_string(),
+            Field::Sound => settings.sound.to_string(),
+            Field::Instrument => settings.instrument.to_string(),
+            Field::ModeInstruments => settings.mode_instruments.to_string(),
+            Field::Velocity => settings.velocity.to_string(),
+            Field::SoundFont => settings
+                .soundfont
+                .as_ref()
+                .map(|path| path.display().to_string())
+                .unwrap_or_default(),
+            Field::ConcertA => settings.concert_a.to_string(),
+            Field::PitchDetection => settings.pitch_detection.to_string(),
+            Field::InputDevice => settings.input_device.clone().unwrap_or_default(),
+            Field::OutputDevice => settings.output_device.clone().unwrap_or_default(),
+            Field::Seed => settings
+                .seed
+                .map(|seed| seed.to_string())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Status {
+    Saved,
+    Error(String),
+}
+
 pub struct ConfigWidget {}
 
 #[derive(Default, Clone)]
 pub struct ConfigWidgetState {
     style: Style,
+    // the settings in place in the core, the form starts out with them
+    settings: Option<Settings>,
+    fields: Vec<TextArea<'static>>,
+    selected: usize,
+    status: Option<Status>,
 }
 
 impl Selection for ConfigWidgetState {
@@ -21,6 +160,129 @@ impl Selection for ConfigWidgetState {
     }
 }
 
+impl ConfigWidgetState {
+    pub fn set_settings(&mut self, settings: Settings) {
+        self.fields = Field::ALL
+            .iter()
+            .map(|field| {
+                let mut text_area = TextArea::new(vec![field.value(&settings)]);
+                text_area.set_placeholder_text(field.placeholder());
+                text_area.set_cursor_line_style(Style::default());
+                text_area.move_cursor(tui_textarea::CursorMove::End);
+                text_area
+            })
+            .collect();
+        self.settings = Some(settings);
+    }
+
+    pub fn saved(&mut self, settings: Settings) {
+        self.set_settings(settings);
+        self.status = Some(Status::Saved);
+    }
+
+    pub fn rejected(&mut self, reason: String) {
+        self.status = Some(Status::Error(reason));
+    }
+
+    pub fn next_field(&mut self) {
+        if !self.fields.is_empty() {
+            self.selected = (self.selected + 1) % self.fields.len();
+        }
+    }
+
+    pub fn previous_field(&mut self) {
+        if !self.fields.is_empty() {
+            self.selected = (self.selected + self.fields.len() - 1) % self.fields.len();
+        }
+    }
+
+    pub fn input(&mut self, key_event: KeyEvent) {
+        if let Some(text_area) = self.fields.get_mut(self.selected) {
+            text_area.input(key_event);
+        }
+    }
+
+    // the settings from the form, a form that can't be parsed is kept for
+    // correction, the core checks whether the values make sense
+    pub fn submit(&mut self) -> Option<UIConfigMessage> {
+        match self.parse() {
+            Ok(settings) => {
+                self.status = None;
+                Some(UIConfigMessage::SaveRequest(settings))
+            }
+            Err(err) => {
+                self.status = Some(Status::Error(format!("{err:#}")));
+                None
+            }
+        }
+    }
+
+    fn parse(&self) -> Result<Settings> {
+        let Some(settings) = &self.settings else {
+            return Err(anyhow!("The settings have not been loaded yet"));
+        };
+        Ok(Settings {
+            lowest_note: self.parse_field(Field::LowestNote)?,
+            highest_note: self.parse_field(Field::HighestNote)?,
+            pool: self.parse_field(Field::Pool)?,
+            octave_span: self.parse_field(Field::OctaveSpan)?,
+            min_offset: self.parse_field(Field::MinOffset)?,
+            ascending: self.parse_field(Field::Ascending)?,
+            allow_unison: self.parse_field(Field::AllowUnison)?,
+            tone_duration: Duration::from_millis(self.parse_field(Field::ToneDuration)?),
+            volume: self.parse_field(Field::Volume)?,
+            sound: self.parse_field(Field::Sound)?,
+            instrument: self.parse_field(Field::Instrument)?,
+            mode_instruments: self.parse_field(Field::ModeInstruments)?,
+            velocity: self.parse_field(Field::Velocity)?,
+            soundfont: self.optional_field(Field::SoundFont).map(PathBuf::from),
+            concert_a: self.parse_field(Field::ConcertA)?,
+            pitch_detection: self.parse_field(Field::PitchDetection)?,
+            input_device: self.optional_field(Field::InputDevice),
+            output_device: self.optional_field(Field::OutputDevice),
+            seed: self.seed_field()?,
+            ..settings.clone()
+        })
+    }
+
+    fn text(&self, field: Field) -> &str {
+        let index = Field::ALL
+            .iter()
+            .position(|known| *known == field)
+            .expect("every field is in the form");
+        self.fields
+            .get(index)
+            .and_then(|text_area| text_area.lines().first())
+            .map(|line| line.trim())
+            .unwrap_or_default()
+    }
+
+    fn parse_field<T>(&self, field: Field) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.text(field)
+            .parse()
+            .map_err(|err| anyhow!("{}: {err}", field.label()))
+    }
+
+    // blank for the default
+    fn optional_field(&self, field: Field) -> Option<String> {
+        Some(self.text(field))
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+    }
+
+    // a blank seed lets every session draw its own
+    fn seed_field(&self) -> Result<Option<u64>> {
+        if self.text(Field::Seed).is_empty() {
+            return Ok(None);
+        }
+        self.parse_field(Field::Seed).map(Some)
+    }
+}
+
 impl StatefulWidget for ConfigWidget {
     type State = ConfigWidgetState;
 
@@ -29,29 +291,61 @@ impl StatefulWidget for ConfigWidget {
             .title("Config")
             .borders(Borders::ALL)
             .style(state.style);
+        outer_block.render(area, buf);
+        let inner = area.inner(Margin::new(2, 1));
 
-        let vertical_split = Layout::default()
+        if state.fields.is_empty() {
+            Text::raw("Loading settings...").render(inner, buf);
+            return;
+        }
+
+        let mut constraints = vec![Constraint::Length(1); state.fields.len()];
+        constraints.extend([
+            Constraint::Length(1),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ]);
+        let rows = Layout::default()
             .direction(Direction::Vertical)
-            .margin(0)
-            .constraints([
-                Constraint::Percentage(50),
-                Constraint::Length(1),
-                Constraint::Percentage(49),
-            ])
-            .split(area);
-
-        let horizontal_split = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Percentage(50),
-                Constraint::Length(14),
-                Constraint::Percentage(49),
-            ])
-            .split(vertical_split[1]);
-
-        let placeholder = Text::raw("Config selected");
+            .constraints(constraints)
+            .split(inner);
 
-        outer_block.render(area, buf);
-        placeholder.render(horizontal_split[1], buf);
+        for (index, (field, text_area)) in Field::ALL.iter().zip(&mut state.fields).enumerate() {
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Length(LABEL_WIDTH), Constraint::Min(1)])
+                .split(rows[index]);
+
+            // only the selected field shows its cursor
+            let (label_style, cursor_style) = if index == state.selected {
+                (
+                    state.style,
+                    Style::default().add_modifier(Modifier::REVERSED),
+                )
+            } else {
+                (Style::default(), Style::default())
+            };
+            text_area.set_cursor_style(cursor_style);
+            Text::styled(field.label(), label_style).render(columns[0], buf);
+            (&*text_area).render(columns[1], buf);
+        }
+
+        let status = match &state.status {
+            Some(Status::Saved) => Text::styled("Saved", Style::default().fg(Color::Green)),
+            Some(Status::Error(reason)) => {
+                Text::styled(reason.as_str(), Style::default().fg(Color::Red))
+            }
+            None => Text::default(),
+        };
+        let status_row = state.fields.len() + 1;
+        Paragraph::new(status)
+            .wrap(Wrap { trim: true })
+            .render(rows[status_row], buf);
+
+        Text::styled(
+            "Up/Down: select field, Enter: save, Esc: back",
+            Style::default().fg(Color::DarkGray),
+        )
+        .render(rows[status_row + 1], buf);
     }
 }
@@ -11,6 +11,40 @@ impl CoreMessageHandler for CoreGameMessage {
             CoreGameMessage::GuessResponse(note_guess) => {
                 view.app.game_widget.set_note_guess(Some(note_guess));
             }
+            CoreGameMessage::IntervalResponse(next_interval) => {
+                view.app.game_widget.set_interval(Some(next_interval));
+            }
+            CoreGameMessage::IntervalGuessResponse(interval_guess) => {
+                view.app
+                    .game_widget
+                    .set_interval_guess(Some(interval_guess));
+            }
+            CoreGameMessage::ChordResponse(next_chord) => {
+                view.app.game_widget.set_chord(Some(next_chord));
+            }
+            CoreGameMessage::ChordGuessResponse(chord_guess) => {
+                view.app.game_widget.set_chord_guess(Some(chord_guess));
+            }
+            CoreGameMessage::ScaleResponse(next_scale) => {
+                view.app.game_widget.set_scale(Some(next_scale));
+            }
+            CoreGameMessage::ScaleGuessResponse(scale_guess) => {
+                view.app.game_widget.set_scale_guess(Some(scale_guess));
+            }
+            CoreGameMessage::DictationResponse(next_melody) => {
+                view.app.game_widget.set_melody(Some(next_melody));
+            }
+            CoreGameMessage::DictationNoteHeard(note) => {
+                view.app.game_widget.add_heard_note(note);
+            }
+            CoreGameMessage::DictationGuessResponse(dictation_guess) => {
+                view.app
+                    .game_widget
+                    .set_dictation_guess(Some(dictation_guess));
+            }
+            CoreGameMessage::ExerciseResponse(exercise) => {
+                view.app.game_widget.set_exercise(exercise);
+            }
         }
     }
 }
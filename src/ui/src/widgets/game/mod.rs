@@ -1,24 +1,83 @@
-use core::{domain::random::NoteTuple, interfaces::ui::NoteGuess};
+use core::{
+    domain::{
+        chord::ChordQuality,
+        dictation::{AlignedNote, parse_answer},
+        exercise::Exercise,
+        interval::Interval,
+        notes::Note,
+        random::NoteTuple,
+        scale::ScaleKind,
+    },
+    interfaces::ui::{
+        ChordGuess, DictationGuess, GameMode, IntervalGuess, NextChord, NextInterval, NextMelody,
+        NextScale, NoteGuess, ScaleGuess, UIGameMessage,
+    },
+};
+use std::fmt;
 
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Direction, Layout, Rect},
     style::Style,
     text::Text,
-    widgets::{Block, Borders, StatefulWidget, Widget},
+    widgets::{Block, Borders, List, ListState, StatefulWidget, Widget},
 };
 
-use super::Selection;
+use super::{HIGHLIGHT_STYLE, Selection};
 
 pub mod events;
 
 pub struct GameWidget {}
 
+// an answer the player can pick from the list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Choice {
+    Interval(Interval),
+    Chord(ChordQuality),
+    Scale(ScaleKind),
+}
+
+impl fmt::Display for Choice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Choice::Interval(interval) => {
+                write!(f, "{:<6}{}", interval.to_string(), interval.name())
+            }
+            Choice::Chord(quality) => write!(f, "{:<6}{quality}", quality.symbol()),
+            Choice::Scale(kind) => write!(f, "{kind}"),
+        }
+    }
+}
+
+impl Choice {
+    pub fn message(&self) -> UIGameMessage {
+        match self {
+            Choice::Interval(interval) => UIGameMessage::IntervalGuess(*interval),
+            Choice::Chord(quality) => UIGameMessage::ChordGuess(*quality),
+            Choice::Scale(kind) => UIGameMessage::ScaleGuess(*kind),
+        }
+    }
+}
+
 #[derive(Default, Clone)]
 pub struct GameWidgetState {
     style: Style,
     current_note_tuple: Option<NoteTuple>,
     current_note_guess: Option<NoteGuess>,
+    mode: GameMode,
+    current_interval: Option<NextInterval>,
+    current_interval_guess: Option<IntervalGuess>,
+    current_chord_guess: Option<ChordGuess>,
+    current_scale_guess: Option<ScaleGuess>,
+    choices: Vec<Choice>,
+    choice_state: ListState,
+    current_melody: Option<NextMelody>,
+    current_dictation_guess: Option<DictationGuess>,
+    dictation_input: String,
+    dictation_error: Option<String>,
+    heard_notes: Vec<Note>,
+    // the random mode plays a fixed exercise instead of adapting
+    exercise: Option<Exercise>,
 }
 
 impl Selection for GameWidgetState {
@@ -36,9 +95,160 @@ impl GameWidgetState {
         self.current_note_guess = note_guess
     }
 
+    pub fn set_exercise(&mut self, exercise: Option<Exercise>) {
+        self.exercise = exercise
+    }
+
+    pub fn set_interval(&mut self, next_interval: Option<NextInterval>) {
+        self.current_interval_guess = None;
+        let choices = next_interval
+            .iter()
+            .flat_map(|interval| interval.choices.iter().copied().map(Choice::Interval))
+            .collect();
+        self.set_choices(choices);
+        self.current_interval = next_interval
+    }
+
+    pub fn set_interval_guess(&mut self, interval_guess: Option<IntervalGuess>) {
+        self.current_interval_guess = interval_guess
+    }
+
+    pub fn set_chord(&mut self, next_chord: Option<NextChord>) {
+        self.current_chord_guess = None;
+        let choices = next_chord
+            .iter()
+            .flat_map(|chord| chord.choices.iter().copied().map(Choice::Chord))
+            .collect();
+        self.set_choices(choices);
+    }
+
+    pub fn set_chord_guess(&mut self, chord_guess: Option<ChordGuess>) {
+        self.current_chord_guess = chord_guess
+    }
+
+    pub fn set_scale(&mut self, next_scale: Option<NextScale>) {
+        self.current_scale_guess = None;
+        let choices = next_scale
+            .iter()
+            .flat_map(|scale| scale.choices.iter().copied().map(Choice::Scale))
+            .collect();
+        self.set_choices(choices);
+    }
+
+    pub fn set_scale_guess(&mut self, scale_guess: Option<ScaleGuess>) {
+        self.current_scale_guess = scale_guess
+    }
+
+    pub fn set_melody(&mut self, next_melody: Option<NextMelody>) {
+        self.current_dictation_guess = None;
+        self.dictation_error = None;
+        self.heard_notes.clear();
+        self.current_melody = next_melody
+    }
+
+    pub fn add_heard_note(&mut self, note: Note) {
+        self.heard_notes.push(note)
+    }
+
+    pub fn set_dictation_guess(&mut self, dictation_guess: Option<DictationGuess>) {
+        self.heard_notes.clear();
+        self.current_dictation_guess = dictation_guess
+    }
+
+    pub fn push_input(&mut self, c: char) {
+        self.dictation_input.push(c)
+    }
+
+    pub fn pop_input(&mut self) {
+        self.dictation_input.pop();
+    }
+
+    // the message for the picked answer or the typed melody, a typed melody
+    // that can't be parsed is kept for correction
+    pub fn submit(&mut self) -> Option<UIGameMessage> {
+        if self.mode != GameMode::Dictation {
+            return self.selected_choice().map(|choice| choice.message());
+        }
+        match parse_answer(&self.dictation_input) {
+            Ok(answer) if answer.is_empty() => None,
+            Ok(answer) => {
+                self.dictation_input.clear();
+                self.dictation_error = None;
+                Some(UIGameMessage::DictationAnswer(answer))
+            }
+            Err(err) => {
+                self.dictation_error = Some(format!("{err:#}"));
+                None
+            }
+        }
+    }
+
+    // keeps the cursor where it was so the same answer can be given quickly
+    fn set_choices(&mut self, choices: Vec<Choice>) {
+        match self.choice_state.selected() {
+            _ if choices.is_empty() => self.choice_state.select(None),
+            Some(index) if index < choices.len() => {}
+            _ => self.choice_state.select(Some(0)),
+        }
+        self.choices = choices;
+    }
+
+    pub fn mode(&self) -> GameMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: GameMode) {
+        self.reset();
+        self.mode = mode;
+    }
+
+    pub fn next_mode(&mut self) {
+        self.reset();
+        self.mode = match self.mode {
+            GameMode::Random => GameMode::Interval,
+            GameMode::Interval => GameMode::Chord,
+            GameMode::Chord => GameMode::Scale,
+            GameMode::Scale => GameMode::Dictation,
+            GameMode::Dictation => GameMode::Review,
+            GameMode::Review => GameMode::Random,
+        }
+    }
+
+    pub fn next_choice(&mut self) {
+        let len = self.choices.len();
+        if let Some(index) = self.choice_state.selected() {
+            self.choice_state.select(Some((index + 1) % len.max(1)));
+        }
+    }
+
+    pub fn previous_choice(&mut self) {
+        let len = self.choices.len();
+        if let Some(index) = self.choice_state.selected() {
+            self.choice_state
+                .select(Some(index.checked_sub(1).unwrap_or(len.saturating_sub(1))));
+        }
+    }
+
+    // the answer under the cursor, as long as there is something to be guessed
+    fn selected_choice(&self) -> Option<Choice> {
+        let index = self.choice_state.selected()?;
+        self.choices.get(index).copied()
+    }
+
     pub fn reset(&mut self) {
         self.current_note_tuple = None;
-        self.current_note_guess = None
+        self.current_note_guess = None;
+        self.current_interval = None;
+        self.current_interval_guess = None;
+        self.current_chord_guess = None;
+        self.current_scale_guess = None;
+        self.current_melody = None;
+        self.current_dictation_guess = None;
+        self.dictation_input.clear();
+        self.dictation_error = None;
+        self.heard_notes.clear();
+        self.choices.clear();
+        self.choice_state.select(None)
     }
 }
 
@@ -47,47 +257,230 @@ impl StatefulWidget for GameWidget {
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
         let outer_block = Block::default()
-            .title("Game")
+            .title(format!("Game - {} (m to switch)", state.mode))
             .borders(Borders::ALL)
             .style(state.style);
+        outer_block.render(area, buf);
 
-        let description = "Press n to play the next note!";
-        let description_text = Text::raw(description);
-        let mut note = "Current note: ".to_string();
-        if let Some(note_tuple) = &state.current_note_tuple {
-            note = format!("{}{}", note, note_tuple.reference());
+        match state.mode {
+            GameMode::Random | GameMode::Review => render_random_mode(area, buf, state),
+            GameMode::Interval => render_interval_mode(area, buf, state),
+            GameMode::Chord => render_chord_mode(area, buf, state),
+            GameMode::Scale => render_scale_mode(area, buf, state),
+            GameMode::Dictation => render_dictation_mode(area, buf, state),
         }
-        let note_text = Text::raw(note.clone());
-
-        let vertical_split = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Length(1),
-                Constraint::Length(1),
-                Constraint::Length(1),
-            ])
-            .split(area);
-
-        let horizontal_split = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Length(1),
-                Constraint::Length(description.len() as u16),
-                Constraint::Min(0),
-            ])
-            .split(vertical_split[1]);
-
-        let horizontal_split2 = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Length(1),
-                Constraint::Length(note.len() as u16),
-                Constraint::Min(0),
-            ])
-            .split(vertical_split[2]);
+    }
+}
 
-        outer_block.render(area, buf);
-        description_text.render(horizontal_split[1], buf);
-        note_text.render(horizontal_split2[1], buf);
+fn render_random_mode(area: Rect, buf: &mut Buffer, state: &GameWidgetState) {
+    let description = "Press n to play the next note!";
+    let description_text = Text::raw(description);
+    let mut note = "Current note: ".to_string();
+    if let Some(note_tuple) = &state.current_note_tuple {
+        note = format!("{}{}", note, note_tuple.reference());
+    }
+    let note_text = Text::raw(note.clone());
+    let mut guess = "Played note: ".to_string();
+    if let Some(note_guess) = &state.current_note_guess {
+        guess = format!(
+            "{}{} ({})",
+            guess,
+            note_guess.note_played,
+            verdict(note_guess.correct)
+        );
+    }
+    let guess_text = Text::raw(guess.clone());
+    let share_code = state
+        .exercise
+        .as_ref()
+        .map(|exercise| format!("Share code: {exercise}"))
+        .unwrap_or_default();
+    let share_code_text = Text::raw(share_code.clone());
+
+    let vertical_split = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .split(area);
+
+    let horizontal_split = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Length(description.len() as u16),
+            Constraint::Min(0),
+        ])
+        .split(vertical_split[1]);
+
+    let horizontal_split2 = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Length(note.len() as u16),
+            Constraint::Min(0),
+        ])
+        .split(vertical_split[2]);
+
+    let horizontal_split3 = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Length(guess.len() as u16),
+            Constraint::Min(0),
+        ])
+        .split(vertical_split[3]);
+
+    let horizontal_split4 = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Length(share_code.len() as u16),
+            Constraint::Min(0),
+        ])
+        .split(vertical_split[4]);
+
+    description_text.render(horizontal_split[1], buf);
+    note_text.render(horizontal_split2[1], buf);
+    guess_text.render(horizontal_split3[1], buf);
+    share_code_text.render(horizontal_split4[1], buf);
+}
+
+fn render_interval_mode(area: Rect, buf: &mut Buffer, state: &mut GameWidgetState) {
+    let description = "Press n to play the next interval, pick the answer with enter!";
+    let mut playback = "Playback: ".to_string();
+    if let Some(next_interval) = &state.current_interval {
+        playback = format!("{}{}", playback, next_interval.playback);
+    }
+    let mut guess = "Your answer: ".to_string();
+    if let Some(interval_guess) = &state.current_interval_guess {
+        guess = format!(
+            "{}{} ({}, it was a {})",
+            guess,
+            interval_guess.guessed.name(),
+            verdict(interval_guess.correct),
+            interval_guess.interval.name()
+        );
     }
+
+    render_choice_mode(
+        area,
+        buf,
+        state,
+        &[description.to_string(), playback, guess],
+    );
+}
+
+fn render_chord_mode(area: Rect, buf: &mut Buffer, state: &mut GameWidgetState) {
+    let description = "Press n to play the next chord, pick its quality with enter!";
+    let mut guess = "Your answer: ".to_string();
+    if let Some(chord_guess) = &state.current_chord_guess {
+        guess = format!(
+            "{}{} ({}, it was {}: {})",
+            guess,
+            chord_guess.guessed,
+            verdict(chord_guess.correct),
+            chord_guess.chord,
+            chord_guess.chord.name()
+        );
+    }
+
+    render_choice_mode(area, buf, state, &[description.to_string(), guess]);
+}
+
+fn render_scale_mode(area: Rect, buf: &mut Buffer, state: &mut GameWidgetState) {
+    let description = "Press n to play the next scale, pick its name with enter!";
+    let mut guess = "Your answer: ".to_string();
+    if let Some(scale_guess) = &state.current_scale_guess {
+        guess = format!(
+            "{}{} ({}, it was {})",
+            guess,
+            scale_guess.guessed,
+            verdict(scale_guess.correct),
+            scale_guess.run.scale()
+        );
+    }
+
+    render_choice_mode(area, buf, state, &[description.to_string(), guess]);
+}
+
+fn render_dictation_mode(area: Rect, buf: &mut Buffer, state: &mut GameWidgetState) {
+    let mut lines = vec![
+        "Press n to play the next melody, type it (like C4 D4:2) or play it, submit with enter!"
+            .to_string(),
+    ];
+    if let Some(melody) = &state.current_melody {
+        lines.push(format!("Key: {}, {} notes", melody.key, melody.length));
+    }
+    lines.push(format!("Your answer: {}_", state.dictation_input));
+    if let Some(err) = &state.dictation_error {
+        lines.push(err.clone());
+    } else if !state.heard_notes.is_empty() {
+        let heard: Vec<String> = state.heard_notes.iter().map(Note::to_string).collect();
+        lines.push(format!("Heard: {}", heard.join(" ")));
+    }
+
+    if let Some(dictation_guess) = &state.current_dictation_guess {
+        let alignment = &dictation_guess.alignment;
+        lines.push(format!(
+            "{} of {} notes right ({}, distance {})",
+            alignment.correct_count(),
+            dictation_guess.melody.len(),
+            verdict(dictation_guess.correct),
+            alignment.distance()
+        ));
+        lines.extend(alignment.notes().iter().map(|aligned| match aligned {
+            AlignedNote::Correct {
+                target,
+                rhythm_correct: Some(false),
+                answer,
+            } => format!(
+                "  {target}  right pitch, {} instead of {} beats",
+                answer.beats.unwrap_or_default(),
+                target.beats
+            ),
+            AlignedNote::Correct { target, .. } => format!("  {target}  correct"),
+            AlignedNote::WrongPitch { target, answer } => {
+                format!("  {target}  wrong, you gave {answer}")
+            }
+            AlignedNote::Missing { target } => format!("  {target}  missing"),
+            AlignedNote::Extra { answer } => format!("  -      extra {answer}"),
+        }));
+    }
+
+    render_choice_mode(area, buf, state, &lines);
+}
+
+fn verdict(correct: bool) -> &'static str {
+    if correct { "correct" } else { "wrong" }
+}
+
+// one line per text above the list of answers
+fn render_choice_mode(area: Rect, buf: &mut Buffer, state: &mut GameWidgetState, lines: &[String]) {
+    let constraints = lines
+        .iter()
+        .map(|_| Constraint::Length(1))
+        .chain(std::iter::once(Constraint::Min(0)));
+    let vertical_split = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints(constraints)
+        .split(area);
+
+    for (line, area) in lines.iter().zip(vertical_split.iter()) {
+        Text::raw(line.as_str()).render(*area, buf);
+    }
+
+    let choices: Vec<String> = state.choices.iter().map(Choice::to_string).collect();
+    let choice_list = List::new(choices).highlight_style(HIGHLIGHT_STYLE);
+    StatefulWidget::render(
+        choice_list,
+        vertical_split[lines.len()],
+        buf,
+        &mut state.choice_state,
+    );
 }
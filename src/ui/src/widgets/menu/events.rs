@@ -1,10 +1,13 @@
-use core::interfaces::ui::{CoreMainMenuMessage, UIMainMenuMessage, UserInterfaceMessage};
+use core::interfaces::ui::{
+    CoreMainMenuMessage, UIConfigMessage, UIMainMenuMessage, UIStatisticsMessage,
+    UserInterfaceMessage,
+};
 
 use crossterm::event::{Event, KeyCode};
 
 use crate::{
     CoreMessageHandler, CursorMain, CursorMenu, CursorState, EventHandler, MenuConfig, MenuGame,
-    MenuState,
+    MenuState, MenuStatistics,
 };
 
 impl CoreMessageHandler for CoreMainMenuMessage {
@@ -36,6 +39,7 @@ impl EventHandler for MenuState {
     fn handle(self, event: Event, view: &mut crate::RatatuiView) {
         match self {
             MenuState::Game(game) => game.handle(event, view),
+            MenuState::Statistics(statistics) => statistics.handle(event, view),
             MenuState::Config(config) => config.handle(event, view),
         }
     }
@@ -48,16 +52,16 @@ impl EventHandler for MenuGame {
                 KeyCode::Right | KeyCode::Enter => {
                     view.core_interface
                         .send(UserInterfaceMessage::MainMenuMessage(
-                            UIMainMenuMessage::Start,
+                            UIMainMenuMessage::Start(view.app.game_widget.mode()),
                         ));
                     view.app.transition_cursor(CursorState::Main(CursorMain {}));
                 }
                 KeyCode::Up => {
-                    view.app.transition_menu(MenuState::Config(MenuConfig {}));
+                    show_config(view);
                     view.app.menu_widget.previous();
                 }
                 KeyCode::Down => {
-                    view.app.transition_menu(MenuState::Config(MenuConfig {}));
+                    show_statistics(view);
                     view.app.menu_widget.next();
                 }
                 _ => {}
@@ -66,6 +70,46 @@ impl EventHandler for MenuGame {
     }
 }
 
+impl EventHandler for MenuStatistics {
+    fn handle(self, event: Event, view: &mut crate::RatatuiView) {
+        if let Event::Key(key_event) = event {
+            match key_event.code {
+                KeyCode::Right | KeyCode::Enter => {
+                    view.app.transition_cursor(CursorState::Main(CursorMain {}));
+                }
+                KeyCode::Up => {
+                    view.app.transition_menu(MenuState::Game(MenuGame {}));
+                    view.app.menu_widget.previous();
+                }
+                KeyCode::Down => {
+                    show_config(view);
+                    view.app.menu_widget.next();
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+// the report is fetched every time the page is opened
+fn show_statistics(view: &mut crate::RatatuiView) {
+    view.app
+        .transition_menu(MenuState::Statistics(MenuStatistics {}));
+    view.core_interface
+        .send(UserInterfaceMessage::StatisticsMessage(
+            UIStatisticsMessage::ReportRequest,
+        ));
+}
+
+// the form is filled with the settings in place every time the page is opened
+fn show_config(view: &mut crate::RatatuiView) {
+    view.app.transition_menu(MenuState::Config(MenuConfig {}));
+    view.core_interface
+        .send(UserInterfaceMessage::ConfigMessage(
+            UIConfigMessage::SettingsRequest,
+        ));
+}
+
 impl EventHandler for MenuConfig {
     fn handle(self, event: Event, view: &mut crate::RatatuiView) {
         if let Event::Key(key_event) = event {
@@ -75,7 +119,7 @@ impl EventHandler for MenuConfig {
                         .transition_cursor(crate::CursorState::Main(CursorMain {}));
                 }
                 KeyCode::Up => {
-                    view.app.transition_menu(MenuState::Game(MenuGame {}));
+                    show_statistics(view);
                     view.app.menu_widget.previous();
                 }
                 KeyCode::Down => {
@@ -27,7 +27,11 @@ impl Selection for SideMenuWidgetState {
 impl Default for SideMenuWidgetState {
     fn default() -> Self {
         Self {
-            menu_items: vec!["Game".to_string(), "Config".to_string()],
+            menu_items: vec![
+                "Game".to_string(),
+                "Statistics".to_string(),
+                "Config".to_string(),
+            ],
             list_state: ListState::default().with_selected(Some(0)),
             style: DEFAULT_STYLE,
         }
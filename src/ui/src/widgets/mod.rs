@@ -4,6 +4,7 @@ pub mod config;
 pub mod game;
 pub mod icon;
 pub mod menu;
+pub mod statistics;
 
 pub const DEFAULT_STYLE: Style = Style::new().fg(Color::Gray);
 pub const HIGHLIGHT_STYLE: Style = Style::new().fg(Color::Cyan);
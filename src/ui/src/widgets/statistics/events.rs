@@ -0,0 +1,13 @@
+use core::interfaces::ui::CoreStatisticsMessage;
+
+use crate::CoreMessageHandler;
+
+impl CoreMessageHandler for CoreStatisticsMessage {
+    fn handle(self, view: &mut crate::RatatuiView) {
+        match self {
+            CoreStatisticsMessage::ReportResponse(report) => {
+                view.app.statistics_widget.set_report(Some(report));
+            }
+        }
+    }
+}
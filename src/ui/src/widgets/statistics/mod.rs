@@ -0,0 +1,203 @@
+use core::domain::statistics::{Accuracy, StatisticsReport};
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Direction, Layout, Margin, Rect},
+    style::{Color, Style, Stylize},
+    symbols,
+    text::{Line, Text},
+    widgets::{
+        Axis, Bar, BarChart, BarGroup, Block, Borders, Chart, Dataset, GraphType, Sparkline,
+        StatefulWidget, Widget,
+    },
+};
+
+pub mod events;
+
+use super::Selection;
+
+pub struct StatisticsWidget {}
+
+#[derive(Default, Clone)]
+pub struct StatisticsWidgetState {
+    style: Style,
+    report: Option<StatisticsReport>,
+}
+
+impl Selection for StatisticsWidgetState {
+    fn select(&mut self, style: Style) {
+        self.style = style;
+    }
+}
+
+impl StatisticsWidgetState {
+    pub fn set_report(&mut self, report: Option<StatisticsReport>) {
+        self.report = report;
+    }
+}
+
+impl StatefulWidget for StatisticsWidget {
+    type State = StatisticsWidgetState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let outer_block = Block::default()
+            .title("Statistics (r to refresh)")
+            .borders(Borders::ALL)
+            .style(state.style);
+        outer_block.render(area, buf);
+
+        let inner = area.inner(Margin::new(1, 1));
+        let Some(report) = &state.report else {
+            Text::raw("Loading statistics...").render(inner, buf);
+            return;
+        };
+
+        let vertical_split = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Length(5),
+                Constraint::Min(8),
+                Constraint::Length(8),
+            ])
+            .split(inner);
+
+        let middle_split = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(65), Constraint::Percentage(35)])
+            .split(vertical_split[2]);
+
+        render_summary(vertical_split[0], buf, report);
+        render_daily(vertical_split[1], buf, report);
+        render_weekly(middle_split[0], buf, report);
+        render_registers(middle_split[1], buf, report);
+        render_intervals(vertical_split[3], buf, report);
+    }
+}
+
+fn percent(accuracy: &Accuracy) -> Option<u64> {
+    accuracy.ratio().map(|ratio| (ratio * 100.0).round() as u64)
+}
+
+fn render_summary(area: Rect, buf: &mut Buffer, report: &StatisticsReport) {
+    let overall = match (
+        percent(&report.overall),
+        report.overall.mean_response_time(),
+    ) {
+        (Some(percent), Some(mean)) => format!(
+            "Random notes: {} answers, {percent}% right, {:.1} s on average",
+            report.overall.attempts,
+            mean.as_secs_f64()
+        ),
+        _ => "Random notes: no answers yet".to_string(),
+    };
+    let streaks = format!(
+        "Streak: {} right in a row (best {}), practiced {} days in a row",
+        report.current_streak, report.best_streak, report.practice_days
+    );
+    Text::from(vec![Line::raw(overall), Line::raw(streaks)]).render(area, buf);
+}
+
+// days without answers are left blank instead of counting as zero
+fn render_daily(area: Rect, buf: &mut Buffer, report: &StatisticsReport) {
+    let data: Vec<Option<u64>> = report
+        .daily
+        .iter()
+        .map(|(_, accuracy)| percent(accuracy))
+        .collect();
+    let title = match report.daily.first() {
+        Some((day, _)) => format!("Daily accuracy since {day}"),
+        None => "Daily accuracy".to_string(),
+    };
+    Sparkline::default()
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .data(data)
+        .max(100)
+        .absent_value_symbol(symbols::shade::LIGHT)
+        .style(Style::default().fg(Color::Cyan))
+        .render(area, buf);
+}
+
+fn render_weekly(area: Rect, buf: &mut Buffer, report: &StatisticsReport) {
+    let block = Block::default()
+        .title("Weekly accuracy")
+        .borders(Borders::ALL);
+    let points: Vec<(f64, f64)> = report
+        .weekly
+        .iter()
+        .enumerate()
+        .filter_map(|(index, (_, accuracy))| {
+            accuracy.ratio().map(|ratio| (index as f64, ratio * 100.0))
+        })
+        .collect();
+    let (Some((first, _)), Some((last, _))) = (report.weekly.first(), report.weekly.last()) else {
+        Text::raw("No answers yet").render(block.inner(area), buf);
+        block.render(area, buf);
+        return;
+    };
+
+    let dataset = Dataset::default()
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(Color::Cyan))
+        .data(&points);
+    Chart::new(vec![dataset])
+        .block(block)
+        .x_axis(
+            Axis::default()
+                .bounds([0.0, (points.len().max(2) - 1) as f64])
+                .labels([first.to_string(), last.to_string()])
+                .gray(),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, 100.0])
+                .labels(["0%", "50%", "100%"])
+                .gray(),
+        )
+        .render(area, buf);
+}
+
+fn bar(label: String, accuracy: &Accuracy) -> Bar<'static> {
+    let value = percent(accuracy).unwrap_or_default();
+    Bar::default()
+        .label(Line::raw(label))
+        .value(value)
+        .text_value(format!("{value}%"))
+}
+
+fn render_registers(area: Rect, buf: &mut Buffer, report: &StatisticsReport) {
+    let bars: Vec<Bar> = report
+        .registers
+        .iter()
+        .map(|(octave, accuracy)| bar(format!("oct {octave}"), accuracy))
+        .collect();
+    BarChart::default()
+        .block(Block::default().title("Per octave").borders(Borders::ALL))
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(6)
+        .bar_gap(1)
+        .max(100)
+        .bar_style(Style::default().fg(Color::Cyan))
+        .render(area, buf);
+}
+
+fn render_intervals(area: Rect, buf: &mut Buffer, report: &StatisticsReport) {
+    let bars: Vec<Bar> = report
+        .intervals
+        .iter()
+        .map(|(offset, accuracy)| bar(format!("{offset:+}"), accuracy))
+        .collect();
+    BarChart::default()
+        .block(
+            Block::default()
+                .title("Per interval (semitones from the reference)")
+                .borders(Borders::ALL),
+        )
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(3)
+        .bar_gap(1)
+        .max(100)
+        .bar_style(Style::default().fg(Color::Cyan))
+        .render(area, buf);
+}